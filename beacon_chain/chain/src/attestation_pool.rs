@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+use types::{AttestationRecord, Bitfield, Hash256};
+
+#[derive(Debug, PartialEq)]
+pub enum AttestationPoolError {
+    NoSigners,
+    AlreadyKnown,
+}
+
+/// The fields of an `AttestationRecord` which determine the message signed by its attesters.
+///
+/// These are the inputs to `generate_signed_message`, where the parent hashes are represented by
+/// the attestation slot and oblique parent hashes. The justified block hash is included so that
+/// the attestations under a key may be merged into a single valid record.
+///
+/// Keys are ordered by slot, then shard, then shard block hash (and then the remaining fields).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct AttestationKey {
+    slot: u64,
+    shard_id: u16,
+    shard_block_hash: Hash256,
+    oblique_parent_hashes: Vec<Hash256>,
+    justified_slot: u64,
+    justified_block_hash: Hash256,
+}
+
+impl<'a> From<&'a AttestationRecord> for AttestationKey {
+    fn from(a: &'a AttestationRecord) -> Self {
+        Self {
+            slot: a.slot,
+            shard_id: a.shard_id,
+            shard_block_hash: a.shard_block_hash,
+            oblique_parent_hashes: a.oblique_parent_hashes.clone(),
+            justified_slot: a.justified_slot,
+            justified_block_hash: a.justified_block_hash,
+        }
+    }
+}
+
+/// A collection of attestations which are waiting to be included in a block.
+///
+/// Attestations which sign the same message are aggregated into a single record where their
+/// signers do not overlap.
+pub struct AttestationPool {
+    cycle_length: u8,
+    attestations: BTreeMap<AttestationKey, Vec<AttestationRecord>>,
+}
+
+impl AttestationPool {
+    pub fn new(cycle_length: u8) -> Self {
+        Self {
+            cycle_length,
+            attestations: BTreeMap::new(),
+        }
+    }
+
+    /// Add an attestation to the pool, merging it with a known attestation for the same message
+    /// if their signers do not overlap.
+    ///
+    /// Returns an error if the attestation has no signers, or if all of its signers are already
+    /// included in a single known attestation for the same message.
+    ///
+    /// Note: the signature of the attestation is not verified.
+    pub fn insert(&mut self, attestation: AttestationRecord) -> Result<(), AttestationPoolError> {
+        if attestation.attester_bitfield.num_set_bits() == 0 {
+            return Err(AttestationPoolError::NoSigners);
+        }
+
+        let records = self
+            .attestations
+            .entry(AttestationKey::from(&attestation))
+            .or_insert_with(Vec::new);
+
+        if records
+            .iter()
+            .any(|r| is_subset(&attestation.attester_bitfield, &r.attester_bitfield))
+        {
+            return Err(AttestationPoolError::AlreadyKnown);
+        }
+
+        /*
+         * Aggregate the attestation into the first record which does not share a signer, otherwise
+         * store it as a new record.
+         */
+        match records.iter_mut().find(|r| {
+            r.attester_bitfield.len() == attestation.attester_bitfield.len()
+                && is_disjoint(&attestation.attester_bitfield, &r.attester_bitfield)
+        }) {
+            Some(record) => {
+                for i in 0..attestation.attester_bitfield.len() {
+                    if attestation.attester_bitfield.get(i).unwrap_or(false) {
+                        record.attester_bitfield.set(i, true);
+                    }
+                }
+                record
+                    .aggregate_sig
+                    .add_aggregate(&attestation.aggregate_sig);
+            }
+            None => records.push(attestation),
+        }
+
+        Ok(())
+    }
+
+    /// Remove all attestations which are too old to be included in a child of a block at
+    /// `parent_block_slot`.
+    ///
+    /// An attestation may be at most `cycle_length + 1` slots prior to the parent block.
+    pub fn prune(&mut self, parent_block_slot: u64) {
+        let oldest_slot =
+            parent_block_slot.saturating_sub(u64::from(self.cycle_length).saturating_add(1));
+        self.attestations.retain(|key, _| key.slot >= oldest_slot);
+    }
+
+    /// Remove the attestations which have been included in the canonical chain, so that they are
+    /// not included again.
+    ///
+    /// A record is removed if all of its signers are included in one of the `attestations` for the
+    /// same message.
+    pub fn remove_included(&mut self, attestations: &[AttestationRecord]) {
+        for attestation in attestations {
+            let key = AttestationKey::from(attestation);
+            let is_empty = match self.attestations.get_mut(&key) {
+                Some(records) => {
+                    records.retain(|r| {
+                        !is_subset(&r.attester_bitfield, &attestation.attester_bitfield)
+                    });
+                    records.is_empty()
+                }
+                None => false,
+            };
+            if is_empty {
+                self.attestations.remove(&key);
+            }
+        }
+    }
+
+    /// Iterate over all attestations in the pool, ordered by slot, then shard, then shard block
+    /// hash.
+    ///
+    /// The order is independent of the order in which the attestations were inserted, so that a
+    /// block produced from the pool is deterministic.
+    pub fn iter(&self) -> impl Iterator<Item = &AttestationRecord> {
+        self.attestations
+            .values()
+            .flat_map(|records| records.iter())
+    }
+
+    /// Returns the number of attestation records in the pool.
+    pub fn len(&self) -> usize {
+        self.attestations
+            .values()
+            .map(|records| records.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns `true` if every bit set in `a` is also set in `b`.
+fn is_subset(a: &Bitfield, b: &Bitfield) -> bool {
+    (0..a.len()).all(|i| !a.get(i).unwrap_or(false) || b.get(i).unwrap_or(false))
+}
+
+/// Returns `true` if no bit is set in both `a` and `b`.
+fn is_disjoint(a: &Bitfield, b: &Bitfield) -> bool {
+    (0..a.len()).all(|i| !a.get(i).unwrap_or(false) || !b.get(i).unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bls;
+
+    use self::bls::{AggregatePublicKey, AggregateSignature, Keypair, Signature};
+    use super::*;
+
+    const MESSAGE: &[u8] = b"attestation_message";
+
+    /// Generate an attestation at `slot` signed by each of the `signers`, where each signer is an
+    /// index into `keypairs`.
+    fn attestation(slot: u64, keypairs: &[Keypair], signers: &[usize]) -> AttestationRecord {
+        let mut a = AttestationRecord::zero();
+        a.slot = slot;
+        a.attester_bitfield = Bitfield::from_elem(keypairs.len(), false);
+        a.aggregate_sig = AggregateSignature::new();
+        for i in signers {
+            a.attester_bitfield.set(*i, true);
+            a.aggregate_sig
+                .add(&Signature::new(MESSAGE, &keypairs[*i].sk));
+        }
+        a
+    }
+
+    fn keypairs(n: usize) -> Vec<Keypair> {
+        (0..n).map(|_| Keypair::random()).collect()
+    }
+
+    #[test]
+    fn test_attestation_pool_merges_disjoint_signers() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        pool.insert(attestation(1, &keypairs, &[0])).unwrap();
+        pool.insert(attestation(1, &keypairs, &[2, 3])).unwrap();
+
+        assert_eq!(pool.len(), 1);
+        let merged = pool.iter().next().unwrap();
+        assert_eq!(merged.attester_bitfield.num_set_bits(), 3);
+        assert!(!merged.attester_bitfield.get(1).unwrap());
+
+        let mut aggregate_pubkey = AggregatePublicKey::new();
+        for i in &[0, 2, 3] {
+            aggregate_pubkey.add(&keypairs[*i].pk);
+        }
+        assert!(merged.aggregate_sig.verify(MESSAGE, &aggregate_pubkey));
+    }
+
+    #[test]
+    fn test_attestation_pool_rejects_covered() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        pool.insert(attestation(1, &keypairs, &[0, 1])).unwrap();
+
+        assert_eq!(
+            pool.insert(attestation(1, &keypairs, &[1])),
+            Err(AttestationPoolError::AlreadyKnown)
+        );
+        assert_eq!(
+            pool.insert(attestation(1, &keypairs, &[])),
+            Err(AttestationPoolError::NoSigners)
+        );
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_attestation_pool_keeps_overlapping_signers_separate() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        pool.insert(attestation(1, &keypairs, &[0, 1])).unwrap();
+        pool.insert(attestation(1, &keypairs, &[1, 2])).unwrap();
+
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_attestation_pool_keys_by_message() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        pool.insert(attestation(1, &keypairs, &[0])).unwrap();
+        pool.insert(attestation(2, &keypairs, &[0])).unwrap();
+
+        let mut other_shard = attestation(1, &keypairs, &[1]);
+        other_shard.shard_id = 1;
+        pool.insert(other_shard).unwrap();
+
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn test_attestation_pool_prune() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        for slot in 0..10 {
+            pool.insert(attestation(slot, &keypairs, &[0])).unwrap();
+        }
+
+        /*
+         * Attestations more than `cycle_length + 1` slots prior to slot 9 are removed.
+         */
+        pool.prune(9);
+
+        let slots: Vec<u64> = pool.iter().map(|a| a.slot).collect();
+        assert_eq!(slots, vec![4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_attestation_pool_remove_included() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        pool.insert(attestation(1, &keypairs, &[0, 1])).unwrap();
+        pool.insert(attestation(1, &keypairs, &[1, 2])).unwrap();
+        pool.insert(attestation(2, &keypairs, &[0])).unwrap();
+
+        /*
+         * Only the records whose signers are all included are removed.
+         */
+        pool.remove_included(&[attestation(1, &keypairs, &[0, 1, 3])]);
+        assert_eq!(pool.len(), 2);
+
+        pool.remove_included(&[
+            attestation(1, &keypairs, &[1, 2]),
+            attestation(2, &keypairs, &[0]),
+        ]);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_attestation_pool_iter_order() {
+        let keypairs = keypairs(4);
+        let mut pool = AttestationPool::new(4);
+
+        for &(slot, shard_id, hash) in &[(2, 0, 1), (1, 1, 0), (1, 0, 2), (1, 0, 1), (0, 3, 0)] {
+            let mut a = attestation(slot, &keypairs, &[0]);
+            a.shard_id = shard_id;
+            a.shard_block_hash = Hash256::from(hash as u64);
+            pool.insert(a).unwrap();
+        }
+
+        let order: Vec<(u64, u16, Hash256)> = pool
+            .iter()
+            .map(|a| (a.slot, a.shard_id, a.shard_block_hash))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (0, 3, Hash256::from(0u64)),
+                (1, 0, Hash256::from(1u64)),
+                (1, 0, Hash256::from(2u64)),
+                (1, 1, Hash256::from(0u64)),
+                (2, 0, Hash256::from(1u64)),
+            ]
+        );
+    }
+}
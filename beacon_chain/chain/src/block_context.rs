@@ -1,19 +1,22 @@
+use super::maps::{generate_attester_and_proposer_maps, AttesterAndProposerMapError};
 use super::BeaconChain;
 use db::stores::BeaconBlockAtSlotError;
 use db::ClientDB;
+use fork_choice::ForkChoice;
 use ssz_helpers::ssz_beacon_block::SszBeaconBlock;
 use std::sync::Arc;
-use types::Hash256;
+use types::{BeaconState, Hash256};
 use validation::block_validation::BeaconBlockValidationContext;
 
+#[derive(Debug, PartialEq)]
 pub enum BlockValidationContextError {
-    UnknownCrystallizedState,
-    UnknownActiveState,
+    UnknownState,
     UnknownAttesterProposerMaps,
     NoParentHash,
     UnknownJustifiedBlock,
     BlockAlreadyKnown,
     BlockSlotLookupError(BeaconBlockAtSlotError),
+    MapGenerationFailed(AttesterAndProposerMapError),
 }
 
 impl From<BeaconBlockAtSlotError> for BlockValidationContextError {
@@ -22,43 +25,44 @@ impl From<BeaconBlockAtSlotError> for BlockValidationContextError {
     }
 }
 
-impl<T> BeaconChain<T>
+impl From<AttesterAndProposerMapError> for BlockValidationContextError {
+    fn from(e: AttesterAndProposerMapError) -> BlockValidationContextError {
+        BlockValidationContextError::MapGenerationFailed(e)
+    }
+}
+
+impl<T, F> BeaconChain<T, F>
 where
     T: ClientDB + Sized,
+    F: ForkChoice,
 {
+    /// Returns the context in which the `block` is validated.
+    ///
+    /// The `pre_block_state` is the state to which the block will be applied (see
+    /// `pre_block_state`), from which the proposer of the block is determined.
     pub(crate) fn block_validation_context(
         &self,
         block: &SszBeaconBlock,
         parent_block: &SszBeaconBlock,
+        pre_block_state: Arc<BeaconState>,
         present_slot: u64,
     ) -> Result<BeaconBlockValidationContext<T>, BlockValidationContextError> {
         /*
-         * Load the crystallized state for this block from our caches.
-         *
-         * Fail if the crystallized state is unknown.
-         */
-        let cry_state_root = Hash256::from(parent_block.cry_state_root());
-        let cry_state = self
-            .crystallized_states
-            .get(&cry_state_root)
-            .ok_or(BlockValidationContextError::UnknownCrystallizedState)?;
-
-        /*
-         * Load the active state for this block from our caches.
+         * Load the state of the parent block from our caches.
          *
-         * Fail if the active state is unknown.
+         * Fail if the state is unknown.
          */
-        let act_state_root = Hash256::from(parent_block.act_state_root());
-        let act_state = self
-            .active_states
-            .get(&act_state_root)
-            .ok_or(BlockValidationContextError::UnknownActiveState)?;
+        let state_root = Hash256::from(parent_block.state_root());
+        let parent_state = self
+            .states
+            .get(&state_root)
+            .ok_or(BlockValidationContextError::UnknownState)?;
 
         /*
-         * Learn the last justified slot from the crystallized state and load
-         * the hash of this block from the database
+         * Learn the last justified slot from the state and load the hash of this block from the
+         * database
          */
-        let last_justified_slot = cry_state.last_justified_slot;
+        let last_justified_slot = parent_state.last_justified_slot;
         let parent_block_hash = block
             .parent_hash()
             .ok_or(BlockValidationContextError::NoParentHash)?;
@@ -69,21 +73,38 @@ where
             .ok_or(BlockValidationContextError::UnknownJustifiedBlock)?;
 
         /*
-         * Load the attester and proposer maps for the crystallized state.
+         * Load the attester and proposer maps for the state.
          */
         let (attester_map, proposer_map) = self
             .attester_proposer_maps
-            .get(&cry_state_root)
+            .get(&state_root)
             .ok_or(BlockValidationContextError::UnknownAttesterProposerMaps)?;
 
+        /*
+         * If the block follows a cycle transition, the proposer map of the parent state may not
+         * cover the slot of the block, so generate the proposer map of the state to which the
+         * block will be applied.
+         */
+        let block_proposer_map = if Arc::ptr_eq(&pre_block_state, parent_state) {
+            proposer_map.clone()
+        } else {
+            let (_, block_proposer_map) = generate_attester_and_proposer_maps(
+                &pre_block_state.shard_and_committee_for_slots,
+                pre_block_state.last_state_recalculation_slot,
+            )?;
+            Arc::new(block_proposer_map)
+        };
+
         Ok(BeaconBlockValidationContext {
             present_slot,
             cycle_length: self.config.cycle_length,
-            last_justified_slot: cry_state.last_justified_slot,
+            parent_state: parent_state.clone(),
+            pre_block_state,
             last_justified_block_hash: Hash256::from(&last_justified_block_hash[..]),
             last_finalized_slot: self.last_finalized_slot,
-            recent_block_hashes: Arc::new(act_state.recent_block_hashes.clone()),
+            last_finalized_block_hash: self.last_finalized_block_hash,
             proposer_map: proposer_map.clone(),
+            block_proposer_map,
             attester_map: attester_map.clone(),
             block_store: self.store.block.clone(),
             validator_store: self.store.validator.clone(),
@@ -1,40 +1,301 @@
 use super::block_context::BlockValidationContextError;
+use super::events::BeaconChainEvent;
+use super::future_blocks::FutureBlockQueueError;
+use super::head::Reorg;
+use super::maps::AttesterAndProposerMapError;
+use super::orphan_blocks::OrphanBlockPoolError;
 use super::state_transition::StateTransitionError;
+use super::states::StateStorageError;
 use super::BeaconChain;
+use db::stores::BeaconBlockAtSlotError;
 use db::{ClientDB, DBError};
-use naive_fork_choice::{naive_fork_choice, ForkChoiceError};
+use fork_choice::{ForkChoice, ForkChoiceError};
 use ssz_helpers::ssz_beacon_block::{SszBeaconBlock, SszBeaconBlockError};
-use types::Hash256;
+use std::sync::mpsc::{channel, Receiver};
+use types::{AttestationRecord, Hash256};
 use validation::block_validation::SszBeaconBlockValidationError;
 
+#[derive(Debug, PartialEq)]
 pub enum BlockProcessingOutcome {
     BlockAlreadyKnown,
     NewCanonicalBlock,
-    NewReorgBlock,
+    /// The block caused the canonical head to switch to another chain.
+    NewReorgBlock(Reorg),
     NewForkBlock,
+    /// The block is from a future slot, it has been queued to be processed once the present slot
+    /// reaches it.
+    FutureBlockQueued,
+    /// The parent of the block is unknown, it has been pooled to be processed once its parent
+    /// is imported.
+    OrphanBlockQueued,
 }
 
+#[derive(Debug, PartialEq)]
 pub enum BlockProcessingError {
     ParentBlockNotFound,
-    ActiveStateRootInvalid,
-    CrystallizedStateRootInvalid,
+    StateRootInvalid,
     NoHeadHashes,
     ForkChoiceFailed(ForkChoiceError),
     ContextGenerationFailed(BlockValidationContextError),
     DeserializationFailed(SszBeaconBlockError),
     ValidationFailed(SszBeaconBlockValidationError),
     StateTransitionFailed(StateTransitionError),
+    MapGenerationFailed(AttesterAndProposerMapError),
+    FutureBlockRejected(FutureBlockQueueError),
+    OrphanBlockRejected(OrphanBlockPoolError),
+    HeadLookupFailed(BeaconBlockAtSlotError),
     DBError(String),
 }
 
-impl<T> BeaconChain<T>
+/// The changes to the canonical chain caused by importing a block, which are resolved before the
+/// chain is modified.
+struct HeadChange {
+    /// The reorg, if the canonical head switched to another chain.
+    reorg: Option<Reorg>,
+    /// The attestations of the blocks removed from the canonical chain.
+    removed_attestations: Vec<AttestationRecord>,
+    /// The attestations of the blocks added to the canonical chain.
+    added_attestations: Vec<AttestationRecord>,
+    /// The last finalized slot of the chain.
+    last_finalized_slot: u64,
+    /// The latest block at or before the `last_finalized_slot` in the new canonical chain.
+    last_finalized_block_hash: Hash256,
+}
+
+impl<T, F> BeaconChain<T, F>
 where
     T: ClientDB + Sized,
+    F: ForkChoice,
 {
+    /// Process a serialized block, adding it to the block tree if it is valid.
+    ///
+    /// Any queued future blocks which are no longer in the future are processed first, so that
+    /// the block may build upon them, and a `FutureBlockRejected` event is published for each
+    /// which fails. A block from a future slot is queued until the present slot reaches it, once
+    /// its proposer has been verified.
+    ///
+    /// A block with an unknown parent is pooled until its parent is imported, and the parent is
+    /// announced to any subscriber to parent requests. Once a block is imported, any pooled
+    /// descendants are imported after it.
     pub fn process_block(
         &mut self,
         ssz: &[u8],
         present_slot: u64,
+    ) -> Result<(BlockProcessingOutcome, Hash256), BlockProcessingError> {
+        /*
+         * The results of the queued blocks are not relevant to this block, those which fail are
+         * announced by `process_future_blocks`. An invalid queued block is discarded, as it would
+         * have been had it not been from the future.
+         */
+        self.process_future_blocks(present_slot);
+        let result = self.process_block_and_orphans(ssz, present_slot);
+
+        self.orphan_blocks.prune(self.last_finalized_slot);
+
+        result
+    }
+
+    /// Process all queued future blocks with a slot no higher than the `present_slot`, in order of
+    /// slot, returning the result of processing each.
+    ///
+    /// A `FutureBlockRejected` event is published for each block which fails to be processed.
+    pub fn process_future_blocks(
+        &mut self,
+        present_slot: u64,
+    ) -> Vec<Result<(BlockProcessingOutcome, Hash256), BlockProcessingError>> {
+        let ready = self.future_blocks.drain_ready(present_slot);
+        let mut results = Vec::with_capacity(ready.len());
+        for &(block_hash, ref ssz) in &ready {
+            let result = self.process_block_and_orphans(ssz, present_slot);
+            if result.is_err() {
+                self.events
+                    .publish(&BeaconChainEvent::FutureBlockRejected { block_hash });
+            }
+            results.push(result);
+        }
+        results
+    }
+
+    /// Returns a channel on which the hash of each unknown parent of a pooled orphan block is
+    /// sent, so that the parent may be requested from the network.
+    ///
+    /// Only the most recent subscriber receives parent requests.
+    pub fn subscribe_parent_requests(&mut self) -> Receiver<Hash256> {
+        let (sender, receiver) = channel();
+        self.parent_requests = Some(sender);
+        receiver
+    }
+
+    /// Process a block, then process any pooled orphan blocks which descend from it.
+    ///
+    /// The results of the orphan blocks are discarded, an invalid orphan block is dropped.
+    fn process_block_and_orphans(
+        &mut self,
+        ssz: &[u8],
+        present_slot: u64,
+    ) -> Result<(BlockProcessingOutcome, Hash256), BlockProcessingError> {
+        let result = self.process_new_block(ssz, present_slot);
+
+        if let Ok((outcome, block_hash)) = &result {
+            if is_imported(outcome) {
+                let mut parents = vec![*block_hash];
+                while let Some(parent_hash) = parents.pop() {
+                    for ssz in self.orphan_blocks.take_children(&parent_hash) {
+                        if let Ok((outcome, block_hash)) =
+                            self.process_new_block(&ssz, present_slot)
+                        {
+                            if is_imported(&outcome) {
+                                parents.push(block_hash);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Add a block whose parent is unknown to the orphan pool, requesting the parent if it is
+    /// not already expected.
+    fn pool_orphan_block(
+        &mut self,
+        ssz: &[u8],
+        ssz_block: &SszBeaconBlock,
+        parent_hash: &Hash256,
+        present_slot: u64,
+    ) -> Result<(BlockProcessingOutcome, Hash256), BlockProcessingError> {
+        let block_hash = Hash256::from(&ssz_block.block_hash()[..]);
+
+        /*
+         * A block at a finalized slot can never be imported.
+         */
+        if ssz_block.slot() <= self.last_finalized_slot {
+            return Err(BlockProcessingError::ParentBlockNotFound);
+        }
+
+        match self.orphan_blocks.insert(
+            *parent_hash,
+            block_hash,
+            ssz_block.slot(),
+            present_slot,
+            ssz.to_vec(),
+        ) {
+            Ok(newly_missing) => {
+                /*
+                 * The parent only needs to be requested once, and not at all if it is already
+                 * waiting in the orphan pool or the future block queue.
+                 */
+                if newly_missing
+                    && !self.orphan_blocks.contains(parent_hash)
+                    && !self.future_blocks.contains(parent_hash)
+                {
+                    self.request_block(parent_hash);
+                }
+            }
+            Err(OrphanBlockPoolError::AlreadyKnown) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok((BlockProcessingOutcome::OrphanBlockQueued, block_hash))
+    }
+
+    /// Resolve the changes to the canonical chain caused by a switch of the canonical head from
+    /// `old_head` to `new_head`, where `switched_chain` is `true` if the new head is not a
+    /// descendant of the old head and `last_finalized_slot` is the last finalized slot once the
+    /// head has switched.
+    fn resolve_head_change(
+        &self,
+        old_head: &Hash256,
+        new_head: &Hash256,
+        switched_chain: bool,
+        last_finalized_slot: u64,
+    ) -> Result<HeadChange, BeaconBlockAtSlotError> {
+        /*
+         * Without a switch of chains, the only block which may be added to the canonical chain is
+         * the new head.
+         */
+        let (reorg, removed_attestations, added_attestations) = if switched_chain {
+            let reorg = self.find_reorg(old_head, new_head)?;
+            let removed_attestations = self.removed_attestations(&reorg)?;
+            let added_attestations = self.block_attestations(&reorg.added)?;
+            (Some(reorg), removed_attestations, added_attestations)
+        } else if new_head != old_head {
+            (None, vec![], self.block_attestations(&[*new_head])?)
+        } else {
+            (None, vec![], vec![])
+        };
+
+        let last_finalized_block_hash = if last_finalized_slot > self.last_finalized_slot {
+            self.latest_block_at_or_before(new_head, last_finalized_slot)?
+        } else {
+            self.last_finalized_block_hash
+        };
+
+        Ok(HeadChange {
+            reorg,
+            removed_attestations,
+            added_attestations,
+            last_finalized_slot,
+            last_finalized_block_hash,
+        })
+    }
+
+    /// Forget a block which was stored and added to the fork choice rule but could not be
+    /// imported, removing it from the fork choice rule and removing the block and its state from
+    /// storage.
+    fn forget_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+        state_root: &Hash256,
+    ) -> Result<(), BlockProcessingError> {
+        self.fork_choice.remove_block(block_hash, parent_hash)?;
+        self.remove_state(state_root)?;
+        self.store.block.delete_block(&block_hash[..])?;
+        Ok(())
+    }
+
+    /// Announce a change of the canonical head from `old_head` to `new_head`, along with any
+    /// increase in the last justified slot.
+    ///
+    /// The block has already been imported, so publishing cannot fail. If the state of either
+    /// head cannot be read, the increase in the last justified slot is unknown and no `Justified`
+    /// event is published.
+    fn publish_head_change(&mut self, old_head: &Hash256, new_head: &Hash256, reorg_depth: u64) {
+        self.events.publish(&BeaconChainEvent::HeadChanged {
+            old: *old_head,
+            new: *new_head,
+            reorg_depth,
+        });
+
+        if let (Ok(Some(old_state)), Ok(Some(new_state))) =
+            (self.block_state(old_head), self.block_state(new_head))
+        {
+            if new_state.last_justified_slot > old_state.last_justified_slot {
+                self.events.publish(&BeaconChainEvent::Justified {
+                    slot: new_state.last_justified_slot,
+                });
+            }
+        }
+    }
+
+    /// Announce that the block at `block_hash` is required, if there is a subscriber to parent
+    /// requests.
+    fn request_block(&mut self, block_hash: &Hash256) {
+        let disconnected = match &self.parent_requests {
+            Some(sender) => sender.send(*block_hash).is_err(),
+            None => false,
+        };
+        if disconnected {
+            self.parent_requests = None;
+        }
+    }
+
+    fn process_new_block(
+        &mut self,
+        ssz: &[u8],
+        present_slot: u64,
     ) -> Result<(BlockProcessingOutcome, Hash256), BlockProcessingError> {
         /*
          * Generate a SszBlock to read directly from the serialized SSZ.
@@ -61,90 +322,118 @@ where
 
         /*
          * Load the parent block from the database and create an SszBeaconBlock for reading it.
+         *
+         * If the parent is unknown, the block is pooled until the parent is imported.
          */
-        let parent_block_ssz_bytes = self
-            .store
-            .block
-            .get_serialized_block(&parent_hash[..])?
-            .ok_or(BlockProcessingError::ParentBlockNotFound)?;
+        let parent_block_ssz_bytes =
+            match self.store.block.get_serialized_block(&parent_hash[..])? {
+                Some(bytes) => bytes,
+                None => {
+                    return self.pool_orphan_block(
+                        ssz,
+                        &ssz_block,
+                        &Hash256::from(parent_hash),
+                        present_slot,
+                    )
+                }
+            };
         let parent_ssz_block = SszBeaconBlock::from_slice(&parent_block_ssz_bytes)?;
 
         /*
-         * Generate the context in which to validate this block.
+         * Ensure the state of the parent block is held in memory, loading it from the database if
+         * required.
          */
-        let validation_context =
-            self.block_validation_context(&ssz_block, &parent_ssz_block, present_slot)?;
+        let parent_state_root = Hash256::from(parent_ssz_block.state_root());
+        self.load_state(&parent_state_root)?;
+        let parent_state = self
+            .states
+            .get(&parent_state_root)
+            .cloned()
+            .ok_or(BlockValidationContextError::UnknownState)?;
 
         /*
-         * Validate the block against the context, checking signatures, parent_hashes, etc.
+         * Reject a block which is too far in the future to be queued before the state of its
+         * parent is transitioned to the slot of the block, as the transition is performed once for
+         * each cycle between the two.
          */
-        let block = validation_context.validate_ssz_block(&ssz_block)?;
+        if self
+            .future_blocks
+            .is_too_far_ahead(ssz_block.slot(), present_slot)
+        {
+            return Err(FutureBlockQueueError::TooFarAhead.into());
+        }
 
-        let (new_act_state, new_cry_state_option) = {
-            /*
-             * Load the states from memory.
-             *
-             * Note: this is the second time we load these, the first was in
-             * `block_validation_context`. Theres an opportunity for some opimisation here.
-             * It was left out because it made the code more cumbersome.
-             */
-            let act_state = self
-                .active_states
-                .get(&block.active_state_root)
-                .ok_or(BlockValidationContextError::UnknownActiveState)?;
-            let cry_state = self
-                .crystallized_states
-                .get(&block.crystallized_state_root)
-                .ok_or(BlockValidationContextError::UnknownCrystallizedState)?;
-
-            self.transition_states(act_state, cry_state, &block, &block_hash)?
+        /*
+         * Determine the state to which the block will be applied, performing a cycle transition
+         * of the parent state if one is due at the slot of the block. The proposer of the block is
+         * determined from this state.
+         *
+         * A block which is not after its parent is rejected during validation, so the parent state
+         * is not transitioned for it.
+         */
+        let (pre_block_state, validator_changes) = if ssz_block.slot() > parent_ssz_block.slot() {
+            self.pre_block_state(&parent_state, ssz_block.slot())?
+        } else {
+            (parent_state, vec![])
         };
 
         /*
-         * Calculate the new active state root and ensure the block state root matches.
+         * Generate the context in which to validate this block.
          */
-        let new_act_state_root = new_act_state.canonical_root();
-        if new_act_state_root != block.active_state_root {
-            return Err(BlockProcessingError::ActiveStateRootInvalid);
-        }
+        let validation_context = self.block_validation_context(
+            &ssz_block,
+            &parent_ssz_block,
+            pre_block_state,
+            present_slot,
+        )?;
 
         /*
-         * Determine the crystallized state root and ensure the block state root matches.
+         * Validate the block against the context, checking signatures, parent_hashes, etc.
          *
-         * If a new crystallized state was created, store it in memory.
+         * A block from a future slot is queued, to be replayed once the present slot reaches it.
+         * The validation only reports a block as being from a future slot once the randao reveal
+         * and the signature of the parent block proposer have been verified, so a block which is
+         * not from the proposer of its slot is rejected rather than queued.
          */
-        let (new_cry_state_root, cry_state_transitioned) = match new_cry_state_option {
-            None => {
-                /*
-                 * A new crystallized state was not created, therefore the
-                 * `crystallized_state_root` of this block must match its parent.
-                 */
-                if Hash256::from(parent_ssz_block.cry_state_root()) != block.crystallized_state_root
-                {
-                    return Err(BlockProcessingError::ActiveStateRootInvalid);
+        let block = match validation_context.validate_ssz_block(&ssz_block) {
+            Err(SszBeaconBlockValidationError::FutureSlot) => {
+                match self.future_blocks.insert(
+                    block_hash,
+                    ssz.to_vec(),
+                    ssz_block.slot(),
+                    present_slot,
+                ) {
+                    Ok(()) | Err(FutureBlockQueueError::AlreadyQueued) => {
+                        return Ok((BlockProcessingOutcome::FutureBlockQueued, block_hash))
+                    }
+                    Err(e) => return Err(e.into()),
                 }
-                // Return the old root
-                (block.crystallized_state_root, false)
-            }
-            Some(new_cry_state) => {
-                /*
-                 * A new crystallized state was created. Check to ensure the crystallized
-                 * state root in the block is the same as the calculated on this node.
-                 */
-                let cry_state_root = new_cry_state.canonical_root();
-                if cry_state_root != block.crystallized_state_root {
-                    return Err(BlockProcessingError::ActiveStateRootInvalid);
-                }
-                /*
-                 * Store the new crystallized state in memory.
-                 */
-                self.crystallized_states
-                    .insert(cry_state_root, new_cry_state);
-                // Return the new root
-                (cry_state_root, true)
             }
+            result => result?,
         };
 
+        /*
+         * Apply the block to the state of its parent, as transitioned to the slot of the block.
+         */
+        let new_state = self.apply_block(
+            &validation_context.pre_block_state,
+            &block,
+            &Hash256::from(parent_hash),
+        )?;
+
+        /*
+         * Calculate the new state root and ensure the block state root matches.
+         */
+        let new_state_root = new_state.canonical_root();
+        if new_state_root != block.state_root {
+            return Err(BlockProcessingError::StateRootInvalid);
+        }
+        let recalculated = new_state.last_state_recalculation_slot
+            != validation_context
+                .parent_state
+                .last_state_recalculation_slot;
+        let recalc_slot = new_state.last_state_recalculation_slot;
+
         /*
          * Store the new block as a leaf in the block tree.
          */
@@ -171,46 +460,170 @@ where
             .put_serialized_block(&block_hash[..], ssz_block.block_ssz())?;
 
         /*
-         * Store the active state in memory.
+         * Store the new state (and its attester and proposer maps), along with any validator set
+         * changes made by its cycle transition.
          */
-        self.active_states.insert(new_act_state_root, new_act_state);
+        self.insert_state(new_state_root, new_state)?;
+        if !validator_changes.is_empty() {
+            self.store
+                .state
+                .put_validator_changes(&new_state_root, &validator_changes)?;
+        }
 
-        let new_canonical_head_block_hash_index =
-            match naive_fork_choice(&self.head_block_hashes, self.store.block.clone())? {
-                None => {
-                    /*
-                     * Fork choice failed, therefore the block, active state and crystallized state
-                     * can be removed from storage (i.e., forgotten).
-                     */
-                    if cry_state_transitioned {
-                        // A new crystallized state was generated, so it should be deleted.
-                        self.crystallized_states.remove(&new_cry_state_root);
-                    }
-                    self.active_states.remove(&new_act_state_root);
-                    self.store.block.delete_block(&block_hash[..])?;
-                    return Err(BlockProcessingError::NoHeadHashes);
-                }
-                Some(i) => i,
-            };
+        /*
+         * Add the block and its attestations to the fork choice rule, then find the new head.
+         *
+         * Should fork choice fail, the block is removed from the fork choice rule and the block
+         * and state are removed from storage (i.e., forgotten), so the block may be processed
+         * again.
+         */
+        self.fork_choice
+            .add_block(&block_hash, &Hash256::from(parent_hash), block.slot)?;
+        let head_index = self
+            .register_attestations(&block, &Hash256::from(parent_hash), &parent_state_root)
+            .and_then(|_| {
+                self.find_head_index(&new_head_block_hashes, &block_hash, &new_state_root)
+            });
+        let new_canonical_head_block_hash_index = match head_index {
+            Ok(Some(i)) => i,
+            Ok(None) => {
+                self.forget_block(&block_hash, &Hash256::from(parent_hash), &new_state_root)?;
+                return Err(BlockProcessingError::NoHeadHashes);
+            }
+            Err(e) => {
+                self.forget_block(&block_hash, &Hash256::from(parent_hash), &new_state_root)?;
+                return Err(e.into());
+            }
+        };
+
+        let old_head = self.canonical_block_hash();
+        let new_head = new_head_block_hashes[new_canonical_head_block_hash_index];
+
+        /*
+         * If this block is the new canonical head, the last finalized slot follows its state.
+         */
+        let old_finalized_slot = self.last_finalized_slot;
+        let mut last_finalized_slot = old_finalized_slot;
+        if new_head == block_hash {
+            if let Some(state) = self.states.get(&new_state_root) {
+                last_finalized_slot = last_finalized_slot.max(state.last_finalized_slot);
+            }
+        }
 
-        if new_canonical_head_block_hash_index != self.canonical_head_block_hash {
+        /*
+         * Resolve the changes to the canonical chain before the chain is modified. Should this
+         * fail, the block is forgotten (as it would be had fork choice failed), so the block may
+         * be processed again rather than being stranded outside the block tree.
+         */
+        let switched_chain = new_canonical_head_block_hash_index != self.canonical_head_block_hash;
+        let head_change = match self.resolve_head_change(
+            &old_head,
+            &new_head,
+            switched_chain,
+            last_finalized_slot,
+        ) {
+            Ok(head_change) => head_change,
+            Err(e) => {
+                self.forget_block(&block_hash, &Hash256::from(parent_hash), &new_state_root)?;
+                return Err(e.into());
+            }
+        };
+
+        let outcome = match head_change.reorg {
             /*
              * The block caused a re-org (switch of chains).
              */
-            Ok((BlockProcessingOutcome::NewReorgBlock, block_hash))
-        } else {
+            Some(reorg) => BlockProcessingOutcome::NewReorgBlock(reorg),
             /*
              * The block did not cause a re-org.
              */
-            if new_parent_head_hash_index == self.canonical_head_block_hash {
-                Ok((BlockProcessingOutcome::NewCanonicalBlock, block_hash))
-            } else {
-                Ok((BlockProcessingOutcome::NewForkBlock, block_hash))
+            None => {
+                if new_parent_head_hash_index == self.canonical_head_block_hash {
+                    BlockProcessingOutcome::NewCanonicalBlock
+                } else {
+                    BlockProcessingOutcome::NewForkBlock
+                }
             }
+        };
+
+        /*
+         * Update the block tree heads and the last finalized slot and block.
+         */
+        self.head_block_hashes = new_head_block_hashes;
+        self.canonical_head_block_hash = new_canonical_head_block_hash_index;
+        self.last_finalized_slot = head_change.last_finalized_slot;
+        self.last_finalized_block_hash = head_change.last_finalized_block_hash;
+
+        /*
+         * Return the attestations of the blocks removed from the canonical chain to the pool, so
+         * that they may be included in the new canonical chain. An attestation which is already
+         * known to the pool is simply ignored.
+         */
+        for attestation in head_change.removed_attestations {
+            let _ = self.attestation_pool.insert(attestation);
+        }
+
+        /*
+         * Remove the attestations included in the blocks added to the canonical chain from the
+         * pool, so that they are not included again.
+         */
+        self.attestation_pool
+            .remove_included(&head_change.added_attestations);
+
+        /*
+         * Persist the chain metadata so the chain may be resumed.
+         */
+        self.persist_metadata()?;
+
+        /*
+         * Announce the changes to the chain to any subscribers.
+         */
+        if recalculated {
+            self.events.publish(&BeaconChainEvent::CycleTransition {
+                block_hash,
+                slot: recalc_slot,
+            });
+        }
+        self.events.publish(&BeaconChainEvent::BlockImported {
+            block_hash,
+            slot: block.slot,
+        });
+        if new_head != old_head {
+            let reorg_depth = match &outcome {
+                BlockProcessingOutcome::NewReorgBlock(reorg) => reorg.depth(),
+                _ => 0,
+            };
+            self.publish_head_change(&old_head, &new_head, reorg_depth);
+        }
+        if self.last_finalized_slot > old_finalized_slot {
+            self.events.publish(&BeaconChainEvent::Finalized {
+                slot: self.last_finalized_slot,
+            });
+        }
+
+        Ok((outcome, block_hash))
+    }
+}
+
+/// Returns `true` if the `outcome` indicates the block is in the block tree.
+fn is_imported(outcome: &BlockProcessingOutcome) -> bool {
+    match outcome {
+        BlockProcessingOutcome::BlockAlreadyKnown
+        | BlockProcessingOutcome::NewCanonicalBlock
+        | BlockProcessingOutcome::NewReorgBlock(_)
+        | BlockProcessingOutcome::NewForkBlock => true,
+        BlockProcessingOutcome::FutureBlockQueued | BlockProcessingOutcome::OrphanBlockQueued => {
+            false
         }
     }
 }
 
+impl From<BeaconBlockAtSlotError> for BlockProcessingError {
+    fn from(e: BeaconBlockAtSlotError) -> Self {
+        BlockProcessingError::HeadLookupFailed(e)
+    }
+}
+
 impl From<BlockValidationContextError> for BlockProcessingError {
     fn from(e: BlockValidationContextError) -> Self {
         BlockProcessingError::ContextGenerationFailed(e)
@@ -246,3 +659,35 @@ impl From<StateTransitionError> for BlockProcessingError {
         BlockProcessingError::StateTransitionFailed(e)
     }
 }
+
+impl From<FutureBlockQueueError> for BlockProcessingError {
+    fn from(e: FutureBlockQueueError) -> Self {
+        BlockProcessingError::FutureBlockRejected(e)
+    }
+}
+
+impl From<OrphanBlockPoolError> for BlockProcessingError {
+    fn from(e: OrphanBlockPoolError) -> Self {
+        BlockProcessingError::OrphanBlockRejected(e)
+    }
+}
+
+impl From<AttesterAndProposerMapError> for BlockProcessingError {
+    fn from(e: AttesterAndProposerMapError) -> Self {
+        BlockProcessingError::MapGenerationFailed(e)
+    }
+}
+
+impl From<StateStorageError> for BlockProcessingError {
+    fn from(e: StateStorageError) -> Self {
+        match e {
+            StateStorageError::DBError(s) => BlockProcessingError::DBError(s),
+            StateStorageError::DecodeError => {
+                BlockProcessingError::DBError("Unable to decode state from database.".to_string())
+            }
+            StateStorageError::MapGenerationFailed(e) => {
+                BlockProcessingError::MapGenerationFailed(e)
+            }
+        }
+    }
+}
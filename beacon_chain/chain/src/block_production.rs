@@ -0,0 +1,818 @@
+use super::block_context::BlockValidationContextError;
+use super::maps::AttesterAndProposerMapError;
+use super::state_transition::StateTransitionError;
+use super::states::StateStorageError;
+use super::BeaconChain;
+use db::{ClientDB, DBError};
+use fork_choice::ForkChoice;
+use ssz::Decodable;
+use ssz_helpers::ssz_beacon_block::{SszBeaconBlock, SszBeaconBlockError};
+use std::sync::Arc;
+use types::beacon_block::child_ancestor_hashes;
+use types::{BeaconBlock, Hash256};
+use validation::attestation_validation::AttestationValidationContext;
+
+#[derive(Debug, PartialEq)]
+pub enum BlockProductionError {
+    SlotNotAfterParent,
+    ParentBlockNotFound,
+    BadAncestorHashesSsz,
+    BadProposerMap,
+    NoProposerAttestation,
+    ContextGenerationFailed(BlockValidationContextError),
+    DeserializationFailed(SszBeaconBlockError),
+    StateTransitionFailed(StateTransitionError),
+    MapGenerationFailed(AttesterAndProposerMapError),
+    DBError(String),
+}
+
+impl<T, F> BeaconChain<T, F>
+where
+    T: ClientDB + Sized,
+    F: ForkChoice,
+{
+    /// Produce a new block at the given `slot` upon the canonical head.
+    ///
+    /// The first attestation of the block is an attestation from the `attestation_pool` which
+    /// includes the signature of the parent block proposer, the remainder are all other valid
+    /// attestations from the pool. The state root of the block is that of the state which results
+    /// from applying the block to the state of the canonical head.
+    ///
+    /// The block is not stored or applied to the chain, it should be passed to `process_block`
+    /// once it has been broadcast.
+    pub fn produce_block(
+        &mut self,
+        slot: u64,
+        randao_reveal: &Hash256,
+    ) -> Result<BeaconBlock, BlockProductionError> {
+        /*
+         * Load the canonical head from the database, it will be the parent of the new block.
+         */
+        let parent_hash = self.canonical_block_hash();
+        let parent_block_ssz_bytes = self
+            .store
+            .block
+            .get_serialized_block(&parent_hash[..])?
+            .ok_or(BlockProductionError::ParentBlockNotFound)?;
+        let parent_ssz_block = SszBeaconBlock::from_slice(&parent_block_ssz_bytes)?;
+        let parent_block_slot = parent_ssz_block.slot();
+
+        if slot <= parent_block_slot {
+            return Err(BlockProductionError::SlotNotAfterParent);
+        }
+
+        /*
+         * Remove any attestations from the pool which are too old to be included in this block.
+         */
+        self.attestation_pool.prune(parent_block_slot);
+
+        /*
+         * Ensure the state of the parent block is held in memory, then load it.
+         */
+        let state_root = Hash256::from(parent_ssz_block.state_root());
+        self.load_state(&state_root)?;
+        let state = self
+            .states
+            .get(&state_root)
+            .ok_or(BlockValidationContextError::UnknownState)?;
+        let (attester_map, proposer_map) = self
+            .attester_proposer_maps
+            .get(&state_root)
+            .ok_or(BlockValidationContextError::UnknownAttesterProposerMaps)?;
+
+        let (parent_ancestor_hashes, _): (Vec<Hash256>, usize) =
+            Decodable::ssz_decode(parent_ssz_block.ancestor_hashes(), 0)
+                .map_err(|_| BlockProductionError::BadAncestorHashesSsz)?;
+        let ancestor_hashes =
+            child_ancestor_hashes(&parent_hash, parent_block_slot, &parent_ancestor_hashes);
+
+        /*
+         * Select the attestations from the pool which are valid for inclusion in this block.
+         *
+         * The first of these which includes a signature from the parent block proposer (and has
+         * no oblique parent hashes) is placed at the start of the block attestations.
+         */
+        let attestation_validation_context = AttestationValidationContext {
+            block_slot: slot,
+            parent_block_slot,
+            cycle_length: self.config.cycle_length,
+            last_justified_slot: state.last_justified_slot,
+            recent_block_hashes: Arc::new(state.recent_block_hashes.clone()),
+            block_store: self.store.block.clone(),
+            validator_store: self.store.validator.clone(),
+            attester_map: attester_map.clone(),
+            fork_data: state.fork_data(),
+        };
+        let parent_block_proposer = proposer_map
+            .get(&parent_block_slot)
+            .ok_or(BlockProductionError::BadProposerMap)?;
+
+        let mut proposer_attestation = None;
+        let mut attestations = vec![];
+        for attestation in self.attestation_pool.iter() {
+            let voters = match attestation_validation_context.validate_attestation(attestation) {
+                Ok(voters) => voters,
+                Err(_) => continue,
+            };
+            if proposer_attestation.is_none()
+                && attestation.oblique_parent_hashes.is_empty()
+                && voters.contains(parent_block_proposer)
+            {
+                proposer_attestation = Some(attestation.clone());
+            } else {
+                attestations.push(attestation.clone());
+            }
+        }
+        let proposer_attestation =
+            proposer_attestation.ok_or(BlockProductionError::NoProposerAttestation)?;
+        attestations.insert(0, proposer_attestation);
+
+        let mut block = BeaconBlock {
+            slot,
+            randao_reveal: *randao_reveal,
+            pow_chain_reference: Hash256::from(parent_ssz_block.pow_chain_reference()),
+            ancestor_hashes,
+            state_root: Hash256::zero(),
+            attestations,
+            specials: vec![],
+        };
+
+        /*
+         * Apply the block to the parent state and record the resulting state root in the block.
+         */
+        let new_state = self.transition_state(state, &block, &parent_hash)?;
+        block.state_root = new_state.canonical_root();
+
+        Ok(block)
+    }
+}
+
+impl From<BlockValidationContextError> for BlockProductionError {
+    fn from(e: BlockValidationContextError) -> Self {
+        BlockProductionError::ContextGenerationFailed(e)
+    }
+}
+
+impl From<SszBeaconBlockError> for BlockProductionError {
+    fn from(e: SszBeaconBlockError) -> Self {
+        BlockProductionError::DeserializationFailed(e)
+    }
+}
+
+impl From<DBError> for BlockProductionError {
+    fn from(e: DBError) -> Self {
+        BlockProductionError::DBError(e.message)
+    }
+}
+
+impl From<StateTransitionError> for BlockProductionError {
+    fn from(e: StateTransitionError) -> Self {
+        BlockProductionError::StateTransitionFailed(e)
+    }
+}
+
+impl From<StateStorageError> for BlockProductionError {
+    fn from(e: StateStorageError) -> Self {
+        match e {
+            StateStorageError::DBError(s) => BlockProductionError::DBError(s),
+            StateStorageError::DecodeError => {
+                BlockProductionError::DBError("Unable to decode state from database.".to_string())
+            }
+            StateStorageError::MapGenerationFailed(e) => {
+                BlockProductionError::MapGenerationFailed(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bls;
+
+    use self::bls::{create_proof_of_possession, AggregateSignature, Keypair, Signature};
+    use super::super::block_processing::{BlockProcessingError, BlockProcessingOutcome};
+    use super::super::events::BeaconChainEvent;
+    use super::super::head::Reorg;
+    use super::super::stores::test_utils::test_chain as chain_from_config;
+    use super::super::stores::BeaconChainStore;
+    use super::*;
+    use db::MemoryDB;
+    use fork_choice::ForkChoiceError;
+    use lmd_ghost::LmdGhost;
+    use ssz::ssz_encode;
+    use state_transition::block_proposer_index;
+    use std::collections::HashMap;
+    use types::beacon_block::ANCESTOR_HASHES_LEN;
+    use types::{
+        Address, AttestationRecord, BeaconState, Bitfield, ChainConfig, DomainType, ForkData,
+        ValidatorRegistration,
+    };
+    use validation::attestation_parent_hashes::attestation_parent_hashes;
+    use validation::block_validation::SszBeaconBlockValidationError;
+    use validation::message_generation::generate_signed_message;
+    use validation::randao_verification::{randao_layers, repeat_hash};
+
+    /// The number of times each validator's randao secret is hashed to form its commitment.
+    const RANDAO_DEPTH: u64 = 64;
+
+    /// The randao secret of the validator at index `i`.
+    fn randao_secret(i: usize) -> Hash256 {
+        Hash256::from(i as u64)
+    }
+
+    fn test_chain() -> (BeaconChain<MemoryDB, LmdGhost<MemoryDB>>, Vec<Keypair>) {
+        let mut config = ChainConfig::standard();
+        config.cycle_length = 4;
+        config.shard_count = 4;
+        let keypairs: Vec<Keypair> = (0..config.cycle_length * 2)
+            .map(|_| Keypair::random())
+            .collect();
+        for (i, keypair) in keypairs.iter().enumerate() {
+            config.initial_validators.push(ValidatorRegistration {
+                pubkey: keypair.pk.clone(),
+                withdrawal_shard: 0,
+                withdrawal_address: Address::random(),
+                randao_commitment: repeat_hash(&randao_secret(i), RANDAO_DEPTH),
+                proof_of_possession: create_proof_of_possession(
+                    &keypair,
+                    ForkData::genesis().domain(0, DomainType::ProofOfPossession),
+                ),
+            });
+        }
+        (chain_from_config(config), keypairs)
+    }
+
+    fn block_hash(ssz: &[u8]) -> Hash256 {
+        Hash256::from(&SszBeaconBlock::from_slice(ssz).unwrap().block_hash()[..])
+    }
+
+    /// Produce and process a block at each of the given `slots` upon the canonical head,
+    /// returning the serialized blocks.
+    fn extend_chain(
+        chain: &mut BeaconChain<MemoryDB, LmdGhost<MemoryDB>>,
+        keypairs: &[Keypair],
+        slots: &[u64],
+    ) -> Vec<Vec<u8>> {
+        slots
+            .iter()
+            .map(|slot| {
+                let attestation = proposer_attestation(chain, keypairs, *slot);
+                chain.attestation_pool.insert(attestation).unwrap();
+                let randao_reveal = randao_reveal(chain, *slot);
+                let ssz = ssz_encode(&chain.produce_block(*slot, &randao_reveal).unwrap());
+                chain.process_block(&ssz, *slot).unwrap();
+                ssz
+            }).collect()
+    }
+
+    /// Generate an attestation to the canonical head, signed only by the proposer of the canonical
+    /// head, for inclusion in a block at `block_slot`.
+    fn proposer_attestation(
+        chain: &BeaconChain<MemoryDB, LmdGhost<MemoryDB>>,
+        keypairs: &[Keypair],
+        block_slot: u64,
+    ) -> AttestationRecord {
+        let parent_hash = chain.canonical_block_hash();
+        let parent_ssz = chain
+            .store
+            .block
+            .get_serialized_block(&parent_hash[..])
+            .unwrap()
+            .unwrap();
+        let parent = SszBeaconBlock::from_slice(&parent_ssz).unwrap();
+        let state_root = Hash256::from(parent.state_root());
+        let state = &chain.states[&state_root];
+        let (attester_map, proposer_map) = &chain.attester_proposer_maps[&state_root];
+
+        let slot = parent.slot();
+        let proposer = proposer_map[&slot];
+        let (shard_id, committee) = attester_map
+            .iter()
+            .find(|((s, _), committee)| *s == slot && committee.contains(&proposer))
+            .map(|((_, shard_id), committee)| (*shard_id, committee))
+            .unwrap();
+
+        let parent_hashes = attestation_parent_hashes(
+            chain.config.cycle_length,
+            block_slot,
+            slot,
+            &state.recent_block_hashes,
+            &[],
+        ).unwrap();
+        let justified_slot = state.last_justified_slot;
+        let (justified_block_hash, _) = chain
+            .store
+            .block
+            .block_at_slot(&parent_hashes.last().unwrap()[..], justified_slot)
+            .unwrap()
+            .unwrap();
+        let shard_block_hash = Hash256::zero();
+
+        let message = generate_signed_message(
+            slot,
+            &parent_hashes,
+            shard_id,
+            &shard_block_hash,
+            justified_slot,
+            &state.fork_data(),
+        );
+        let mut attester_bitfield = Bitfield::from_elem(committee.len(), false);
+        let mut aggregate_sig = AggregateSignature::new();
+        let position = committee.iter().position(|i| *i == proposer).unwrap();
+        attester_bitfield.set(position, true);
+        aggregate_sig.add(&Signature::new(&message, &keypairs[proposer].sk));
+
+        AttestationRecord {
+            slot,
+            shard_id,
+            oblique_parent_hashes: vec![],
+            shard_block_hash,
+            attester_bitfield,
+            justified_slot,
+            justified_block_hash: Hash256::from(&justified_block_hash[..]),
+            aggregate_sig,
+        }
+    }
+
+    /// Returns the index of the proposer of a block at `block_slot` upon the canonical head,
+    /// along with the state to which the block will be applied (from which the proposer is
+    /// determined).
+    fn block_proposer(
+        chain: &BeaconChain<MemoryDB, LmdGhost<MemoryDB>>,
+        block_slot: u64,
+    ) -> (usize, Arc<BeaconState>) {
+        let parent_hash = chain.canonical_block_hash();
+        let parent_ssz = chain
+            .store
+            .block
+            .get_serialized_block(&parent_hash[..])
+            .unwrap()
+            .unwrap();
+        let parent = SszBeaconBlock::from_slice(&parent_ssz).unwrap();
+        let state_root = Hash256::from(parent.state_root());
+        let (state, _) = chain
+            .pre_block_state(&chain.states[&state_root], block_slot)
+            .unwrap();
+
+        (block_proposer_index(&state, block_slot).unwrap(), state)
+    }
+
+    /// Generate the randao reveal for the proposer of a block at `block_slot` upon the canonical
+    /// head.
+    ///
+    /// Each reveal of the proposer replaces their commitment, so the depth of the present
+    /// commitment in the proposer's hash chain is found before hashing back from it.
+    fn randao_reveal(
+        chain: &BeaconChain<MemoryDB, LmdGhost<MemoryDB>>,
+        block_slot: u64,
+    ) -> Hash256 {
+        let (proposer, state) = block_proposer(chain, block_slot);
+        let validator = &state.validators[proposer];
+        let depth = (0..RANDAO_DEPTH + 1)
+            .find(|d| repeat_hash(&randao_secret(proposer), *d) == validator.randao_commitment)
+            .unwrap();
+
+        let layers = randao_layers(block_slot, validator.randao_last_change);
+        repeat_hash(&randao_secret(proposer), depth - layers)
+    }
+
+    #[test]
+    fn test_produce_block_is_valid() {
+        let (mut chain, keypairs) = test_chain();
+
+        for slot in 1..3 {
+            let attestation = proposer_attestation(&chain, &keypairs, slot);
+            chain.attestation_pool.insert(attestation.clone()).unwrap();
+
+            let randao_reveal = randao_reveal(&chain, slot);
+            let block = chain.produce_block(slot, &randao_reveal).unwrap();
+
+            assert_eq!(block.slot, slot);
+            assert_eq!(block.randao_reveal, randao_reveal);
+            assert_eq!(block.parent_hash(), Some(&chain.canonical_block_hash()));
+            assert_eq!(block.ancestor_hashes.len(), ANCESTOR_HASHES_LEN);
+            assert_eq!(block.attestations[0], attestation);
+
+            /*
+             * The produced block must pass validation.
+             */
+            let ssz = ssz_encode(&block);
+            let ssz_block = SszBeaconBlock::from_slice(&ssz).unwrap();
+            let parent_ssz = chain
+                .store
+                .block
+                .get_serialized_block(ssz_block.parent_hash().unwrap())
+                .unwrap()
+                .unwrap();
+            let parent_ssz_block = SszBeaconBlock::from_slice(&parent_ssz).unwrap();
+            let parent_state = &chain.states[&Hash256::from(parent_ssz_block.state_root())];
+            let (pre_block_state, _) = chain.pre_block_state(parent_state, slot).unwrap();
+            let context = chain
+                .block_validation_context(&ssz_block, &parent_ssz_block, pre_block_state, slot)
+                .unwrap();
+            assert_eq!(context.validate_ssz_block(&ssz_block), Ok(block.clone()));
+
+            /*
+             * The state root of the produced block must match that computed when processing it.
+             */
+            let (outcome, block_hash) = chain.process_block(&ssz, slot).unwrap();
+            assert_eq!(outcome, BlockProcessingOutcome::NewCanonicalBlock);
+            assert_eq!(chain.canonical_block_hash(), block_hash);
+        }
+    }
+
+    #[test]
+    fn test_included_attestations_removed_from_pool() {
+        let (mut chain, keypairs) = test_chain();
+        let blocks = extend_chain(&mut chain, &keypairs, &[1, 2]);
+
+        /*
+         * The attestation included in the first block is removed from the pool once the block is
+         * imported, so the second block includes only the attestation to its parent.
+         */
+        for (ssz, attestation_slot) in blocks.iter().zip(&[0, 1]) {
+            let (block, _) = BeaconBlock::ssz_decode(ssz, 0).unwrap();
+            assert_eq!(block.attestations.len(), 1);
+            assert_eq!(block.attestations[0].slot, *attestation_slot);
+        }
+        assert!(chain.attestation_pool.is_empty());
+    }
+
+    #[test]
+    fn test_randao_change_recorded_at_reveal_slot() {
+        let (mut chain, keypairs) = test_chain();
+
+        /*
+         * The reveal of each block replaces the commitment of its proposer once a cycle
+         * transition processes it. The reveals of the blocks before slot 12 are processed by the
+         * transitions of the blocks at slots 8 and 12.
+         */
+        let mut reveal_slots = HashMap::new();
+        for slot in 1..13 {
+            let (proposer, _) = block_proposer(&chain, slot);
+            if slot < 12 {
+                reveal_slots.insert(proposer, slot);
+            }
+            extend_chain(&mut chain, &keypairs, &[slot]);
+        }
+
+        /*
+         * Each change is recorded at the slot of the latest reveal by the proposer.
+         */
+        let state = chain
+            .block_state(&chain.canonical_block_hash())
+            .unwrap()
+            .unwrap();
+        for (proposer, slot) in reveal_slots {
+            assert_eq!(state.validators[proposer].randao_last_change, slot);
+        }
+    }
+
+    #[test]
+    fn test_process_blocks_after_skipped_slots() {
+        let (mut chain, keypairs) = test_chain();
+
+        /*
+         * The slots between the blocks are skipped, so the blocks at slots 11 and 15 are at slots
+         * to which the state of their parent assigns no proposer. Each is applied to (and has its
+         * proposer determined from) the state of its parent after a cycle transition.
+         */
+        let blocks = extend_chain(&mut chain, &keypairs, &[3, 7, 11, 15]);
+        assert_eq!(chain.canonical_block_hash(), block_hash(&blocks[3]));
+
+        let state = chain
+            .block_state(&chain.canonical_block_hash())
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.last_state_recalculation_slot, 8);
+    }
+
+    #[test]
+    fn test_process_future_block() {
+        let (mut chain, keypairs) = test_chain();
+        let genesis_hash = chain.canonical_block_hash();
+
+        let attestation = proposer_attestation(&chain, &keypairs, 1);
+        chain.attestation_pool.insert(attestation).unwrap();
+        let randao_reveal = randao_reveal(&chain, 1);
+        let ssz = ssz_encode(&chain.produce_block(1, &randao_reveal).unwrap());
+
+        /*
+         * The block is queued while its slot is in the future.
+         */
+        let (outcome, block_hash) = chain.process_block(&ssz, 0).unwrap();
+        assert_eq!(outcome, BlockProcessingOutcome::FutureBlockQueued);
+        assert_eq!(chain.canonical_block_hash(), genesis_hash);
+        assert!(chain.future_blocks.contains(&block_hash));
+        assert_eq!(chain.process_future_blocks(0), vec![]);
+
+        /*
+         * Once the present slot reaches the block, it is replayed before any other block is
+         * processed.
+         */
+        assert_eq!(
+            chain.process_block(&ssz, 1),
+            Ok((BlockProcessingOutcome::BlockAlreadyKnown, block_hash))
+        );
+        assert!(chain.future_blocks.is_empty());
+        assert_eq!(chain.canonical_block_hash(), block_hash);
+    }
+
+    #[test]
+    fn test_process_future_block_invalid_randao_reveal() {
+        let (mut chain, keypairs) = test_chain();
+
+        let attestation = proposer_attestation(&chain, &keypairs, 1);
+        chain.attestation_pool.insert(attestation).unwrap();
+        let ssz = ssz_encode(&chain.produce_block(1, &Hash256::zero()).unwrap());
+
+        /*
+         * A block which is not from the proposer of its slot is rejected rather than queued.
+         */
+        assert_eq!(
+            chain.process_block(&ssz, 0),
+            Err(BlockProcessingError::ValidationFailed(
+                SszBeaconBlockValidationError::InvalidRandaoReveal
+            ))
+        );
+        assert!(chain.future_blocks.is_empty());
+    }
+
+    #[test]
+    fn test_process_future_block_rejected_event() {
+        let (mut chain, _) = test_chain();
+        let events = chain.events.subscribe();
+
+        let block_hash = Hash256::from(42u64);
+        chain
+            .future_blocks
+            .insert(block_hash, vec![0; 8], 1, 0)
+            .unwrap();
+
+        /*
+         * A queued block which fails once its slot is reached is discarded and announced.
+         */
+        let results = chain.process_future_blocks(1);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+        assert!(chain.future_blocks.is_empty());
+        assert_eq!(
+            events.try_recv().ok(),
+            Some(BeaconChainEvent::FutureBlockRejected { block_hash })
+        );
+    }
+
+    #[test]
+    fn test_process_orphan_blocks() {
+        let (mut chain, keypairs) = test_chain();
+        let blocks = extend_chain(&mut chain, &keypairs, &[1, 2, 3]);
+
+        /*
+         * A second chain from the same genesis receives the blocks in reverse order.
+         */
+        let mut other = chain_from_config(chain.config.clone());
+        let parent_requests = other.subscribe_parent_requests();
+        let genesis_hash = other.canonical_block_hash();
+
+        let (outcome, _) = other.process_block(&blocks[2], 3).unwrap();
+        assert_eq!(outcome, BlockProcessingOutcome::OrphanBlockQueued);
+        let (outcome, _) = other.process_block(&blocks[1], 3).unwrap();
+        assert_eq!(outcome, BlockProcessingOutcome::OrphanBlockQueued);
+        assert_eq!(other.orphan_blocks.len(), 2);
+        assert_eq!(other.canonical_block_hash(), genesis_hash);
+
+        /*
+         * The missing parent of each orphan is requested.
+         */
+        let block_1_hash = block_hash(&blocks[0]);
+        let block_2_hash = block_hash(&blocks[1]);
+        assert_eq!(
+            parent_requests.try_iter().collect::<Vec<Hash256>>(),
+            vec![block_2_hash, block_1_hash]
+        );
+
+        /*
+         * Importing the missing block imports its descendants in order.
+         */
+        assert_eq!(
+            other.process_block(&blocks[0], 3),
+            Ok((BlockProcessingOutcome::NewCanonicalBlock, block_1_hash))
+        );
+        assert!(other.orphan_blocks.is_empty());
+        assert_eq!(other.canonical_block_hash(), chain.canonical_block_hash());
+    }
+
+    #[test]
+    fn test_block_import_events() {
+        let (mut chain, keypairs) = test_chain();
+        let events = chain.events.subscribe();
+        let genesis_hash = chain.canonical_block_hash();
+
+        let blocks = extend_chain(&mut chain, &keypairs, &[1, 2]);
+        let block_1_hash = block_hash(&blocks[0]);
+        let block_2_hash = block_hash(&blocks[1]);
+
+        assert_eq!(
+            events.try_iter().collect::<Vec<BeaconChainEvent>>(),
+            vec![
+                BeaconChainEvent::BlockImported {
+                    block_hash: block_1_hash,
+                    slot: 1,
+                },
+                BeaconChainEvent::HeadChanged {
+                    old: genesis_hash,
+                    new: block_1_hash,
+                    reorg_depth: 0,
+                },
+                BeaconChainEvent::BlockImported {
+                    block_hash: block_2_hash,
+                    slot: 2,
+                },
+                BeaconChainEvent::HeadChanged {
+                    old: block_1_hash,
+                    new: block_2_hash,
+                    reorg_depth: 0,
+                },
+            ]
+        );
+
+        /*
+         * A known block produces no events.
+         */
+        chain.process_block(&blocks[0], 2).unwrap();
+        assert_eq!(events.try_recv().ok(), None);
+    }
+
+    #[test]
+    fn test_reorg() {
+        let (mut chain, keypairs) = test_chain();
+        let genesis_hash = chain.canonical_block_hash();
+        let blocks = extend_chain(&mut chain, &keypairs, &[1, 2, 3]);
+        let old_chain: Vec<Hash256> = blocks.iter().map(|ssz| block_hash(ssz)).collect();
+
+        /*
+         * A second chain from the same genesis skips slot 1, so its block at slot 2 forks from
+         * genesis. Store the fork block without processing it, so the head does not change.
+         */
+        let mut other = chain_from_config(chain.config.clone());
+        let fork_blocks = extend_chain(&mut other, &keypairs, &[2]);
+        let fork_hash = block_hash(&fork_blocks[0]);
+        chain
+            .store
+            .block
+            .put_serialized_block(&fork_hash[..], &fork_blocks[0])
+            .unwrap();
+
+        let reorg = chain.find_reorg(&old_chain[2], &fork_hash).unwrap();
+
+        assert_eq!(
+            reorg,
+            Reorg {
+                common_ancestor: genesis_hash,
+                removed: old_chain.clone(),
+                added: vec![fork_hash],
+            }
+        );
+        assert_eq!(reorg.depth(), 3);
+
+        /*
+         * The attestations of the removed blocks are to be returned to the pool.
+         */
+        assert_eq!(chain.removed_attestations(&reorg).unwrap().len(), 3);
+
+        /*
+         * Switching to a descendant of the head removes no blocks.
+         */
+        let reorg = chain.find_reorg(&old_chain[0], &old_chain[2]).unwrap();
+        assert_eq!(reorg.depth(), 0);
+        assert_eq!(reorg.added, old_chain[1..].to_vec());
+
+        assert_eq!(
+            chain.find_reorg(&old_chain[2], &Hash256::from("unknown".as_bytes())),
+            Err(BeaconBlockAtSlotError::UnknownBeaconBlock)
+        );
+    }
+
+    #[test]
+    fn test_process_block_not_descendant_of_finalized() {
+        let (mut chain, keypairs) = test_chain();
+        let blocks = extend_chain(&mut chain, &keypairs, &[1, 2]);
+
+        /*
+         * A second chain from the same genesis skips slots 1 and 2, so its block at slot 3 does
+         * not include the block at slot 1 in its chain.
+         */
+        let mut other = chain_from_config(chain.config.clone());
+        let fork_blocks = extend_chain(&mut other, &keypairs, &[3]);
+
+        chain.last_finalized_slot = 1;
+        chain.last_finalized_block_hash = block_hash(&blocks[0]);
+
+        assert_eq!(
+            chain.process_block(&fork_blocks[0], 3),
+            Err(BlockProcessingError::ValidationFailed(
+                SszBeaconBlockValidationError::NotDescendantOfFinalized
+            ))
+        );
+
+        /*
+         * A block which builds upon the finalized block is accepted.
+         */
+        let blocks = extend_chain(&mut chain, &keypairs, &[3]);
+        assert_eq!(chain.canonical_block_hash(), block_hash(&blocks[0]));
+    }
+
+    #[test]
+    fn test_process_block_after_fork_choice_failure() {
+        let (mut chain, keypairs) = test_chain();
+        let genesis_hash = chain.canonical_block_hash();
+        extend_chain(&mut chain, &keypairs, &[1]);
+
+        let attestation = proposer_attestation(&chain, &keypairs, 2);
+        chain.attestation_pool.insert(attestation).unwrap();
+        let randao_reveal = randao_reveal(&chain, 2);
+        let ssz = ssz_encode(&chain.produce_block(2, &randao_reveal).unwrap());
+        let hash = block_hash(&ssz);
+
+        /*
+         * A head which is missing from the database causes fork choice to fail, so the block is
+         * forgotten.
+         */
+        let missing_hash = Hash256::from("missing".as_bytes());
+        chain
+            .fork_choice
+            .add_block(&missing_hash, &genesis_hash, 1)
+            .unwrap();
+        assert_eq!(
+            chain.process_block(&ssz, 2),
+            Err(BlockProcessingError::ForkChoiceFailed(
+                ForkChoiceError::MissingBlock
+            ))
+        );
+        assert!(!chain.store.block.block_exists(&hash).unwrap());
+
+        /*
+         * The block was also removed from the fork choice rule, so it is imported once the
+         * missing head is removed.
+         */
+        chain
+            .fork_choice
+            .remove_block(&missing_hash, &genesis_hash)
+            .unwrap();
+        assert_eq!(
+            chain.process_block(&ssz, 2),
+            Ok((BlockProcessingOutcome::NewCanonicalBlock, hash))
+        );
+        assert_eq!(chain.canonical_block_hash(), hash);
+    }
+
+    #[test]
+    fn test_chain_from_store_restores_attestation_targets() {
+        let (mut chain, keypairs) = test_chain();
+        extend_chain(&mut chain, &keypairs, &[1, 2, 3]);
+
+        let store = BeaconChainStore {
+            block: chain.store.block.clone(),
+            metadata: chain.store.metadata.clone(),
+            pow_chain: chain.store.pow_chain.clone(),
+            state: chain.store.state.clone(),
+            validator: chain.store.validator.clone(),
+        };
+        let fork_choice = LmdGhost::new(store.block.clone());
+        let resumed = BeaconChain::from_store(store, chain.config.clone(), fork_choice).unwrap();
+
+        /*
+         * The attestations of the blocks are pending in the state of the head, so the latest
+         * attestation target of each validator is restored.
+         */
+        assert!(
+            (0..keypairs.len()).any(|i| chain.fork_choice.latest_attestation_target(i).is_some())
+        );
+        for i in 0..keypairs.len() {
+            assert_eq!(
+                resumed.fork_choice.latest_attestation_target(i),
+                chain.fork_choice.latest_attestation_target(i)
+            );
+        }
+    }
+
+    #[test]
+    fn test_produce_block_without_proposer_attestation() {
+        let (mut chain, _) = test_chain();
+
+        assert_eq!(
+            chain.produce_block(1, &Hash256::zero()),
+            Err(BlockProductionError::NoProposerAttestation)
+        );
+    }
+
+    #[test]
+    fn test_produce_block_at_parent_slot() {
+        let (mut chain, _) = test_chain();
+
+        assert_eq!(
+            chain.produce_block(0, &Hash256::zero()),
+            Err(BlockProductionError::SlotNotAfterParent)
+        );
+    }
+}
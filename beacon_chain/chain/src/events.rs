@@ -0,0 +1,103 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use types::Hash256;
+
+/// An event which occurred while processing a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BeaconChainEvent {
+    /// A block was added to the block tree.
+    BlockImported { block_hash: Hash256, slot: u64 },
+    /// The canonical head changed from `old` to `new`, where `reorg_depth` is the number of blocks
+    /// in the chain of `old` which are not in the chain of `new`.
+    HeadChanged {
+        old: Hash256,
+        new: Hash256,
+        reorg_depth: u64,
+    },
+    /// The last justified slot of the canonical head increased to `slot`.
+    Justified { slot: u64 },
+    /// The last finalized slot of the chain increased to `slot`.
+    Finalized { slot: u64 },
+    /// The state of the block at `block_hash` was recalculated at the cycle boundary `slot`.
+    CycleTransition { block_hash: Hash256, slot: u64 },
+    /// The queued block at `block_hash` was replayed once its slot was reached, but failed to be
+    /// processed and has been discarded.
+    FutureBlockRejected { block_hash: Hash256 },
+}
+
+/// Distributes each `BeaconChainEvent` to all subscribers.
+///
+/// A subscriber is removed once its receiver has been dropped.
+pub struct EventBus {
+    subscribers: Vec<Sender<BeaconChainEvent>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            subscribers: vec![],
+        }
+    }
+
+    /// Returns a channel on which all subsequent events are received.
+    pub fn subscribe(&mut self) -> Receiver<BeaconChainEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Send the `event` to all subscribers.
+    pub fn publish(&mut self, event: &BeaconChainEvent) {
+        self.subscribers
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// Returns the number of subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bus_publish() {
+        let mut bus = EventBus::new();
+        let a = bus.subscribe();
+        let event = BeaconChainEvent::Finalized { slot: 4 };
+
+        bus.publish(&event);
+        let b = bus.subscribe();
+        bus.publish(&BeaconChainEvent::Justified { slot: 8 });
+
+        assert_eq!(
+            a.try_iter().collect::<Vec<BeaconChainEvent>>(),
+            vec![event, BeaconChainEvent::Justified { slot: 8 }]
+        );
+        assert_eq!(
+            b.try_iter().collect::<Vec<BeaconChainEvent>>(),
+            vec![BeaconChainEvent::Justified { slot: 8 }]
+        );
+    }
+
+    #[test]
+    fn test_event_bus_removes_dropped_subscribers() {
+        let mut bus = EventBus::new();
+        let a = bus.subscribe();
+        let b = bus.subscribe();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        drop(b);
+        bus.publish(&BeaconChainEvent::Finalized { slot: 4 });
+
+        assert_eq!(bus.subscriber_count(), 1);
+        assert_eq!(a.try_recv(), Ok(BeaconChainEvent::Finalized { slot: 4 }));
+    }
+}
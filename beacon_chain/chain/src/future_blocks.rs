@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use types::Hash256;
+
+/// The maximum number of slots beyond the present slot at which a block may be queued.
+pub const MAX_FUTURE_BLOCK_SLOT_DISTANCE: u64 = 64;
+/// The maximum number of blocks which may be held in a `FutureBlockQueue`.
+pub const MAX_FUTURE_BLOCKS: usize = 256;
+
+#[derive(Debug, PartialEq)]
+pub enum FutureBlockQueueError {
+    TooFarAhead,
+    QueueFull,
+    AlreadyQueued,
+}
+
+/// A collection of serialized blocks with slots beyond the present slot, which are held until the
+/// present slot reaches them.
+///
+/// The queue is bounded both in the number of blocks it holds and in how far beyond the present
+/// slot a block may be. When full, the block with the highest slot is evicted to make room for a
+/// block with a lower slot.
+pub struct FutureBlockQueue {
+    max_slot_distance: u64,
+    max_len: usize,
+    blocks: BTreeMap<u64, Vec<(Hash256, Vec<u8>)>>,
+}
+
+impl FutureBlockQueue {
+    pub fn new(max_slot_distance: u64, max_len: usize) -> Self {
+        Self {
+            max_slot_distance,
+            max_len,
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// Returns `true` if a block at `slot` is more than `max_slot_distance` beyond the
+    /// `present_slot`, such that it would not be queued.
+    pub fn is_too_far_ahead(&self, slot: u64, present_slot: u64) -> bool {
+        slot > present_slot.saturating_add(self.max_slot_distance)
+    }
+
+    /// Add the serialized block `ssz`, with the given `block_hash` and `slot`, to the queue.
+    ///
+    /// Returns an error if the slot is more than `max_slot_distance` beyond the `present_slot`, if
+    /// the block is already queued, or if the queue is full of blocks with slots no higher than
+    /// `slot`.
+    pub fn insert(
+        &mut self,
+        block_hash: Hash256,
+        ssz: Vec<u8>,
+        slot: u64,
+        present_slot: u64,
+    ) -> Result<(), FutureBlockQueueError> {
+        if self.is_too_far_ahead(slot, present_slot) {
+            return Err(FutureBlockQueueError::TooFarAhead);
+        }
+
+        if self.contains(&block_hash) {
+            return Err(FutureBlockQueueError::AlreadyQueued);
+        }
+
+        if self.len() >= self.max_len {
+            /*
+             * Blocks with lower slots will be processed sooner, so they are preferred.
+             */
+            let highest_slot = match self.blocks.keys().next_back() {
+                Some(highest_slot) if *highest_slot > slot => *highest_slot,
+                _ => return Err(FutureBlockQueueError::QueueFull),
+            };
+            let is_empty = match self.blocks.get_mut(&highest_slot) {
+                Some(blocks) => {
+                    blocks.pop();
+                    blocks.is_empty()
+                }
+                None => false,
+            };
+            if is_empty {
+                self.blocks.remove(&highest_slot);
+            }
+        }
+
+        self.blocks
+            .entry(slot)
+            .or_insert_with(Vec::new)
+            .push((block_hash, ssz));
+        Ok(())
+    }
+
+    /// Remove and return the hash and serialized form of all blocks with a slot no higher than the
+    /// `present_slot`, ordered by slot.
+    pub fn drain_ready(&mut self, present_slot: u64) -> Vec<(Hash256, Vec<u8>)> {
+        let future = self.blocks.split_off(&present_slot.saturating_add(1));
+        let ready = ::std::mem::replace(&mut self.blocks, future);
+        ready
+            .into_iter()
+            .flat_map(|(_, blocks)| blocks.into_iter())
+            .collect()
+    }
+
+    /// Returns `true` if the block with the given `block_hash` is queued.
+    pub fn contains(&self, block_hash: &Hash256) -> bool {
+        self.blocks
+            .values()
+            .any(|blocks| blocks.iter().any(|(hash, _)| hash == block_hash))
+    }
+
+    /// Returns the number of blocks in the queue.
+    pub fn len(&self) -> usize {
+        self.blocks.values().map(|blocks| blocks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(i: u8) -> (Hash256, Vec<u8>) {
+        (Hash256::from(u64::from(i)), vec![i])
+    }
+
+    #[test]
+    fn test_future_block_queue_drain_ready() {
+        let mut queue = FutureBlockQueue::new(10, 10);
+
+        for (i, slot) in [5, 3, 4, 3].iter().enumerate() {
+            let (hash, ssz) = block(i as u8);
+            queue.insert(hash, ssz, *slot, 2).unwrap();
+        }
+        assert_eq!(queue.len(), 4);
+
+        assert!(queue.drain_ready(2).is_empty());
+        assert_eq!(queue.drain_ready(4), vec![block(1), block(3), block(2)]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.drain_ready(10), vec![block(0)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_future_block_queue_rejects() {
+        let mut queue = FutureBlockQueue::new(10, 10);
+
+        let (hash, ssz) = block(0);
+        assert!(queue.is_too_far_ahead(13, 2));
+        assert!(!queue.is_too_far_ahead(12, 2));
+        assert_eq!(
+            queue.insert(hash, ssz.clone(), 13, 2),
+            Err(FutureBlockQueueError::TooFarAhead)
+        );
+        queue.insert(hash, ssz.clone(), 12, 2).unwrap();
+        assert!(queue.contains(&hash));
+        assert_eq!(
+            queue.insert(hash, ssz, 12, 2),
+            Err(FutureBlockQueueError::AlreadyQueued)
+        );
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_future_block_queue_evicts_highest_slot() {
+        let mut queue = FutureBlockQueue::new(10, 2);
+
+        let (hash_0, ssz_0) = block(0);
+        let (hash_1, ssz_1) = block(1);
+        let (hash_2, ssz_2) = block(2);
+        let (hash_3, ssz_3) = block(3);
+        queue.insert(hash_0, ssz_0, 5, 0).unwrap();
+        queue.insert(hash_1, ssz_1, 7, 0).unwrap();
+
+        /*
+         * A block with a higher slot than all queued blocks is rejected.
+         */
+        assert_eq!(
+            queue.insert(hash_2, ssz_2, 7, 0),
+            Err(FutureBlockQueueError::QueueFull)
+        );
+
+        /*
+         * A block with a lower slot replaces the block with the highest slot.
+         */
+        queue.insert(hash_3, ssz_3, 6, 0).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert!(!queue.contains(&hash_1));
+        assert_eq!(queue.drain_ready(10), vec![block(0), block(3)]);
+    }
+}
@@ -1,9 +1,12 @@
-use super::{ActiveState, BeaconChainError, ChainConfig, CrystallizedState};
-use types::{CrosslinkRecord, Hash256, ValidatorStatus};
+use super::{BeaconChainError, ChainConfig};
+use types::beacon_block::ANCESTOR_HASHES_LEN;
+use types::{
+    BeaconBlock, BeaconState, CrosslinkRecord, Hash256, ValidatorStatus, INITIAL_FORK_VERSION,
+};
 use validator_induction::ValidatorInductor;
-use validator_shuffling::{shard_and_committees_for_cycle, ValidatorAssignmentError};
-
-pub const INITIAL_FORK_VERSION: u32 = 0;
+use validator_shuffling::{
+    initial_persistent_committees, shard_and_committees_for_cycle, ValidatorAssignmentError,
+};
 
 impl From<ValidatorAssignmentError> for BeaconChainError {
     fn from(_: ValidatorAssignmentError) -> BeaconChainError {
@@ -14,9 +17,7 @@ impl From<ValidatorAssignmentError> for BeaconChainError {
 /// Initialize a new ChainHead with genesis parameters.
 ///
 /// Used when syncing a chain from scratch.
-pub fn genesis_states(
-    config: &ChainConfig,
-) -> Result<(ActiveState, CrystallizedState), ValidatorAssignmentError> {
+pub fn genesis_state(config: &ChainConfig) -> Result<BeaconState, ValidatorAssignmentError> {
     /*
      * Parse the ValidatorRegistrations into ValidatorRecords and induct them.
      *
@@ -33,7 +34,7 @@ pub fn genesis_states(
     /*
      * Assign the validators to shards, using all zeros as the seed.
      *
-     * Crystallizedstate stores two cycles, so we simply repeat the same assignment twice.
+     * The state stores two cycles, so we simply repeat the same assignment twice.
      */
     let shard_and_committee_for_slots = {
         let mut a = shard_and_committees_for_cycle(&vec![0; 32], &validators, 0, &config)?;
@@ -42,6 +43,12 @@ pub fn genesis_states(
         a
     };
 
+    /*
+     * Assign the validators to persistent committees, using all zeros as the seed.
+     */
+    let persistent_committees =
+        initial_persistent_committees(&[0; 32], &validators, config.shard_count)?;
+
     /*
      * Set all the crosslink records to reference zero hashes.
      */
@@ -58,40 +65,35 @@ pub fn genesis_states(
     };
 
     /*
-     * Initialize a genesis `Crystallizedstate`
+     * Set all recent block hashes to zero (i.e., the genesis block hash).
+     *
+     * Two cycles of hashes are required to validate attestations.
+     */
+    let recent_block_hashes = vec![Hash256::zero(); config.cycle_length as usize * 2];
+
+    /*
+     * Initialize a genesis `BeaconState`
      */
-    let crystallized_state = CrystallizedState {
-        validator_set_change_slot: 0,
+    Ok(BeaconState {
         validators: validators.to_vec(),
         crosslinks,
-        last_state_recalculation_slot: 0,
-        last_finalized_slot: 0,
-        last_justified_slot: 0,
-        justified_streak: 0,
         shard_and_committee_for_slots,
-        deposits_penalized_in_period: vec![],
-        validator_set_delta_hash_chain: Hash256::zero(),
+        persistent_committees,
         pre_fork_version: INITIAL_FORK_VERSION,
         post_fork_version: INITIAL_FORK_VERSION,
-        fork_slot_number: 0,
-    };
-
-    /*
-     * Set all recent block hashes to zero.
-     */
-    let recent_block_hashes = vec![Hash256::zero(); config.cycle_length as usize];
-
-    /*
-     * Create an active state.
-     */
-    let active_state = ActiveState {
-        pending_attestations: vec![],
-        pending_specials: vec![],
         recent_block_hashes,
-        randao_mix: Hash256::zero(),
-    };
+        ..BeaconState::zero()
+    })
+}
 
-    Ok((active_state, crystallized_state))
+/// Generate the genesis block, which references the genesis state.
+///
+/// The genesis block is known by the zero hash, so all of its ancestor hashes are zero.
+pub fn genesis_block(state_root: &Hash256) -> BeaconBlock {
+    let mut block = BeaconBlock::zero();
+    block.ancestor_hashes = vec![Hash256::zero(); ANCESTOR_HASHES_LEN];
+    block.state_root = *state_root;
+    block
 }
 
 #[cfg(test)]
@@ -101,42 +103,51 @@ mod tests {
 
     use self::bls::{create_proof_of_possession, Keypair};
     use super::*;
-    use types::{Address, Hash256, ValidatorRegistration};
+    use types::{Address, DomainType, ForkData, Hash256, ValidatorRegistration};
 
     #[test]
     fn test_genesis_no_validators() {
         let config = ChainConfig::standard();
-        let (act, cry) = genesis_states(&config).unwrap();
+        let state = genesis_state(&config).unwrap();
 
-        assert_eq!(cry.validator_set_change_slot, 0);
-        assert_eq!(cry.validators.len(), 0);
-        assert_eq!(cry.crosslinks.len(), config.shard_count as usize);
-        for cl in cry.crosslinks {
+        assert_eq!(state.validator_set_change_slot, 0);
+        assert_eq!(state.validators.len(), 0);
+        assert_eq!(state.crosslinks.len(), config.shard_count as usize);
+        for cl in state.crosslinks {
             assert_eq!(cl.recently_changed, false);
             assert_eq!(cl.slot, 0);
             assert_eq!(cl.hash, Hash256::zero());
         }
-        assert_eq!(cry.last_state_recalculation_slot, 0);
-        assert_eq!(cry.last_finalized_slot, 0);
-        assert_eq!(cry.last_justified_slot, 0);
-        assert_eq!(cry.justified_streak, 0);
+        assert_eq!(state.last_state_recalculation_slot, 0);
+        assert_eq!(state.last_finalized_slot, 0);
+        assert_eq!(state.last_justified_slot, 0);
+        assert_eq!(state.justified_streak, 0);
         assert_eq!(
-            cry.shard_and_committee_for_slots.len(),
+            state.shard_and_committee_for_slots.len(),
             (config.cycle_length as usize) * 2
         );
-        assert_eq!(cry.deposits_penalized_in_period.len(), 0);
-        assert_eq!(cry.validator_set_delta_hash_chain, Hash256::zero());
-        assert_eq!(cry.pre_fork_version, INITIAL_FORK_VERSION);
-        assert_eq!(cry.post_fork_version, INITIAL_FORK_VERSION);
-        assert_eq!(cry.fork_slot_number, 0);
-
-        assert_eq!(act.pending_attestations.len(), 0);
-        assert_eq!(act.pending_specials.len(), 0);
         assert_eq!(
-            act.recent_block_hashes,
-            vec![Hash256::zero(); config.cycle_length as usize]
+            state.persistent_committees,
+            vec![vec![]; config.shard_count as usize]
         );
-        assert_eq!(act.randao_mix, Hash256::zero());
+        assert_eq!(state.persistent_committee_reassignments, vec![]);
+        assert_eq!(state.deposits_penalized_in_period.len(), 0);
+        assert_eq!(state.validator_set_delta_hash_chain, Hash256::zero());
+        assert_eq!(state.pre_fork_version, INITIAL_FORK_VERSION);
+        assert_eq!(state.post_fork_version, INITIAL_FORK_VERSION);
+        assert_eq!(state.fork_slot_number, 0);
+
+        assert_eq!(state.pending_attestations.len(), 0);
+        assert_eq!(state.pending_specials.len(), 0);
+        assert_eq!(
+            state.recent_block_hashes,
+            vec![Hash256::zero(); config.cycle_length as usize * 2]
+        );
+        assert_eq!(state.randao_mix, Hash256::zero());
+    }
+
+    fn pop_domain() -> u64 {
+        ForkData::genesis().domain(0, DomainType::ProofOfPossession)
     }
 
     fn random_registration() -> ValidatorRegistration {
@@ -146,7 +157,7 @@ mod tests {
             withdrawal_shard: 0,
             withdrawal_address: Address::random(),
             randao_commitment: Hash256::random(),
-            proof_of_possession: create_proof_of_possession(&keypair),
+            proof_of_possession: create_proof_of_possession(&keypair, pop_domain()),
         }
     }
 
@@ -159,9 +170,12 @@ mod tests {
             config.initial_validators.push(random_registration());
         }
 
-        let (_, cry) = genesis_states(&config).unwrap();
+        let state = genesis_state(&config).unwrap();
 
-        assert_eq!(cry.validators.len(), validator_count);
+        assert_eq!(state.validators.len(), validator_count);
+        for i in 0..validator_count {
+            assert!(state.persistent_committee_shard(i).is_some());
+        }
     }
 
     #[test]
@@ -175,19 +189,19 @@ mod tests {
 
         let mut bad_v = random_registration();
         let bad_kp = Keypair::random();
-        bad_v.proof_of_possession = create_proof_of_possession(&bad_kp);
+        bad_v.proof_of_possession = create_proof_of_possession(&bad_kp, pop_domain());
         config.initial_validators.push(bad_v);
 
         let mut bad_v = random_registration();
         bad_v.withdrawal_shard = config.shard_count + 1;
         config.initial_validators.push(bad_v);
 
-        let (_, cry) = genesis_states(&config).unwrap();
+        let state = genesis_state(&config).unwrap();
 
         assert!(
             config.initial_validators.len() != good_validator_count,
             "test is invalid"
         );
-        assert_eq!(cry.validators.len(), good_validator_count);
+        assert_eq!(state.validators.len(), good_validator_count);
     }
 }
@@ -0,0 +1,231 @@
+use super::BeaconChain;
+use db::stores::BeaconBlockAtSlotError;
+use db::ClientDB;
+use fork_choice::{ForkChoice, ForkChoiceError};
+use ssz::Decodable;
+use ssz_helpers::ssz_beacon_block::SszBeaconBlock;
+use std::sync::Arc;
+use types::{AttestationRecord, AttesterMap, BeaconBlock, BeaconState, Hash256};
+
+/// The change to the canonical chain caused by a switch of the canonical head to another chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reorg {
+    /// The latest block which is in both the old and the new canonical chains.
+    pub common_ancestor: Hash256,
+    /// The blocks of the old canonical chain which descend from the common ancestor, ordered by
+    /// slot.
+    pub removed: Vec<Hash256>,
+    /// The blocks of the new canonical chain which descend from the common ancestor, ordered by
+    /// slot.
+    pub added: Vec<Hash256>,
+}
+
+impl Reorg {
+    /// Returns the number of blocks removed from the canonical chain.
+    pub fn depth(&self) -> u64 {
+        self.removed.len() as u64
+    }
+}
+
+impl<T, F> BeaconChain<T, F>
+where
+    T: ClientDB + Sized,
+    F: ForkChoice,
+{
+    /// Register each attester of each attestation in the block with the fork choice rule.
+    ///
+    /// The target of an attestation is the block at the attestation slot in the chain of the
+    /// `parent_hash`. Attestations to skipped slots are ignored.
+    pub(crate) fn register_attestations(
+        &mut self,
+        block: &BeaconBlock,
+        parent_hash: &Hash256,
+        state_root: &Hash256,
+    ) -> Result<(), ForkChoiceError> {
+        let attester_map = match self.attester_proposer_maps.get(state_root) {
+            Some((attester_map, _)) => attester_map.clone(),
+            None => return Ok(()),
+        };
+
+        for attestation in &block.attestations {
+            self.register_attestation(attestation, parent_hash, &attester_map)?;
+        }
+
+        Ok(())
+    }
+
+    /// Register each attester of the `attestation` with the fork choice rule, where the
+    /// attesters are found in the `attester_map`.
+    ///
+    /// The target of the attestation is the block at the attestation slot in the chain of
+    /// `chain_hash`. An attestation to a skipped slot is ignored.
+    pub(crate) fn register_attestation(
+        &mut self,
+        attestation: &AttestationRecord,
+        chain_hash: &Hash256,
+        attester_map: &AttesterMap,
+    ) -> Result<(), ForkChoiceError> {
+        let target_hash = match self
+            .store
+            .block
+            .block_at_slot(&chain_hash[..], attestation.slot)
+        {
+            Ok(Some((hash, _))) => Hash256::from(&hash[..]),
+            _ => return Ok(()),
+        };
+        let attestation_indices = match attester_map.get(&(attestation.slot, attestation.shard_id))
+        {
+            Some(indices) => indices,
+            None => return Ok(()),
+        };
+        for (i, validator_index) in attestation_indices.iter().enumerate() {
+            if attestation.attester_bitfield.get(i).unwrap_or(false) {
+                self.fork_choice.add_attestation(
+                    *validator_index,
+                    attestation.slot,
+                    &target_hash,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the index of the head of the chain in `head_block_hashes`, if any.
+    ///
+    /// Fork choice starts at the last justified block in the chain of `block_hash`, or at genesis
+    /// if that block is not known.
+    pub(crate) fn find_head_index(
+        &self,
+        head_block_hashes: &[Hash256],
+        block_hash: &Hash256,
+        state_root: &Hash256,
+    ) -> Result<Option<usize>, ForkChoiceError> {
+        let state = match self.states.get(state_root) {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+
+        let justified_slot = state.last_justified_slot;
+        let (justified_block_hash, justified_slot) = match self
+            .store
+            .block
+            .block_at_slot(&block_hash[..], justified_slot)
+        {
+            Ok(Some((hash, _))) => (Hash256::from(&hash[..]), justified_slot),
+            _ => (Hash256::zero(), 0),
+        };
+
+        let head =
+            self.fork_choice
+                .find_head(&justified_block_hash, justified_slot, &state.validators)?;
+
+        Ok(head_block_hashes.iter().position(|hash| *hash == head))
+    }
+
+    /// Find the blocks which would be removed from and added to the canonical chain by switching
+    /// from the chain of `old_head` to the chain of `new_head`.
+    pub(crate) fn find_reorg(
+        &self,
+        old_head: &Hash256,
+        new_head: &Hash256,
+    ) -> Result<Reorg, BeaconBlockAtSlotError> {
+        let common_ancestor = self
+            .store
+            .block
+            .find_common_ancestor(&old_head[..], &new_head[..])?
+            .ok_or(BeaconBlockAtSlotError::UnknownBeaconBlock)?;
+        let common_ancestor = Hash256::from(&common_ancestor[..]);
+
+        let removed = self.chain_segment(old_head, &common_ancestor)?;
+        let added = self.chain_segment(new_head, &common_ancestor)?;
+
+        Ok(Reorg {
+            common_ancestor,
+            removed,
+            added,
+        })
+    }
+
+    /// Returns the attestations included in the blocks removed from the canonical chain by the
+    /// `reorg`, so that they may be returned to the attestation pool and included in the new
+    /// canonical chain.
+    pub(crate) fn removed_attestations(
+        &self,
+        reorg: &Reorg,
+    ) -> Result<Vec<AttestationRecord>, BeaconBlockAtSlotError> {
+        self.block_attestations(&reorg.removed)
+    }
+
+    /// Returns the attestations included in each of the blocks with the given `block_hashes`.
+    pub(crate) fn block_attestations(
+        &self,
+        block_hashes: &[Hash256],
+    ) -> Result<Vec<AttestationRecord>, BeaconBlockAtSlotError> {
+        let mut attestations = vec![];
+        for block_hash in block_hashes {
+            let ssz = self
+                .store
+                .block
+                .get_serialized_block(&block_hash[..])?
+                .ok_or(BeaconBlockAtSlotError::UnknownBeaconBlock)?;
+            let (block, _) = BeaconBlock::ssz_decode(&ssz, 0)
+                .map_err(|_| BeaconBlockAtSlotError::InvalidBeaconBlock)?;
+            attestations.extend(block.attestations);
+        }
+        Ok(attestations)
+    }
+
+    /// Returns the state of the block at `block_hash`, if it is held in memory.
+    pub(crate) fn block_state(
+        &self,
+        block_hash: &Hash256,
+    ) -> Result<Option<Arc<BeaconState>>, BeaconBlockAtSlotError> {
+        let ssz = self
+            .store
+            .block
+            .get_serialized_block(&block_hash[..])?
+            .ok_or(BeaconBlockAtSlotError::UnknownBeaconBlock)?;
+        let block = SszBeaconBlock::from_slice(&ssz)
+            .map_err(|_| BeaconBlockAtSlotError::InvalidBeaconBlock)?;
+        let state_root = Hash256::from(block.state_root());
+        Ok(self.states.get(&state_root).cloned())
+    }
+
+    /// Returns the hash of the latest block at or before `slot` in the chain of `head`.
+    pub(crate) fn latest_block_at_or_before(
+        &self,
+        head: &Hash256,
+        slot: u64,
+    ) -> Result<Hash256, BeaconBlockAtSlotError> {
+        let mut block_hash = *head;
+        loop {
+            let (parent_hash, block_slot) = self.store.block.parent_and_slot(&block_hash[..])?;
+            if block_slot <= slot {
+                return Ok(block_hash);
+            }
+            block_hash = Hash256::from(&parent_hash[..]);
+        }
+    }
+
+    /// Returns the hashes of the blocks in the chain of `head` which descend from `ancestor`,
+    /// ordered by slot.
+    fn chain_segment(
+        &self,
+        head: &Hash256,
+        ancestor: &Hash256,
+    ) -> Result<Vec<Hash256>, BeaconBlockAtSlotError> {
+        let mut segment = vec![];
+        let mut block_hash = *head;
+        while block_hash != *ancestor {
+            let (parent_hash, slot) = self.store.block.parent_and_slot(&block_hash[..])?;
+            if slot == 0 {
+                return Err(BeaconBlockAtSlotError::UnknownBeaconBlock);
+            }
+            segment.push(block_hash);
+            block_hash = Hash256::from(&parent_hash[..]);
+        }
+        segment.reverse();
+        Ok(segment)
+    }
+}
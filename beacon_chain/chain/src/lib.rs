@@ -1,95 +1,316 @@
 extern crate db;
-extern crate naive_fork_choice;
+extern crate fork_choice;
+extern crate lmd_ghost;
 extern crate ssz;
 extern crate ssz_helpers;
 extern crate state_transition;
 extern crate types;
 extern crate validation;
+extern crate validator_change;
 extern crate validator_induction;
 extern crate validator_shuffling;
 
+mod attestation_pool;
 mod block_context;
 mod block_processing;
+mod block_production;
+mod events;
+mod future_blocks;
 mod genesis;
+mod head;
 mod maps;
+mod orphan_blocks;
+mod states;
 mod stores;
 mod transition;
+mod validator_changes;
 
-use db::ClientDB;
-use genesis::genesis_states;
-use maps::{generate_attester_and_proposer_maps, AttesterAndProposerMapError};
+use db::stores::{MetadataStoreError, ValidatorStoreError};
+use db::{ClientDB, DBError};
+use fork_choice::{ForkChoice, ForkChoiceError};
+use future_blocks::{MAX_FUTURE_BLOCKS, MAX_FUTURE_BLOCK_SLOT_DISTANCE};
+use genesis::{genesis_block, genesis_state};
+use maps::AttesterAndProposerMapError;
+use orphan_blocks::MAX_ORPHAN_BLOCKS;
+use ssz::ssz_encode;
+use ssz_helpers::ssz_beacon_block::SszBeaconBlock;
+use states::StateStorageError;
 use std::collections::HashMap;
+use std::sync::mpsc::Sender;
 use std::sync::Arc;
-use stores::BeaconChainStore;
-use types::{ActiveState, AttesterMap, ChainConfig, CrystallizedState, Hash256, ProposerMap};
+use types::{
+    AttestationRecord, AttesterMap, BeaconState, ChainConfig, Hash256, PoWBlock, ProposerMap,
+};
+
+pub use attestation_pool::{AttestationPool, AttestationPoolError};
+pub use events::{BeaconChainEvent, EventBus};
+pub use future_blocks::{FutureBlockQueue, FutureBlockQueueError};
+pub use orphan_blocks::{OrphanBlockPool, OrphanBlockPoolError};
+pub use stores::BeaconChainStore;
+pub use validator_changes::ValidatorChangesError;
 
 #[derive(Debug, PartialEq)]
 pub enum BeaconChainError {
     InvalidGenesis,
     InsufficientValidators,
     UnableToGenerateMaps(AttesterAndProposerMapError),
+    ForkChoiceFailed(ForkChoiceError),
+    MissingChainMetadata,
+    InvalidChainMetadata,
+    MissingHeadBlock,
+    MissingHeadState,
+    MissingGenesisState,
+    MissingFinalizedBlock,
     DBError(String),
 }
 
-pub struct BeaconChain<T: ClientDB + Sized> {
+pub struct BeaconChain<T: ClientDB + Sized, F: ForkChoice> {
     /// The last slot which has been finalized, this is common to all forks.
     pub last_finalized_slot: u64,
+    /// The hash of the latest block at or before the `last_finalized_slot` in the canonical chain.
+    pub last_finalized_block_hash: Hash256,
     /// A vec of all block heads (tips of chains).
     pub head_block_hashes: Vec<Hash256>,
     /// The index of the canonical block in `head_block_hashes`.
     pub canonical_head_block_hash: usize,
-    /// A map where the value is an active state the the key is its hash.
-    pub active_states: HashMap<Hash256, ActiveState>,
-    /// A map where the value is crystallized state the the key is its hash.
-    pub crystallized_states: HashMap<Hash256, CrystallizedState>,
-    /// A map of crystallized state to a proposer and attester map.
+    /// A map where the value is a state and the key is its root.
+    pub states: HashMap<Hash256, Arc<BeaconState>>,
+    /// A map of state root to a proposer and attester map.
     pub attester_proposer_maps: HashMap<Hash256, (Arc<AttesterMap>, Arc<ProposerMap>)>,
+    /// Attestations which are waiting to be included in a block.
+    pub attestation_pool: AttestationPool,
+    /// Blocks from future slots which are waiting for the present slot to reach them.
+    pub future_blocks: FutureBlockQueue,
+    /// Blocks whose parents are unknown, which are waiting for their parents to be imported.
+    pub orphan_blocks: OrphanBlockPool,
+    /// The subscriber to requests for the unknown parents of orphan blocks, if any.
+    pub parent_requests: Option<Sender<Hash256>>,
+    /// The subscribers to events which occur while processing blocks.
+    pub events: EventBus,
+    /// The fork choice rule used to determine the canonical head.
+    pub fork_choice: F,
     /// A collection of database stores used by the chain.
     pub store: BeaconChainStore<T>,
     /// The chain configuration.
     pub config: ChainConfig,
 }
 
-impl<T> BeaconChain<T>
+impl<T, F> BeaconChain<T, F>
 where
     T: ClientDB + Sized,
+    F: ForkChoice,
 {
-    pub fn new(store: BeaconChainStore<T>, config: ChainConfig) -> Result<Self, BeaconChainError> {
+    pub fn new(
+        store: BeaconChainStore<T>,
+        config: ChainConfig,
+        fork_choice: F,
+    ) -> Result<Self, BeaconChainError> {
         if config.initial_validators.is_empty() {
             return Err(BeaconChainError::InsufficientValidators);
         }
 
-        let (active_state, crystallized_state) = genesis_states(&config)?;
+        let state = genesis_state(&config)?;
 
         let canonical_latest_block_hash = Hash256::zero();
         let head_block_hashes = vec![canonical_latest_block_hash];
         let canonical_head_block_hash = 0;
-        let mut active_states = HashMap::new();
-        let mut crystallized_states = HashMap::new();
-        let mut attester_proposer_maps = HashMap::new();
 
-        let (attester_map, proposer_map) = generate_attester_and_proposer_maps(
-            &crystallized_state.shard_and_committee_for_slots,
-            0,
+        let state_root = state.canonical_root();
+        let genesis_block = genesis_block(&state_root);
+
+        let mut chain = Self {
+            last_finalized_slot: 0,
+            last_finalized_block_hash: canonical_latest_block_hash,
+            head_block_hashes,
+            canonical_head_block_hash,
+            states: HashMap::new(),
+            attester_proposer_maps: HashMap::new(),
+            attestation_pool: AttestationPool::new(config.cycle_length),
+            future_blocks: FutureBlockQueue::new(MAX_FUTURE_BLOCK_SLOT_DISTANCE, MAX_FUTURE_BLOCKS),
+            orphan_blocks: OrphanBlockPool::new(MAX_FUTURE_BLOCK_SLOT_DISTANCE, MAX_ORPHAN_BLOCKS),
+            parent_requests: None,
+            events: EventBus::new(),
+            fork_choice,
+            store,
+            config,
+        };
+
+        /*
+         * Store the public keys of the genesis validators so their signatures may be verified.
+         */
+        for (i, validator) in state.validators.iter().enumerate() {
+            chain
+                .store
+                .validator
+                .put_public_key_by_index(i, &validator.pubkey)?;
+        }
+
+        /*
+         * Store the genesis state in memory and in the database, recording its root so it may be
+         * loaded when the chain is resumed.
+         */
+        chain.insert_state(state_root, state)?;
+        chain.store.metadata.put_genesis_state_root(&state_root)?;
+
+        /*
+         * Store the genesis block under the zero hash, which is how it is referenced by the
+         * genesis `recent_block_hashes` and by the children of the genesis block. The PoW chain
+         * reference of the genesis block is considered known.
+         */
+        chain.store.block.put_serialized_block(
+            &canonical_latest_block_hash[..],
+            &ssz_encode(&genesis_block),
         )?;
+        chain.store.pow_chain.put_block(&PoWBlock {
+            hash: genesis_block.pow_chain_reference,
+            ..PoWBlock::zero()
+        })?;
+        chain.persist_metadata()?;
 
-        active_states.insert(canonical_latest_block_hash, active_state);
-        crystallized_states.insert(canonical_latest_block_hash, crystallized_state);
-        attester_proposer_maps.insert(
-            canonical_latest_block_hash,
-            (Arc::new(attester_map), Arc::new(proposer_map)),
-        );
+        /*
+         * The genesis block is the root of the block tree.
+         */
+        chain
+            .fork_choice
+            .add_block(&canonical_latest_block_hash, &Hash256::zero(), 0)?;
 
-        Ok(Self {
-            last_finalized_slot: 0,
+        Ok(chain)
+    }
+
+    /// Resume a `BeaconChain` from the metadata and states persisted in the `store`.
+    ///
+    /// The head block hashes, canonical head and last finalized slot are loaded from the store,
+    /// as are the genesis state and the state referenced by each of the head blocks. The last
+    /// finalized block is found in the chain of the canonical head.
+    ///
+    /// The latest attestation targets of the fork choice rule are restored from the attestations
+    /// pending in the state of each head block. These are the attestations since the last state
+    /// recalculation, so the target of a validator which has not attested since is not restored.
+    pub fn from_store(
+        store: BeaconChainStore<T>,
+        config: ChainConfig,
+        fork_choice: F,
+    ) -> Result<Self, BeaconChainError> {
+        let head_block_hashes = store
+            .metadata
+            .get_head_block_hashes()?
+            .ok_or(BeaconChainError::MissingChainMetadata)?;
+        let canonical_head_block_hash = store
+            .metadata
+            .get_canonical_head_index()?
+            .ok_or(BeaconChainError::MissingChainMetadata)?;
+        let last_finalized_slot = store
+            .metadata
+            .get_last_finalized_slot()?
+            .ok_or(BeaconChainError::MissingChainMetadata)?;
+        let genesis_state_root = store
+            .metadata
+            .get_genesis_state_root()?
+            .ok_or(BeaconChainError::MissingChainMetadata)?;
+
+        if canonical_head_block_hash >= head_block_hashes.len() {
+            return Err(BeaconChainError::InvalidChainMetadata);
+        }
+
+        let mut chain = Self {
+            last_finalized_slot,
+            last_finalized_block_hash: Hash256::zero(),
             head_block_hashes,
             canonical_head_block_hash,
-            active_states,
-            crystallized_states,
-            attester_proposer_maps,
+            states: HashMap::new(),
+            attester_proposer_maps: HashMap::new(),
+            attestation_pool: AttestationPool::new(config.cycle_length),
+            future_blocks: FutureBlockQueue::new(MAX_FUTURE_BLOCK_SLOT_DISTANCE, MAX_FUTURE_BLOCKS),
+            orphan_blocks: OrphanBlockPool::new(MAX_FUTURE_BLOCK_SLOT_DISTANCE, MAX_ORPHAN_BLOCKS),
+            parent_requests: None,
+            events: EventBus::new(),
+            fork_choice,
             store,
             config,
-        })
+        };
+
+        /*
+         * Load the genesis state into memory.
+         */
+        chain.load_state(&genesis_state_root)?;
+        if !chain.states.contains_key(&genesis_state_root) {
+            return Err(BeaconChainError::MissingGenesisState);
+        }
+
+        /*
+         * Load the state for each head block into memory and add each head to the fork choice.
+         */
+        let mut head_state_roots = vec![];
+        for head_block_hash in chain.head_block_hashes.clone() {
+            let ssz = chain
+                .store
+                .block
+                .get_serialized_block(&head_block_hash[..])?
+                .ok_or(BeaconChainError::MissingHeadBlock)?;
+            let block =
+                SszBeaconBlock::from_slice(&ssz).map_err(|_| BeaconChainError::MissingHeadBlock)?;
+            let parent_hash = block
+                .parent_hash()
+                .ok_or(BeaconChainError::MissingHeadBlock)?;
+            let state_root = Hash256::from(block.state_root());
+
+            chain.load_state(&state_root)?;
+
+            if !chain.states.contains_key(&state_root) {
+                return Err(BeaconChainError::MissingHeadState);
+            }
+
+            chain.fork_choice.add_block(
+                &head_block_hash,
+                &Hash256::from(parent_hash),
+                block.slot(),
+            )?;
+            head_state_roots.push((head_block_hash, state_root));
+        }
+
+        /*
+         * Register the attestations pending in the state of each head with the fork choice. The
+         * attesters are found in the committees of the head state, which cover the slots of its
+         * pending attestations.
+         */
+        for (head_block_hash, state_root) in head_state_roots {
+            let attester_map = match chain.attester_proposer_maps.get(&state_root) {
+                Some((attester_map, _)) => attester_map.clone(),
+                None => continue,
+            };
+            let attestations: Vec<AttestationRecord> = chain.states[&state_root]
+                .pending_attestations
+                .iter()
+                .map(|pending| pending.attestation.clone())
+                .collect();
+            for attestation in &attestations {
+                chain.register_attestation(attestation, &head_block_hash, &attester_map)?;
+            }
+        }
+
+        /*
+         * The last finalized block is found in the chain of the canonical head.
+         */
+        let canonical_block_hash = chain.canonical_block_hash();
+        chain.last_finalized_block_hash = chain
+            .latest_block_at_or_before(&canonical_block_hash, last_finalized_slot)
+            .map_err(|_| BeaconChainError::MissingFinalizedBlock)?;
+
+        Ok(chain)
+    }
+
+    /// Write the head block hashes, canonical head and last finalized slot to the store so the
+    /// chain may later be resumed with `from_store`.
+    pub(crate) fn persist_metadata(&self) -> Result<(), DBError> {
+        self.store
+            .metadata
+            .put_head_block_hashes(&self.head_block_hashes)?;
+        self.store
+            .metadata
+            .put_canonical_head_index(self.canonical_head_block_hash)?;
+        self.store
+            .metadata
+            .put_last_finalized_slot(self.last_finalized_slot)
     }
 
     pub fn canonical_block_hash(&self) -> Hash256 {
@@ -103,42 +324,242 @@ impl From<AttesterAndProposerMapError> for BeaconChainError {
     }
 }
 
+impl From<ForkChoiceError> for BeaconChainError {
+    fn from(e: ForkChoiceError) -> BeaconChainError {
+        BeaconChainError::ForkChoiceFailed(e)
+    }
+}
+
+impl From<DBError> for BeaconChainError {
+    fn from(e: DBError) -> BeaconChainError {
+        BeaconChainError::DBError(e.message)
+    }
+}
+
+impl From<MetadataStoreError> for BeaconChainError {
+    fn from(e: MetadataStoreError) -> BeaconChainError {
+        match e {
+            MetadataStoreError::DBError(s) => BeaconChainError::DBError(s),
+            MetadataStoreError::DecodeError => BeaconChainError::InvalidChainMetadata,
+        }
+    }
+}
+
+impl From<ValidatorStoreError> for BeaconChainError {
+    fn from(e: ValidatorStoreError) -> BeaconChainError {
+        match e {
+            ValidatorStoreError::DBError(s) => BeaconChainError::DBError(s),
+            ValidatorStoreError::DecodeError => {
+                BeaconChainError::DBError("Unable to decode validator from database.".to_string())
+            }
+        }
+    }
+}
+
+impl From<StateStorageError> for BeaconChainError {
+    fn from(e: StateStorageError) -> BeaconChainError {
+        match e {
+            StateStorageError::DBError(s) => BeaconChainError::DBError(s),
+            StateStorageError::DecodeError => {
+                BeaconChainError::DBError("Unable to decode state from database.".to_string())
+            }
+            StateStorageError::MapGenerationFailed(e) => BeaconChainError::UnableToGenerateMaps(e),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::stores::test_utils::{test_config, test_store};
     use super::*;
     use db::stores::*;
     use db::MemoryDB;
+    use lmd_ghost::LmdGhost;
     use std::sync::Arc;
-    use types::ValidatorRegistration;
+    use types::ValidatorRecord;
+
+    /// A `ForkChoice` which records the blocks it is given and always returns the justified block.
+    struct StubForkChoice {
+        blocks: Vec<Hash256>,
+    }
+
+    impl ForkChoice for StubForkChoice {
+        fn add_block(
+            &mut self,
+            block_hash: &Hash256,
+            _parent_hash: &Hash256,
+            _slot: u64,
+        ) -> Result<(), ForkChoiceError> {
+            self.blocks.push(*block_hash);
+            Ok(())
+        }
+
+        fn remove_block(
+            &mut self,
+            block_hash: &Hash256,
+            _parent_hash: &Hash256,
+        ) -> Result<(), ForkChoiceError> {
+            self.blocks.retain(|hash| hash != block_hash);
+            Ok(())
+        }
+
+        fn add_attestation(
+            &mut self,
+            _validator_index: usize,
+            _target_slot: u64,
+            _target_hash: &Hash256,
+        ) -> Result<(), ForkChoiceError> {
+            Ok(())
+        }
+
+        fn find_head(
+            &self,
+            justified_block_hash: &Hash256,
+            _justified_slot: u64,
+            _validators: &[ValidatorRecord],
+        ) -> Result<Hash256, ForkChoiceError> {
+            Ok(*justified_block_hash)
+        }
+    }
+
+    fn test_fork_choice(db: &Arc<MemoryDB>) -> LmdGhost<MemoryDB> {
+        LmdGhost::new(Arc::new(BeaconBlockStore::new(db.clone())))
+    }
 
     #[test]
     fn test_new_chain() {
-        let mut config = ChainConfig::standard();
-        config.cycle_length = 4;
-        config.shard_count = 4;
+        let config = test_config();
         let db = Arc::new(MemoryDB::open());
-        let store = BeaconChainStore {
-            block: Arc::new(BeaconBlockStore::new(db.clone())),
-            pow_chain: Arc::new(PoWChainStore::new(db.clone())),
-            validator: Arc::new(ValidatorStore::new(db.clone())),
-        };
+        let store = test_store(db.clone());
 
-        for _ in 0..config.cycle_length * 2 {
-            config
-                .initial_validators
-                .push(ValidatorRegistration::random())
-        }
-
-        let chain = BeaconChain::new(store, config.clone()).unwrap();
-        let (act, cry) = genesis_states(&config).unwrap();
+        let chain = BeaconChain::new(store, config.clone(), test_fork_choice(&db)).unwrap();
+        let state = genesis_state(&config).unwrap();
 
         assert_eq!(chain.last_finalized_slot, 0);
         assert_eq!(chain.canonical_block_hash(), Hash256::zero());
 
-        let stored_act = chain.active_states.get(&Hash256::zero()).unwrap();
-        assert_eq!(act, *stored_act);
+        let stored_state = chain.states.get(&state.canonical_root()).unwrap();
+        assert_eq!(state, **stored_state);
+        assert_eq!(
+            chain.store.metadata.get_genesis_state_root(),
+            Ok(Some(state.canonical_root()))
+        );
+
+        assert!(chain
+            .attester_proposer_maps
+            .contains_key(&state.canonical_root()));
+
+        let genesis_ssz = chain
+            .store
+            .block
+            .get_serialized_block(&Hash256::zero()[..])
+            .unwrap()
+            .unwrap();
+        let genesis_block = SszBeaconBlock::from_slice(&genesis_ssz).unwrap();
+        assert_eq!(genesis_block.slot(), 0);
+        assert_eq!(
+            Hash256::from(genesis_block.state_root()),
+            state.canonical_root()
+        );
+
+        for (i, validator) in state.validators.iter().enumerate() {
+            let pubkey = chain.store.validator.get_public_key_by_index(i).unwrap();
+            assert_eq!(pubkey, Some(validator.pubkey.clone()));
+        }
+    }
+
+    #[test]
+    fn test_chain_from_store() {
+        let config = test_config();
+        let db = Arc::new(MemoryDB::open());
+
+        let chain = BeaconChain::new(
+            test_store(db.clone()),
+            config.clone(),
+            test_fork_choice(&db),
+        )
+        .unwrap();
+
+        /*
+         * Resume the chain without any initial validators, as would a restarted node.
+         */
+        let mut resume_config = config.clone();
+        resume_config.initial_validators = vec![];
+        let resumed =
+            BeaconChain::from_store(test_store(db.clone()), resume_config, test_fork_choice(&db))
+                .unwrap();
+
+        assert_eq!(resumed.head_block_hashes, chain.head_block_hashes);
+        assert_eq!(
+            resumed.canonical_head_block_hash,
+            chain.canonical_head_block_hash
+        );
+        assert_eq!(resumed.last_finalized_slot, chain.last_finalized_slot);
+        assert_eq!(
+            resumed.last_finalized_block_hash,
+            chain.last_finalized_block_hash
+        );
+        assert_eq!(resumed.states, chain.states);
+        for root in chain.states.keys() {
+            assert!(resumed.attester_proposer_maps.contains_key(root));
+        }
+    }
+
+    #[test]
+    fn test_chain_from_empty_store() {
+        let config = test_config();
+        let db = Arc::new(MemoryDB::open());
+
+        let result = BeaconChain::from_store(test_store(db.clone()), config, test_fork_choice(&db));
+
+        assert_eq!(result.err(), Some(BeaconChainError::MissingChainMetadata));
+    }
+
+    #[test]
+    fn test_chain_from_store_invalid_canonical_head() {
+        let config = test_config();
+        let db = Arc::new(MemoryDB::open());
+
+        let chain = BeaconChain::new(
+            test_store(db.clone()),
+            config.clone(),
+            test_fork_choice(&db),
+        )
+        .unwrap();
+        chain
+            .store
+            .metadata
+            .put_canonical_head_index(chain.head_block_hashes.len())
+            .unwrap();
+
+        let result = BeaconChain::from_store(test_store(db.clone()), config, test_fork_choice(&db));
+
+        assert_eq!(result.err(), Some(BeaconChainError::InvalidChainMetadata));
+    }
+
+    #[test]
+    fn test_chain_with_stub_fork_choice() {
+        let config = test_config();
+        let db = Arc::new(MemoryDB::open());
+
+        let chain = BeaconChain::new(
+            test_store(db.clone()),
+            config.clone(),
+            StubForkChoice { blocks: vec![] },
+        )
+        .unwrap();
+
+        // The genesis block is added to the fork choice.
+        assert_eq!(chain.fork_choice.blocks, vec![Hash256::zero()]);
+
+        let resumed = BeaconChain::from_store(
+            test_store(db.clone()),
+            config,
+            StubForkChoice { blocks: vec![] },
+        )
+        .unwrap();
 
-        let stored_cry = chain.crystallized_states.get(&Hash256::zero()).unwrap();
-        assert_eq!(cry, *stored_cry);
+        // Each head is added to the fork choice when resuming.
+        assert_eq!(resumed.fork_choice.blocks, chain.head_block_hashes);
     }
 }
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use types::Hash256;
+
+/// The maximum number of blocks which may be held in an `OrphanBlockPool`.
+pub const MAX_ORPHAN_BLOCKS: usize = 256;
+
+#[derive(Debug, PartialEq)]
+pub enum OrphanBlockPoolError {
+    TooFarAhead,
+    PoolFull,
+    AlreadyKnown,
+}
+
+/// A serialized block which is waiting for its parent to be imported.
+struct OrphanBlock {
+    block_hash: Hash256,
+    slot: u64,
+    ssz: Vec<u8>,
+}
+
+/// A collection of serialized blocks whose parents are unknown, keyed by the hash of the missing
+/// parent.
+///
+/// The pool is bounded both in the number of blocks it holds and in how far beyond the present
+/// slot a block may be. When full, the block with the highest slot is evicted to make room for a
+/// block with a lower slot.
+pub struct OrphanBlockPool {
+    max_slot_distance: u64,
+    max_len: usize,
+    orphans: HashMap<Hash256, Vec<OrphanBlock>>,
+}
+
+impl OrphanBlockPool {
+    pub fn new(max_slot_distance: u64, max_len: usize) -> Self {
+        Self {
+            max_slot_distance,
+            max_len,
+            orphans: HashMap::new(),
+        }
+    }
+
+    /// Add the serialized block `ssz`, with the given `block_hash` and `slot`, to the pool to wait
+    /// for the block at `parent_hash`.
+    ///
+    /// Returns `true` if no other block in the pool was waiting for the same parent.
+    ///
+    /// Returns an error if the slot is more than `max_slot_distance` beyond the `present_slot`, if
+    /// the block is already in the pool, or if the pool is full of blocks with slots no higher
+    /// than `slot`.
+    pub fn insert(
+        &mut self,
+        parent_hash: Hash256,
+        block_hash: Hash256,
+        slot: u64,
+        present_slot: u64,
+        ssz: Vec<u8>,
+    ) -> Result<bool, OrphanBlockPoolError> {
+        if slot > present_slot.saturating_add(self.max_slot_distance) {
+            return Err(OrphanBlockPoolError::TooFarAhead);
+        }
+
+        if self.contains(&block_hash) {
+            return Err(OrphanBlockPoolError::AlreadyKnown);
+        }
+
+        if self.len() >= self.max_len {
+            /*
+             * Blocks with lower slots are nearer to the imported chain, so they are preferred.
+             */
+            let highest_slot = self
+                .orphans
+                .values()
+                .flat_map(|blocks| blocks.iter().map(|b| b.slot))
+                .max();
+            match highest_slot {
+                Some(highest_slot) if highest_slot > slot => {
+                    self.remove_where(|b| b.slot == highest_slot, 1)
+                }
+                _ => return Err(OrphanBlockPoolError::PoolFull),
+            }
+        }
+
+        let blocks = self.orphans.entry(parent_hash).or_insert_with(Vec::new);
+        blocks.push(OrphanBlock {
+            block_hash,
+            slot,
+            ssz,
+        });
+        Ok(blocks.len() == 1)
+    }
+
+    /// Remove and return all serialized blocks which are waiting for the block at `parent_hash`,
+    /// ordered by slot.
+    pub fn take_children(&mut self, parent_hash: &Hash256) -> Vec<Vec<u8>> {
+        let mut blocks = self.orphans.remove(parent_hash).unwrap_or_default();
+        blocks.sort_by_key(|b| b.slot);
+        blocks.into_iter().map(|b| b.ssz).collect()
+    }
+
+    /// Remove all blocks with a slot no higher than the `last_finalized_slot`, as they can never
+    /// be imported.
+    pub fn prune(&mut self, last_finalized_slot: u64) {
+        self.remove_where(|b| b.slot <= last_finalized_slot, usize::max_value());
+    }
+
+    /// Returns `true` if the block with the given `block_hash` is in the pool.
+    pub fn contains(&self, block_hash: &Hash256) -> bool {
+        self.orphans
+            .values()
+            .any(|blocks| blocks.iter().any(|b| b.block_hash == *block_hash))
+    }
+
+    /// Returns the number of blocks in the pool.
+    pub fn len(&self) -> usize {
+        self.orphans.values().map(|blocks| blocks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove at most `limit` blocks which satisfy the `predicate`.
+    fn remove_where<P>(&mut self, predicate: P, limit: usize)
+    where
+        P: Fn(&OrphanBlock) -> bool,
+    {
+        let mut removed = 0;
+        for blocks in self.orphans.values_mut() {
+            blocks.retain(|b| {
+                if removed < limit && predicate(b) {
+                    removed += 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.orphans.retain(|_, blocks| !blocks.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(i: u64) -> Hash256 {
+        Hash256::from(i)
+    }
+
+    #[test]
+    fn test_orphan_block_pool_take_children() {
+        let mut pool = OrphanBlockPool::new(10, 10);
+
+        assert_eq!(pool.insert(hash(0), hash(1), 3, 0, vec![1]), Ok(true));
+        assert_eq!(pool.insert(hash(0), hash(2), 2, 0, vec![2]), Ok(false));
+        assert_eq!(pool.insert(hash(1), hash(3), 4, 0, vec![3]), Ok(true));
+        assert_eq!(
+            pool.insert(hash(1), hash(3), 4, 0, vec![3]),
+            Err(OrphanBlockPoolError::AlreadyKnown)
+        );
+        assert_eq!(pool.len(), 3);
+        assert!(pool.contains(&hash(3)));
+
+        assert_eq!(pool.take_children(&hash(0)), vec![vec![2], vec![1]]);
+        assert_eq!(pool.take_children(&hash(0)), Vec::<Vec<u8>>::new());
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.take_children(&hash(1)), vec![vec![3]]);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_orphan_block_pool_too_far_ahead() {
+        let mut pool = OrphanBlockPool::new(10, 10);
+
+        assert_eq!(
+            pool.insert(hash(0), hash(1), 13, 2, vec![1]),
+            Err(OrphanBlockPoolError::TooFarAhead)
+        );
+        assert_eq!(pool.insert(hash(0), hash(1), 12, 2, vec![1]), Ok(true));
+    }
+
+    #[test]
+    fn test_orphan_block_pool_evicts_highest_slot() {
+        let mut pool = OrphanBlockPool::new(10, 2);
+
+        pool.insert(hash(0), hash(1), 5, 0, vec![1]).unwrap();
+        pool.insert(hash(0), hash(2), 7, 0, vec![2]).unwrap();
+
+        /*
+         * A block with a slot no lower than all blocks in the pool is rejected.
+         */
+        assert_eq!(
+            pool.insert(hash(0), hash(3), 7, 0, vec![3]),
+            Err(OrphanBlockPoolError::PoolFull)
+        );
+
+        /*
+         * A block with a lower slot replaces the block with the highest slot.
+         */
+        pool.insert(hash(4), hash(5), 6, 0, vec![5]).unwrap();
+        assert_eq!(pool.len(), 2);
+        assert!(pool.contains(&hash(1)));
+        assert!(!pool.contains(&hash(2)));
+        assert!(pool.contains(&hash(5)));
+    }
+
+    #[test]
+    fn test_orphan_block_pool_prune() {
+        let mut pool = OrphanBlockPool::new(10, 10);
+
+        for i in 1..6 {
+            pool.insert(hash(0), hash(i), i, 0, vec![i as u8]).unwrap();
+        }
+
+        pool.prune(3);
+
+        assert_eq!(pool.take_children(&hash(0)), vec![vec![4], vec![5]]);
+    }
+}
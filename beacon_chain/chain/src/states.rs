@@ -0,0 +1,131 @@
+use super::maps::{generate_attester_and_proposer_maps, AttesterAndProposerMapError};
+use super::BeaconChain;
+use db::stores::BeaconStateStoreError;
+use db::{ClientDB, DBError};
+use fork_choice::ForkChoice;
+use std::sync::Arc;
+use types::{BeaconState, Hash256};
+
+#[derive(Debug, PartialEq)]
+pub enum StateStorageError {
+    DBError(String),
+    DecodeError,
+    MapGenerationFailed(AttesterAndProposerMapError),
+}
+
+impl<T, F> BeaconChain<T, F>
+where
+    T: ClientDB + Sized,
+    F: ForkChoice,
+{
+    /// Store a `BeaconState` (and its attester and proposer maps) in memory and write it through
+    /// to the database.
+    pub(crate) fn insert_state(
+        &mut self,
+        root: Hash256,
+        state: BeaconState,
+    ) -> Result<(), StateStorageError> {
+        self.store.state.put_state(&root, &state)?;
+        self.cache_state(root, state)
+    }
+
+    /// Remove a `BeaconState` (and its attester and proposer maps) from memory and the database,
+    /// along with any validator set changes stored for it.
+    pub(crate) fn remove_state(&mut self, root: &Hash256) -> Result<(), StateStorageError> {
+        self.states.remove(root);
+        self.attester_proposer_maps.remove(root);
+        self.store.state.delete_state(root)?;
+        self.store.state.delete_validator_changes(root)?;
+        Ok(())
+    }
+
+    /// Ensure the `BeaconState` with the given root is held in memory, loading it from the
+    /// database (and generating its attester and proposer maps) if it is not.
+    ///
+    /// Does nothing if the state is unknown to the database.
+    pub(crate) fn load_state(&mut self, root: &Hash256) -> Result<(), StateStorageError> {
+        if self.states.contains_key(root) {
+            return Ok(());
+        }
+        if let Some(state) = self.store.state.get_state(root)? {
+            self.cache_state(*root, state)?;
+        }
+        Ok(())
+    }
+
+    /// Store a `BeaconState` in memory, alongside its attester and proposer maps.
+    fn cache_state(&mut self, root: Hash256, state: BeaconState) -> Result<(), StateStorageError> {
+        let (attester_map, proposer_map) = generate_attester_and_proposer_maps(
+            &state.shard_and_committee_for_slots,
+            state.last_state_recalculation_slot,
+        )?;
+        self.attester_proposer_maps
+            .insert(root, (Arc::new(attester_map), Arc::new(proposer_map)));
+        self.states.insert(root, Arc::new(state));
+        Ok(())
+    }
+}
+
+impl From<DBError> for StateStorageError {
+    fn from(e: DBError) -> Self {
+        StateStorageError::DBError(e.message)
+    }
+}
+
+impl From<BeaconStateStoreError> for StateStorageError {
+    fn from(e: BeaconStateStoreError) -> Self {
+        match e {
+            BeaconStateStoreError::DBError(s) => StateStorageError::DBError(s),
+            BeaconStateStoreError::DecodeError => StateStorageError::DecodeError,
+        }
+    }
+}
+
+impl From<AttesterAndProposerMapError> for StateStorageError {
+    fn from(e: AttesterAndProposerMapError) -> Self {
+        StateStorageError::MapGenerationFailed(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stores::test_utils::{test_chain, test_config};
+    use super::*;
+
+    #[test]
+    fn test_states_written_through_to_db() {
+        let chain = test_chain(test_config());
+
+        for (root, state) in chain.states.iter() {
+            let stored = chain.store.state.get_state(root).unwrap();
+            assert_eq!(stored.as_ref(), Some(&**state));
+        }
+    }
+
+    #[test]
+    fn test_states_loaded_from_db_on_cache_miss() {
+        let mut chain = test_chain(test_config());
+        let root = *chain.states.keys().next().unwrap();
+
+        let state = chain.states.remove(&root).unwrap();
+        chain.attester_proposer_maps.remove(&root);
+
+        chain.load_state(&root).unwrap();
+
+        assert_eq!(chain.states.get(&root), Some(&state));
+        assert!(chain.attester_proposer_maps.contains_key(&root));
+    }
+
+    #[test]
+    fn test_remove_states() {
+        let mut chain = test_chain(test_config());
+        let root = *chain.states.keys().next().unwrap();
+
+        chain.remove_state(&root).unwrap();
+        chain.load_state(&root).unwrap();
+
+        assert!(chain.states.is_empty());
+        assert!(chain.attester_proposer_maps.is_empty());
+        assert!(!chain.store.state.state_exists(&root).unwrap());
+    }
+}
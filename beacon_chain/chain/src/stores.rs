@@ -1,9 +1,53 @@
-use db::stores::{BeaconBlockStore, PoWChainStore, ValidatorStore};
+use db::stores::{
+    BeaconBlockStore, BeaconStateStore, MetadataStore, PoWChainStore, ValidatorStore,
+};
 use db::ClientDB;
 use std::sync::Arc;
 
 pub struct BeaconChainStore<T: ClientDB + Sized> {
     pub block: Arc<BeaconBlockStore<T>>,
+    pub metadata: Arc<MetadataStore<T>>,
     pub pow_chain: Arc<PoWChainStore<T>>,
+    pub state: Arc<BeaconStateStore<T>>,
     pub validator: Arc<ValidatorStore<T>>,
 }
+
+#[cfg(test)]
+pub mod test_utils {
+    use super::super::BeaconChain;
+    use super::*;
+    use db::MemoryDB;
+    use lmd_ghost::LmdGhost;
+    use types::{ChainConfig, ValidatorRegistration};
+
+    /// Open each of the stores upon the same `db`.
+    pub fn test_store(db: Arc<MemoryDB>) -> BeaconChainStore<MemoryDB> {
+        BeaconChainStore {
+            block: Arc::new(BeaconBlockStore::new(db.clone())),
+            metadata: Arc::new(MetadataStore::new(db.clone())),
+            pow_chain: Arc::new(PoWChainStore::new(db.clone())),
+            state: Arc::new(BeaconStateStore::new(db.clone())),
+            validator: Arc::new(ValidatorStore::new(db.clone())),
+        }
+    }
+
+    /// A config with short cycles, few shards and two cycles worth of random initial validators.
+    pub fn test_config() -> ChainConfig {
+        let mut config = ChainConfig::standard();
+        config.cycle_length = 4;
+        config.shard_count = 4;
+        for _ in 0..config.cycle_length * 2 {
+            config
+                .initial_validators
+                .push(ValidatorRegistration::random())
+        }
+        config
+    }
+
+    /// Start a new chain from the `config` upon an empty `MemoryDB`, with LMD-GHOST fork choice.
+    pub fn test_chain(config: ChainConfig) -> BeaconChain<MemoryDB, LmdGhost<MemoryDB>> {
+        let store = test_store(Arc::new(MemoryDB::open()));
+        let fork_choice = LmdGhost::new(store.block.clone());
+        BeaconChain::new(store, config, fork_choice).unwrap()
+    }
+}
@@ -1,29 +1,265 @@
 use super::BeaconChain;
+use db::stores::PoWChainStoreError;
 use db::ClientDB;
-use state_transition::{extend_active_state, StateTransitionError};
-use types::{ActiveState, BeaconBlock, CrystallizedState, Hash256};
+use fork_choice::ForkChoice;
+use state_transition::{
+    block_proposer_index, cycle_transition_due, per_block_transition, process_deposits,
+    recalculate_state, StateTransitionError,
+};
+use std::sync::Arc;
+use types::{BeaconBlock, BeaconState, Hash256, ValidatorRegistration};
+use validator_change::ValidatorChangeRecord;
 
-impl<T> BeaconChain<T>
+impl<T, F> BeaconChain<T, F>
 where
     T: ClientDB + Sized,
+    F: ForkChoice,
 {
-    pub(crate) fn transition_states(
+    /// Apply the `block` to the state of its parent, performing a cycle-boundary recalculation
+    /// of the state if required (see `pre_block_state`).
+    ///
+    /// The `parent_hash` is added to the `recent_block_hashes` (see `per_block_transition`).
+    pub(crate) fn transition_state(
         &self,
-        act_state: &ActiveState,
-        cry_state: &CrystallizedState,
+        state: &Arc<BeaconState>,
         block: &BeaconBlock,
-        block_hash: &Hash256,
-    ) -> Result<(ActiveState, Option<CrystallizedState>), StateTransitionError> {
-        let state_recalc_distance = block
-            .slot
-            .checked_sub(cry_state.last_state_recalculation_slot)
-            .ok_or(StateTransitionError::BlockSlotBeforeRecalcSlot)?;
-
-        if state_recalc_distance >= u64::from(self.config.cycle_length) {
-            panic!("Not implemented!")
+        parent_hash: &Hash256,
+    ) -> Result<BeaconState, StateTransitionError> {
+        let (pre_block_state, _) = self.pre_block_state(state, block.slot)?;
+        self.apply_block(&pre_block_state, block, parent_hash)
+    }
+
+    /// Returns the state to which a block at `slot` is applied, given the `state` of its parent.
+    ///
+    /// Should a cycle have ended at least a cycle before `slot` (see `cycle_transition_due`), this
+    /// is the `state` recalculated, with the validators which made deposits after the previously
+    /// processed PoW receipt root inducted. Otherwise it is the `state` itself.
+    ///
+    /// Also returns the validator set changes made by the recalculation (see
+    /// `recalculate_state`).
+    pub(crate) fn pre_block_state(
+        &self,
+        state: &Arc<BeaconState>,
+        slot: u64,
+    ) -> Result<(Arc<BeaconState>, Vec<(u64, Vec<ValidatorChangeRecord>)>), StateTransitionError>
+    {
+        if slot < state.last_state_recalculation_slot {
+            return Err(StateTransitionError::BlockSlotBeforeRecalcSlot);
+        }
+        if !cycle_transition_due(state, slot, self.config.cycle_length) {
+            return Ok((state.clone(), vec![]));
+        }
+
+        let (mut recalc_state, validator_changes) = recalculate_state(state, slot, &self.config)?;
+
+        /*
+         * If a new PoW receipt root was processed during the recalculation, induct the
+         * validators which made deposits after the previously processed receipt root.
+         */
+        if recalc_state.processed_pow_receipt_root != state.processed_pow_receipt_root {
+            let deposits = self.new_deposits(
+                &state.processed_pow_receipt_root,
+                &recalc_state.processed_pow_receipt_root,
+            )?;
+            let slot = recalc_state.last_state_recalculation_slot;
+            process_deposits(&mut recalc_state, &deposits, slot, &self.config);
+        }
+
+        Ok((Arc::new(recalc_state), validator_changes))
+    }
+
+    /// Apply the `block` to the state returned by `pre_block_state` for its slot.
+    ///
+    /// The proposer of the `block` is determined from the `pre_block_state`, as the state of its
+    /// parent does not assign a proposer to the slot of a block which follows a skipped cycle.
+    pub(crate) fn apply_block(
+        &self,
+        pre_block_state: &BeaconState,
+        block: &BeaconBlock,
+        parent_hash: &Hash256,
+    ) -> Result<BeaconState, StateTransitionError> {
+        let proposer_index = block_proposer_index(pre_block_state, block.slot)
+            .ok_or(StateTransitionError::NoBlockProposer)?;
+        per_block_transition(pre_block_state, block, parent_hash, proposer_index)
+    }
+
+    /// Returns the deposits which were made after the `previous_root` PoW receipt root, up to and
+    /// including the `root`.
+    ///
+    /// A zero `previous_root` indicates that no receipt root has been processed, in which case
+    /// all deposits up to the `root` are returned.
+    ///
+    /// Returns an error if the deposits for either root are unknown.
+    fn new_deposits(
+        &self,
+        previous_root: &Hash256,
+        root: &Hash256,
+    ) -> Result<Vec<ValidatorRegistration>, StateTransitionError> {
+        let pow_store = &self.store.pow_chain;
+
+        let processed_count = if previous_root.is_zero() {
+            0
         } else {
-            let new_act_state = extend_active_state(act_state, block, block_hash)?;
-            Ok((new_act_state, None))
+            pow_store
+                .get_deposits(&previous_root[..])
+                .map_err(pow_chain_store_error)?
+                .ok_or(StateTransitionError::UnknownPoWReceiptRoot)?
+                .len()
+        };
+        let deposits = pow_store
+            .get_deposits(&root[..])
+            .map_err(pow_chain_store_error)?
+            .ok_or(StateTransitionError::UnknownPoWReceiptRoot)?;
+
+        Ok(deposits.into_iter().skip(processed_count).collect())
+    }
+}
+
+fn pow_chain_store_error(e: PoWChainStoreError) -> StateTransitionError {
+    match e {
+        PoWChainStoreError::DBError(message) => StateTransitionError::DBError(message),
+        PoWChainStoreError::DecodeError => {
+            StateTransitionError::DBError("Unable to decode deposits.".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stores::test_utils::{test_chain as chain_from_config, test_config};
+    use super::*;
+    use db::MemoryDB;
+    use lmd_ghost::LmdGhost;
+    use types::{CandidatePoWReceiptRootRecord, ValidatorStatus};
+
+    fn test_chain() -> BeaconChain<MemoryDB, LmdGhost<MemoryDB>> {
+        let mut config = test_config();
+        config.pow_receipt_root_voting_period = 4;
+        chain_from_config(config)
+    }
+
+    /// The genesis state of the `chain`, where the `root` has received a majority of the votes
+    /// in the first voting period.
+    fn voted_state(
+        chain: &BeaconChain<MemoryDB, LmdGhost<MemoryDB>>,
+        root: &Hash256,
+    ) -> BeaconState {
+        let mut state = (**chain.states.values().next().unwrap()).clone();
+        state.candidate_pow_receipt_roots = vec![CandidatePoWReceiptRootRecord {
+            candidate_pow_receipt_root: *root,
+            votes: 3,
+        }];
+        state
+    }
+
+    #[test]
+    fn test_transition_inducts_deposits() {
+        let chain = test_chain();
+        let previous_root = Hash256::from("previous_root".as_bytes());
+        let root = Hash256::from("root".as_bytes());
+
+        let mut state = voted_state(&chain, &root);
+        state.processed_pow_receipt_root = previous_root;
+        let validator_count = state.validators.len();
+
+        /*
+         * The first deposit was inducted when the previous root was processed.
+         */
+        let deposits: Vec<ValidatorRegistration> =
+            (0..3).map(|_| ValidatorRegistration::random()).collect();
+        chain
+            .store
+            .pow_chain
+            .put_deposits(&previous_root[..], &deposits[..1])
+            .unwrap();
+        chain
+            .store
+            .pow_chain
+            .put_deposits(&root[..], &deposits)
+            .unwrap();
+
+        let mut block = BeaconBlock::zero();
+        block.slot = 8;
+        let new_state = chain
+            .transition_state(&Arc::new(state), &block, &Hash256::zero())
+            .unwrap();
+
+        assert_eq!(new_state.processed_pow_receipt_root, root);
+        assert_eq!(new_state.validators.len(), validator_count + 2);
+        for (validator, deposit) in new_state.validators[validator_count..]
+            .iter()
+            .zip(deposits[1..].iter())
+        {
+            assert_eq!(validator.pubkey, deposit.pubkey);
+            assert_eq!(validator.status, ValidatorStatus::PendingActivation as u8);
         }
     }
+
+    #[test]
+    fn test_transition_inducts_first_deposits() {
+        let chain = test_chain();
+        let root = Hash256::from("root".as_bytes());
+
+        let state = voted_state(&chain, &root);
+        let validator_count = state.validators.len();
+
+        /*
+         * No root has been processed, so every deposit is inducted.
+         */
+        let deposits: Vec<ValidatorRegistration> =
+            (0..2).map(|_| ValidatorRegistration::random()).collect();
+        chain
+            .store
+            .pow_chain
+            .put_deposits(&root[..], &deposits)
+            .unwrap();
+
+        let mut block = BeaconBlock::zero();
+        block.slot = 8;
+        let new_state = chain
+            .transition_state(&Arc::new(state), &block, &Hash256::zero())
+            .unwrap();
+
+        assert_eq!(new_state.validators.len(), validator_count + 2);
+    }
+
+    #[test]
+    fn test_transition_unknown_pow_receipt_root() {
+        let chain = test_chain();
+        let state = voted_state(&chain, &Hash256::from("root".as_bytes()));
+
+        let mut block = BeaconBlock::zero();
+        block.slot = 8;
+
+        assert_eq!(
+            chain.transition_state(&Arc::new(state), &block, &Hash256::zero()),
+            Err(StateTransitionError::UnknownPoWReceiptRoot)
+        );
+    }
+
+    #[test]
+    fn test_transition_unknown_previous_pow_receipt_root() {
+        let chain = test_chain();
+        let root = Hash256::from("root".as_bytes());
+
+        let mut state = voted_state(&chain, &root);
+        state.processed_pow_receipt_root = Hash256::from("previous_root".as_bytes());
+
+        /*
+         * The deposits already inducted are unknown, so the new deposits cannot be determined.
+         */
+        chain
+            .store
+            .pow_chain
+            .put_deposits(&root[..], &[ValidatorRegistration::random()])
+            .unwrap();
+
+        let mut block = BeaconBlock::zero();
+        block.slot = 8;
+
+        assert_eq!(
+            chain.transition_state(&Arc::new(state), &block, &Hash256::zero()),
+            Err(StateTransitionError::UnknownPoWReceiptRoot)
+        );
+    }
 }
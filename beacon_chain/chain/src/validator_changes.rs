@@ -0,0 +1,285 @@
+use super::BeaconChain;
+use db::stores::BeaconStateStoreError;
+use db::{ClientDB, DBError};
+use fork_choice::ForkChoice;
+use ssz_helpers::ssz_beacon_block::SszBeaconBlock;
+use types::Hash256;
+use validator_change::ValidatorChangeRecord;
+
+#[derive(Debug, PartialEq)]
+pub enum ValidatorChangesError {
+    InvalidSlotRange,
+    UnknownBlock,
+    DecodeError,
+    DBError(String),
+}
+
+impl<T, F> BeaconChain<T, F>
+where
+    T: ClientDB + Sized,
+    F: ForkChoice,
+{
+    /// Returns the changes made to the validator set by the canonical chain after `from_slot`,
+    /// up to and including `to_slot`, in the order in which they were appended to the
+    /// `validator_set_delta_hash_chain`.
+    ///
+    /// A light client which trusts the validator set (and hash chain) of the canonical chain at
+    /// `from_slot` may use these records to follow the validator set to `to_slot`.
+    ///
+    /// The changes are stored alongside the state of each block whose cycle transition made them.
+    pub fn validator_changes(
+        &self,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<ValidatorChangeRecord>, ValidatorChangesError> {
+        if from_slot > to_slot {
+            return Err(ValidatorChangesError::InvalidSlotRange);
+        }
+
+        /*
+         * Walk back from the canonical head, collecting the state root of each block after
+         * `from_slot`. A change is made by the first block of a later cycle, so a block after
+         * `to_slot` may still make a change within the range.
+         */
+        let mut state_roots = vec![];
+        let mut block_hash = self.canonical_block_hash();
+        loop {
+            let ssz = self
+                .store
+                .block
+                .get_serialized_block(&block_hash[..])?
+                .ok_or(ValidatorChangesError::UnknownBlock)?;
+            let block =
+                SszBeaconBlock::from_slice(&ssz).map_err(|_| ValidatorChangesError::DecodeError)?;
+            if block.slot() <= from_slot {
+                break;
+            }
+            state_roots.push(Hash256::from(block.state_root()));
+            block_hash = Hash256::from(
+                block
+                    .parent_hash()
+                    .ok_or(ValidatorChangesError::UnknownBlock)?,
+            );
+        }
+        state_roots.reverse();
+
+        /*
+         * Collect the changes stored for each state which were made within the range.
+         */
+        let mut records = vec![];
+        for root in &state_roots {
+            for (change_slot, mut changes) in self.store.state.get_validator_changes(root)? {
+                if change_slot > from_slot && change_slot <= to_slot {
+                    records.append(&mut changes);
+                }
+            }
+        }
+
+        Ok(records)
+    }
+}
+
+impl From<DBError> for ValidatorChangesError {
+    fn from(e: DBError) -> Self {
+        ValidatorChangesError::DBError(e.message)
+    }
+}
+
+impl From<BeaconStateStoreError> for ValidatorChangesError {
+    fn from(e: BeaconStateStoreError) -> Self {
+        match e {
+            BeaconStateStoreError::DBError(s) => ValidatorChangesError::DBError(s),
+            BeaconStateStoreError::DecodeError => ValidatorChangesError::DecodeError,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bls;
+
+    use self::bls::{Keypair, Signature};
+    use super::super::genesis::genesis_block;
+    use super::super::stores::test_utils::{test_chain, test_config};
+    use super::*;
+    use db::MemoryDB;
+    use lmd_ghost::LmdGhost;
+    use ssz::ssz_encode;
+    use std::sync::Arc;
+    use types::{
+        AttestationRecord, BeaconState, Bitfield, LogoutSpecial, SpecialRecord, ValidatorStatus,
+        LOGOUT_MESSAGE,
+    };
+    use validator_change::light_client::LightClientValidatorSet;
+    use validator_change::VALIDATOR_FLAG_EXIT;
+
+    /// Apply a block at `slot` with the given `specials` to the `parent_state`, store it upon the
+    /// canonical head and make it the canonical head.
+    ///
+    /// The block includes an attestation by every member of each committee assigned to the
+    /// preceding slot. Any validator set changes made by the cycle transition are stored, as they
+    /// are when a block is processed.
+    ///
+    /// Returns the state of the new block.
+    fn add_block(
+        chain: &mut BeaconChain<MemoryDB, LmdGhost<MemoryDB>>,
+        parent_state: &BeaconState,
+        slot: u64,
+        specials: Vec<SpecialRecord>,
+    ) -> BeaconState {
+        let parent_hash = chain.canonical_block_hash();
+        let (pre_block_state, validator_changes) = chain
+            .pre_block_state(&Arc::new(parent_state.clone()), slot)
+            .unwrap();
+
+        let attestation_slot = slot - 1;
+        let i = (attestation_slot - pre_block_state.last_state_recalculation_slot) as usize;
+        let attestations = pre_block_state.shard_and_committee_for_slots[i]
+            .iter()
+            .map(|sac| {
+                let mut a = AttestationRecord::zero();
+                a.slot = attestation_slot;
+                a.shard_id = sac.shard;
+                a.attester_bitfield = Bitfield::from_elem(sac.committee.len(), true);
+                a
+            }).collect();
+
+        let mut block = genesis_block(&Hash256::zero());
+        block.slot = slot;
+        block.ancestor_hashes[0] = parent_hash;
+        block.attestations = attestations;
+        block.specials = specials;
+        let state = chain
+            .apply_block(&pre_block_state, &block, &parent_hash)
+            .unwrap();
+        block.state_root = state.canonical_root();
+        chain.insert_state(block.state_root, state.clone()).unwrap();
+        chain
+            .store
+            .state
+            .put_validator_changes(&block.state_root, &validator_changes)
+            .unwrap();
+
+        let block_hash = Hash256::from(slot);
+        chain
+            .store
+            .block
+            .put_serialized_block(&block_hash[..], &ssz_encode(&block))
+            .unwrap();
+
+        chain.head_block_hashes = vec![block_hash];
+        chain.canonical_head_block_hash = 0;
+        state
+    }
+
+    /// A logout by validator 0, which is included in a block without being validated.
+    fn logout_specials() -> Vec<SpecialRecord> {
+        let logout = LogoutSpecial {
+            validator_index: 0,
+            signature: Signature::new(LOGOUT_MESSAGE, &Keypair::random().sk),
+        };
+        vec![SpecialRecord::logout(&ssz_encode(&logout))]
+    }
+
+    #[test]
+    fn test_validator_changes() {
+        let mut chain = test_chain(test_config());
+        chain.config.max_validator_churn_quotient = 1;
+        let genesis_state = (**chain.states.values().next().unwrap()).clone();
+
+        /*
+         * A slot has been finalized and every shard crosslinked since genesis, so a validator
+         * set change is due at the next recalculation.
+         */
+        let mut due_state = genesis_state.clone();
+        due_state.last_finalized_slot = 1;
+        for crosslink in due_state.crosslinks.iter_mut() {
+            crosslink.slot = 1;
+        }
+
+        /*
+         * Validator 0 logs out at slot 2. The logout is applied by the recalculation at slot 4,
+         * which then exits the validator during the validator set change. The recalculation is
+         * performed once a further cycle has passed, by the block at slot 8.
+         */
+        let logout_state = add_block(&mut chain, &due_state, 2, logout_specials());
+        let exit_state = add_block(&mut chain, &logout_state, 8, vec![]);
+        assert_eq!(
+            logout_state.validators[0].status,
+            ValidatorStatus::Active as u8
+        );
+        assert_eq!(
+            exit_state.validators[0].status,
+            ValidatorStatus::PendingWithdraw as u8
+        );
+
+        let exit = ValidatorChangeRecord {
+            index: 0,
+            pubkey: genesis_state.validators[0].pubkey.clone(),
+            flag: VALIDATOR_FLAG_EXIT,
+        };
+        assert_eq!(chain.validator_changes(0, 4), Ok(vec![exit.clone()]));
+        assert_eq!(chain.validator_changes(2, 8), Ok(vec![exit]));
+        assert_eq!(chain.validator_changes(0, 3), Ok(vec![]));
+        assert_eq!(chain.validator_changes(4, 8), Ok(vec![]));
+        assert_eq!(
+            chain.validator_changes(5, 4),
+            Err(ValidatorChangesError::InvalidSlotRange)
+        );
+    }
+
+    #[test]
+    fn test_validator_changes_after_finalization() {
+        let mut config = test_config();
+        config.min_committee_size = 2;
+        config.max_validator_churn_quotient = 1;
+        let mut chain = test_chain(config);
+        let genesis_state = (**chain.states.values().next().unwrap()).clone();
+
+        /*
+         * Every committee attests to every slot and validator 0 logs out at slot 2.
+         *
+         * The first cycle is justified at slot 8, which applies the logout. The second cycle is
+         * justified at slot 12, which finalizes slot 2 and crosslinks every shard. A validator
+         * set change is then due, which exits validator 0.
+         */
+        let mut state = genesis_state.clone();
+        for slot in 1..13 {
+            let specials = if slot == 2 { logout_specials() } else { vec![] };
+            state = add_block(&mut chain, &state, slot, specials);
+        }
+        assert_eq!(state.last_finalized_slot, 2);
+        assert_eq!(state.validator_set_change_slot, 8);
+        assert_eq!(
+            state.validators[0].status,
+            ValidatorStatus::PendingWithdraw as u8
+        );
+
+        /*
+         * A light client which trusts the genesis validator set follows the change to the
+         * validator set of the head.
+         */
+        let records = chain.validator_changes(0, 12).unwrap();
+        assert_eq!(
+            records,
+            vec![ValidatorChangeRecord {
+                index: 0,
+                pubkey: genesis_state.validators[0].pubkey.clone(),
+                flag: VALIDATOR_FLAG_EXIT,
+            }]
+        );
+
+        let mut light_client = LightClientValidatorSet::from_validator_records(
+            &genesis_state.validators,
+            genesis_state.validator_set_delta_hash_chain,
+        );
+        light_client
+            .apply_changes(&records, &state.validator_set_delta_hash_chain)
+            .unwrap();
+        assert!(!light_client.validators.contains_key(&0));
+        assert_eq!(
+            light_client.validators.len(),
+            genesis_state.validators.len() - 1
+        );
+    }
+}
@@ -0,0 +1,221 @@
+extern crate db;
+extern crate ssz;
+extern crate ssz_helpers;
+extern crate types;
+
+use db::stores::BeaconBlockStore;
+use db::{ClientDB, DBError};
+use ssz::DecodeError;
+use ssz_helpers::ssz_beacon_block::{SszBeaconBlock, SszBeaconBlockError};
+use types::{Hash256, ValidatorRecord};
+
+#[derive(Debug, PartialEq)]
+pub enum ForkChoiceError {
+    BadSszInDatabase,
+    MissingBlock,
+    DBError(String),
+}
+
+/// A rule for choosing the canonical head of the block tree.
+///
+/// Implementations are informed of each new block and attestation and may be queried for the
+/// present head at any time.
+pub trait ForkChoice {
+    /// Add a block with `block_hash` at `slot` to the block tree, as a child of `parent_hash`.
+    fn add_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+        slot: u64,
+    ) -> Result<(), ForkChoiceError>;
+
+    /// Remove the block with `block_hash`, a child of `parent_hash`, from the block tree.
+    ///
+    /// This undoes `add_block` for a block which could not be imported. The block must have no
+    /// children.
+    fn remove_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+    ) -> Result<(), ForkChoiceError>;
+
+    /// Record an attestation by the validator with `validator_index` to the block with
+    /// `target_hash` at `target_slot`.
+    fn add_attestation(
+        &mut self,
+        validator_index: usize,
+        target_slot: u64,
+        target_hash: &Hash256,
+    ) -> Result<(), ForkChoiceError>;
+
+    /// Find the hash of the head block, given the last justified block and the present set of
+    /// validators.
+    fn find_head(
+        &self,
+        justified_block_hash: &Hash256,
+        justified_slot: u64,
+        validators: &[ValidatorRecord],
+    ) -> Result<Hash256, ForkChoiceError>;
+}
+
+/// Add a block to a list of head block hashes, replacing its parent if the parent was a head.
+///
+/// This is useful to implementations of `ForkChoice` which track the tips of the block tree.
+pub fn update_head_block_hashes(
+    head_block_hashes: &mut Vec<Hash256>,
+    block_hash: &Hash256,
+    parent_hash: &Hash256,
+) {
+    match head_block_hashes.iter().position(|x| x == parent_hash) {
+        Some(i) => head_block_hashes[i] = *block_hash,
+        None => head_block_hashes.push(*block_hash),
+    }
+}
+
+/// Remove a block, which has no children, from a list of head block hashes, undoing
+/// `update_head_block_hashes`.
+///
+/// The parent takes the place of the block unless some other head descends from the parent, in
+/// which case the parent was not a head before the block was added. A head which is missing from
+/// the database is assumed not to descend from the parent.
+pub fn remove_head_block_hash<T>(
+    head_block_hashes: &mut Vec<Hash256>,
+    block_hash: &Hash256,
+    parent_hash: &Hash256,
+    block_store: &BeaconBlockStore<T>,
+) -> Result<(), ForkChoiceError>
+where
+    T: ClientDB + Sized,
+{
+    let i = match head_block_hashes.iter().position(|x| x == block_hash) {
+        Some(i) => i,
+        None => return Ok(()),
+    };
+    head_block_hashes.remove(i);
+
+    let parent_slot = {
+        let ssz = block_store
+            .get_serialized_block(&parent_hash[..])?
+            .ok_or(ForkChoiceError::MissingBlock)?;
+        SszBeaconBlock::from_slice(&ssz)?.slot()
+    };
+    for head_block_hash in head_block_hashes.iter() {
+        /*
+         * Walk back from the head until either the parent is found or the slot of the parent is
+         * reached.
+         */
+        let mut hash = *head_block_hash;
+        loop {
+            if hash == *parent_hash {
+                return Ok(());
+            }
+            let ssz = match block_store.get_serialized_block(&hash[..])? {
+                Some(ssz) => ssz,
+                None => break,
+            };
+            let block = SszBeaconBlock::from_slice(&ssz)?;
+            match block.parent_hash() {
+                Some(next_hash) if block.slot() > parent_slot => hash = Hash256::from(next_hash),
+                _ => break,
+            }
+        }
+    }
+
+    head_block_hashes.insert(i, *parent_hash);
+    Ok(())
+}
+
+impl From<DBError> for ForkChoiceError {
+    fn from(e: DBError) -> Self {
+        ForkChoiceError::DBError(e.message)
+    }
+}
+
+impl From<DecodeError> for ForkChoiceError {
+    fn from(_: DecodeError) -> Self {
+        ForkChoiceError::BadSszInDatabase
+    }
+}
+
+impl From<SszBeaconBlockError> for ForkChoiceError {
+    fn from(_: SszBeaconBlockError) -> Self {
+        ForkChoiceError::BadSszInDatabase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::MemoryDB;
+    use ssz::SszStream;
+    use std::sync::Arc;
+    use types::BeaconBlock;
+
+    /// Store a block with the given `slot` and `parent` under the key `hash`.
+    fn store_block(
+        store: &BeaconBlockStore<MemoryDB>,
+        hash: &Hash256,
+        parent: &Hash256,
+        slot: u64,
+    ) {
+        let mut block = BeaconBlock::zero();
+        block.slot = slot;
+        block.ancestor_hashes.push(*parent);
+        let mut s = SszStream::new();
+        s.append(&block);
+        store.put_serialized_block(&hash[..], &s.drain()).unwrap();
+    }
+
+    #[test]
+    fn test_update_head_block_hashes() {
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+        let c = Hash256::from("c".as_bytes());
+        let d = Hash256::from("d".as_bytes());
+
+        let mut heads = vec![a];
+
+        // `b` extends `a`.
+        update_head_block_hashes(&mut heads, &b, &a);
+        assert_eq!(heads, vec![b]);
+
+        // `c` forks from `a`.
+        update_head_block_hashes(&mut heads, &c, &a);
+        assert_eq!(heads, vec![b, c]);
+
+        // `d` extends `c`.
+        update_head_block_hashes(&mut heads, &d, &c);
+        assert_eq!(heads, vec![b, d]);
+    }
+
+    #[test]
+    fn test_remove_head_block_hash() {
+        let genesis = Hash256::from("genesis".as_bytes());
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+        let c = Hash256::from("c".as_bytes());
+
+        let store = BeaconBlockStore::new(Arc::new(MemoryDB::open()));
+        store_block(&store, &a, &genesis, 1);
+        store_block(&store, &b, &a, 2);
+        store_block(&store, &c, &a, 3);
+
+        let mut heads = vec![a];
+
+        // Removing `b` restores its parent `a`.
+        update_head_block_hashes(&mut heads, &b, &a);
+        remove_head_block_hash(&mut heads, &b, &a, &store).unwrap();
+        assert_eq!(heads, vec![a]);
+
+        // `a` was not a head before `c` was added, as `b` descends from it.
+        update_head_block_hashes(&mut heads, &b, &a);
+        update_head_block_hashes(&mut heads, &c, &a);
+        assert_eq!(heads, vec![b, c]);
+        remove_head_block_hash(&mut heads, &c, &a, &store).unwrap();
+        assert_eq!(heads, vec![b]);
+
+        // A block which is not a head is ignored.
+        remove_head_block_hash(&mut heads, &c, &a, &store).unwrap();
+        assert_eq!(heads, vec![b]);
+    }
+}
@@ -0,0 +1,414 @@
+extern crate db;
+extern crate fork_choice;
+extern crate ssz;
+extern crate ssz_helpers;
+extern crate types;
+
+use db::stores::BeaconBlockStore;
+use db::ClientDB;
+use fork_choice::{remove_head_block_hash, update_head_block_hashes, ForkChoice, ForkChoiceError};
+use ssz_helpers::ssz_beacon_block::SszBeaconBlock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{Hash256, ValidatorRecord};
+
+/// An implementation of the "Latest Message Driven Greedy Heaviest Observed SubTree" (LMD-GHOST)
+/// fork choice rule.
+///
+/// The latest attestation target of each validator is tracked. When finding the head, the block
+/// tree is walked from the last justified block, at each fork choosing the child with the greatest
+/// total balance of validators whose latest attestation target is that child or a descendant of
+/// it.
+pub struct LmdGhost<T>
+where
+    T: ClientDB + Sized,
+{
+    block_store: Arc<BeaconBlockStore<T>>,
+    /// The tips of the block tree.
+    head_block_hashes: Vec<Hash256>,
+    /// A map of validator index to the slot and hash of the latest block they attested to.
+    latest_attestation_targets: HashMap<usize, (u64, Hash256)>,
+}
+
+impl<T> LmdGhost<T>
+where
+    T: ClientDB + Sized,
+{
+    pub fn new(block_store: Arc<BeaconBlockStore<T>>) -> Self {
+        Self {
+            block_store,
+            head_block_hashes: vec![],
+            latest_attestation_targets: HashMap::new(),
+        }
+    }
+
+    /// Returns the hash of the latest block attested to by the validator with `validator_index`,
+    /// if any.
+    pub fn latest_attestation_target(&self, validator_index: usize) -> Option<&Hash256> {
+        self.latest_attestation_targets
+            .get(&validator_index)
+            .map(|(_, hash)| hash)
+    }
+
+    /// Build a map of block hash to parent hash for all blocks which are descendants of the
+    /// justified block and ancestors of (or equal to) one of the known heads.
+    fn block_tree(
+        &self,
+        justified_block_hash: &Hash256,
+        justified_slot: u64,
+    ) -> Result<HashMap<Hash256, Hash256>, ForkChoiceError> {
+        let mut parents = HashMap::new();
+
+        for head_block_hash in &self.head_block_hashes {
+            let mut path = vec![];
+            let mut block_hash = *head_block_hash;
+
+            /*
+             * Walk back from the head until either the justified block or a block which is already
+             * in the tree is found. If the walk passes the justified slot, the head does not
+             * descend from the justified block and is ignored.
+             */
+            let connected = loop {
+                if block_hash == *justified_block_hash || parents.contains_key(&block_hash) {
+                    break true;
+                }
+                let ssz = self
+                    .block_store
+                    .get_serialized_block(&block_hash[..])?
+                    .ok_or(ForkChoiceError::MissingBlock)?;
+                let block = SszBeaconBlock::from_slice(&ssz)?;
+                if block.slot() <= justified_slot {
+                    break false;
+                }
+                match block.parent_hash() {
+                    Some(parent_hash) => {
+                        let parent_hash = Hash256::from(parent_hash);
+                        path.push((block_hash, parent_hash));
+                        block_hash = parent_hash;
+                    }
+                    None => break false,
+                }
+            };
+
+            if connected {
+                parents.extend(path);
+            }
+        }
+
+        Ok(parents)
+    }
+}
+
+impl<T> ForkChoice for LmdGhost<T>
+where
+    T: ClientDB + Sized,
+{
+    /// Add a block to the tips of the block tree.
+    ///
+    /// The block itself is read from the database when finding the head.
+    fn add_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+        _slot: u64,
+    ) -> Result<(), ForkChoiceError> {
+        update_head_block_hashes(&mut self.head_block_hashes, block_hash, parent_hash);
+        Ok(())
+    }
+
+    /// Remove a block from the tips of the block tree, restoring its parent if the parent was a
+    /// tip before the block was added.
+    ///
+    /// Attestations are kept, as they target ancestors of the block rather than the block itself.
+    fn remove_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+    ) -> Result<(), ForkChoiceError> {
+        remove_head_block_hash(
+            &mut self.head_block_hashes,
+            block_hash,
+            parent_hash,
+            &self.block_store,
+        )
+    }
+
+    /// Record an attestation by the validator with `validator_index` to the block with
+    /// `target_hash` at `target_slot`.
+    ///
+    /// The attestation is ignored if the validator has already attested to a block at the same or
+    /// a later slot.
+    fn add_attestation(
+        &mut self,
+        validator_index: usize,
+        target_slot: u64,
+        target_hash: &Hash256,
+    ) -> Result<(), ForkChoiceError> {
+        let is_latest = match self.latest_attestation_targets.get(&validator_index) {
+            Some((slot, _)) => target_slot > *slot,
+            None => true,
+        };
+        if is_latest {
+            self.latest_attestation_targets
+                .insert(validator_index, (target_slot, *target_hash));
+        }
+        Ok(())
+    }
+
+    /// Find the head of the chain, starting at the last justified block.
+    ///
+    /// Only blocks which are ancestors of one of the known heads and descendants of the justified
+    /// block are considered. Votes are weighted by the balances in `validators`.
+    ///
+    /// If no blocks descend from the justified block, the justified block hash is returned.
+    fn find_head(
+        &self,
+        justified_block_hash: &Hash256,
+        justified_slot: u64,
+        validators: &[ValidatorRecord],
+    ) -> Result<Hash256, ForkChoiceError> {
+        let parents = self.block_tree(justified_block_hash, justified_slot)?;
+
+        let mut children: HashMap<Hash256, Vec<Hash256>> = HashMap::new();
+        for (child, parent) in &parents {
+            children
+                .entry(*parent)
+                .or_insert_with(|| vec![])
+                .push(*child);
+        }
+
+        /*
+         * Add the balance of each validator to their latest attestation target and all of its
+         * ancestors, back to (but excluding) the justified block.
+         *
+         * Targets outside of the block tree are ignored.
+         */
+        let mut weights: HashMap<Hash256, u64> = HashMap::new();
+        for (validator_index, (_, target)) in &self.latest_attestation_targets {
+            let balance = match validators.get(*validator_index) {
+                Some(validator) => validator.balance,
+                None => continue,
+            };
+            let mut block_hash = *target;
+            while let Some(parent) = parents.get(&block_hash) {
+                let weight = weights.entry(block_hash).or_insert(0);
+                *weight = weight.saturating_add(balance);
+                block_hash = *parent;
+            }
+        }
+
+        /*
+         * Walk down the tree from the justified block, choosing the heaviest child at each step.
+         *
+         * Ties are broken in favour of the lowest block hash.
+         */
+        let mut head = *justified_block_hash;
+        let weight = |hash: &Hash256| *weights.get(hash).unwrap_or(&0);
+        while let Some(head_children) = children.get(&head) {
+            head = *head_children
+                .iter()
+                .max_by(|a, b| weight(*a).cmp(&weight(*b)).then_with(|| b.cmp(a)))
+                .ok_or(ForkChoiceError::MissingBlock)?;
+        }
+
+        Ok(head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::MemoryDB;
+    use ssz::SszStream;
+    use types::BeaconBlock;
+
+    /// Store a block with the given `slot` and `parent` under the key `hash`.
+    fn store_block(
+        store: &BeaconBlockStore<MemoryDB>,
+        hash: &Hash256,
+        parent: &Hash256,
+        slot: u64,
+    ) {
+        let mut block = BeaconBlock::zero();
+        block.slot = slot;
+        block.ancestor_hashes.push(*parent);
+        let mut s = SszStream::new();
+        s.append(&block);
+        store.put_serialized_block(&hash[..], &s.drain()).unwrap();
+    }
+
+    fn validators(balances: &[u64]) -> Vec<ValidatorRecord> {
+        balances
+            .iter()
+            .map(|balance| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.balance = *balance;
+                v
+            })
+            .collect()
+    }
+
+    fn hash(s: &str) -> Hash256 {
+        Hash256::from(s.as_bytes())
+    }
+
+    /// Store each `(hash, parent, slot)` block in a new database and add it to a new `LmdGhost`.
+    fn lmd_ghost(blocks: &[(&str, &str, u64)]) -> LmdGhost<MemoryDB> {
+        let db = Arc::new(MemoryDB::open());
+        let store = Arc::new(BeaconBlockStore::new(db));
+        let mut fork_choice = LmdGhost::new(store.clone());
+        for (block_hash, parent_hash, slot) in blocks {
+            store_block(&store, &hash(block_hash), &hash(parent_hash), *slot);
+            fork_choice
+                .add_block(&hash(block_hash), &hash(parent_hash), *slot)
+                .unwrap();
+        }
+        fork_choice
+    }
+
+    /// Build the following block tree, where `genesis` is not stored in the database:
+    ///
+    /// ```text
+    /// genesis (0) -> a (1) -> b (2)
+    ///                  \
+    ///                   -> c (2) -> d (3)
+    /// ```
+    fn forked_tree() -> LmdGhost<MemoryDB> {
+        lmd_ghost(&[
+            ("a", "genesis", 1),
+            ("b", "a", 2),
+            ("c", "a", 2),
+            ("d", "c", 3),
+        ])
+    }
+
+    #[test]
+    fn test_lmd_ghost_single_chain() {
+        let fork_choice = lmd_ghost(&[("a", "genesis", 1), ("c", "a", 2), ("d", "c", 3)]);
+
+        let head = fork_choice
+            .find_head(&hash("genesis"), 0, &validators(&[]))
+            .unwrap();
+
+        assert_eq!(head, hash("d"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_heaviest_fork_wins() {
+        let mut fork_choice = forked_tree();
+        let validators = validators(&[10, 10, 10, 10]);
+
+        fork_choice.add_attestation(0, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(1, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(2, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(3, 3, &hash("d")).unwrap();
+
+        let head = fork_choice
+            .find_head(&hash("genesis"), 0, &validators)
+            .unwrap();
+
+        // `b` wins, even though `d` has a higher slot.
+        assert_eq!(head, hash("b"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_weighted_by_balance() {
+        let mut fork_choice = forked_tree();
+        let validators = validators(&[10, 10, 100]);
+
+        fork_choice.add_attestation(0, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(1, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(2, 2, &hash("c")).unwrap();
+
+        let head = fork_choice
+            .find_head(&hash("genesis"), 0, &validators)
+            .unwrap();
+
+        // A vote for `c` is also a vote for its descendant `d`.
+        assert_eq!(head, hash("d"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_only_latest_attestation_counts() {
+        let mut fork_choice = forked_tree();
+        let validators = validators(&[10, 10, 10]);
+
+        fork_choice.add_attestation(0, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(1, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(2, 2, &hash("c")).unwrap();
+        // Validator 1 switches to `d`, then an older attestation to `a` is ignored.
+        fork_choice.add_attestation(1, 3, &hash("d")).unwrap();
+        fork_choice.add_attestation(1, 1, &hash("a")).unwrap();
+
+        assert_eq!(fork_choice.latest_attestation_target(1), Some(&hash("d")));
+
+        let head = fork_choice
+            .find_head(&hash("genesis"), 0, &validators)
+            .unwrap();
+
+        assert_eq!(head, hash("d"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_tie_break_lowest_hash() {
+        let fork_choice = forked_tree();
+
+        let head = fork_choice
+            .find_head(&hash("genesis"), 0, &validators(&[]))
+            .unwrap();
+
+        // `b` and `c` have no votes, `b` is the lowest hash.
+        assert!(hash("b") < hash("c"));
+        assert_eq!(head, hash("b"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_starts_from_justified_block() {
+        let mut fork_choice = forked_tree();
+        let validators = validators(&[10, 10]);
+
+        fork_choice.add_attestation(0, 2, &hash("b")).unwrap();
+        fork_choice.add_attestation(1, 2, &hash("b")).unwrap();
+
+        let head = fork_choice.find_head(&hash("c"), 2, &validators).unwrap();
+
+        // `b` does not descend from the justified block `c`.
+        assert_eq!(head, hash("d"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_no_descendants_of_justified_block() {
+        let fork_choice = forked_tree();
+
+        let head = fork_choice
+            .find_head(&hash("b"), 2, &validators(&[]))
+            .unwrap();
+
+        assert_eq!(head, hash("b"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_remove_block() {
+        let mut fork_choice = forked_tree();
+
+        fork_choice.remove_block(&hash("d"), &hash("c")).unwrap();
+        fork_choice.remove_block(&hash("b"), &hash("a")).unwrap();
+
+        let head = fork_choice
+            .find_head(&hash("genesis"), 0, &validators(&[]))
+            .unwrap();
+
+        // `c` is the only remaining tip.
+        assert_eq!(head, hash("c"));
+    }
+
+    #[test]
+    fn test_lmd_ghost_missing_head_block() {
+        let mut fork_choice = forked_tree();
+        fork_choice.add_block(&hash("e"), &hash("d"), 4).unwrap();
+
+        let result = fork_choice.find_head(&hash("genesis"), 0, &validators(&[]));
+
+        assert_eq!(result, Err(ForkChoiceError::MissingBlock));
+    }
+}
@@ -1,18 +1,16 @@
 extern crate db;
+extern crate fork_choice;
 extern crate ssz;
 extern crate types;
 
 use db::stores::BeaconBlockStore;
-use db::{ClientDB, DBError};
-use ssz::{Decodable, DecodeError};
+use db::ClientDB;
+use fork_choice::{remove_head_block_hash, update_head_block_hashes, ForkChoice};
+use ssz::Decodable;
 use std::sync::Arc;
-use types::{BeaconBlock, Hash256};
+use types::{BeaconBlock, Hash256, ValidatorRecord};
 
-pub enum ForkChoiceError {
-    BadSszInDatabase,
-    MissingBlock,
-    DBError(String),
-}
+pub use fork_choice::ForkChoiceError;
 
 pub fn naive_fork_choice<T>(
     head_block_hashes: &Vec<Hash256>,
@@ -37,11 +35,11 @@ where
     /*
      * Loop through all the head blocks and find the highest slot.
      */
-    let highest_slot: Option<u64> = None;
+    let mut highest_slot: Option<u64> = None;
     for (_, block) in &head_blocks {
         let slot = block.slot;
 
-        match highest_slot {
+        highest_slot = match highest_slot {
             None => Some(slot),
             Some(winning_slot) => {
                 if slot > winning_slot {
@@ -76,22 +74,142 @@ where
     }
 }
 
-impl From<DecodeError> for ForkChoiceError {
-    fn from(_: DecodeError) -> Self {
-        ForkChoiceError::BadSszInDatabase
+/// A `ForkChoice` which selects the head with the highest slot, ignoring attestations.
+///
+/// See `naive_fork_choice`.
+pub struct LongestChain<T>
+where
+    T: ClientDB + Sized,
+{
+    block_store: Arc<BeaconBlockStore<T>>,
+    /// The tips of the block tree.
+    head_block_hashes: Vec<Hash256>,
+}
+
+impl<T> LongestChain<T>
+where
+    T: ClientDB + Sized,
+{
+    pub fn new(block_store: Arc<BeaconBlockStore<T>>) -> Self {
+        Self {
+            block_store,
+            head_block_hashes: vec![],
+        }
     }
 }
 
-impl From<DBError> for ForkChoiceError {
-    fn from(e: DBError) -> Self {
-        ForkChoiceError::DBError(e.message)
+impl<T> ForkChoice for LongestChain<T>
+where
+    T: ClientDB + Sized,
+{
+    fn add_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+        _slot: u64,
+    ) -> Result<(), ForkChoiceError> {
+        update_head_block_hashes(&mut self.head_block_hashes, block_hash, parent_hash);
+        Ok(())
+    }
+
+    fn remove_block(
+        &mut self,
+        block_hash: &Hash256,
+        parent_hash: &Hash256,
+    ) -> Result<(), ForkChoiceError> {
+        remove_head_block_hash(
+            &mut self.head_block_hashes,
+            block_hash,
+            parent_hash,
+            &self.block_store,
+        )
+    }
+
+    fn add_attestation(
+        &mut self,
+        _validator_index: usize,
+        _target_slot: u64,
+        _target_hash: &Hash256,
+    ) -> Result<(), ForkChoiceError> {
+        Ok(())
+    }
+
+    /// Find the head with the highest slot. The justified block and validators are ignored.
+    fn find_head(
+        &self,
+        justified_block_hash: &Hash256,
+        _justified_slot: u64,
+        _validators: &[ValidatorRecord],
+    ) -> Result<Hash256, ForkChoiceError> {
+        match naive_fork_choice(&self.head_block_hashes, self.block_store.clone())? {
+            Some(i) => Ok(self.head_block_hashes[i]),
+            None => Ok(*justified_block_hash),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use db::MemoryDB;
+    use ssz::SszStream;
+
+    fn store_block(
+        store: &BeaconBlockStore<MemoryDB>,
+        hash: &Hash256,
+        parent: &Hash256,
+        slot: u64,
+    ) {
+        let mut block = BeaconBlock::zero();
+        block.slot = slot;
+        block.ancestor_hashes.push(*parent);
+        let mut s = SszStream::new();
+        s.append(&block);
+        store.put_serialized_block(&hash[..], &s.drain()).unwrap();
+    }
+
     #[test]
     fn test_naive_fork_choice() {
-        assert_eq!(2 + 2, 4);
+        let db = Arc::new(MemoryDB::open());
+        let store = Arc::new(BeaconBlockStore::new(db));
+
+        let genesis = Hash256::from("genesis".as_bytes());
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+        let c = Hash256::from("c".as_bytes());
+        let d = Hash256::from("d".as_bytes());
+
+        store_block(&store, &a, &genesis, 1);
+        store_block(&store, &b, &a, 3);
+        store_block(&store, &c, &a, 2);
+        store_block(&store, &d, &a, 3);
+
+        assert_eq!(naive_fork_choice(&vec![b, c], store.clone()), Ok(Some(0)));
+        assert_eq!(naive_fork_choice(&vec![c, b], store.clone()), Ok(Some(1)));
+        // `b` and `d` have the same slot, the lowest hash wins.
+        assert_eq!(naive_fork_choice(&vec![d, b], store.clone()), Ok(Some(1)));
+        assert_eq!(naive_fork_choice(&vec![], store.clone()), Ok(None));
+    }
+
+    #[test]
+    fn test_longest_chain() {
+        let db = Arc::new(MemoryDB::open());
+        let store = Arc::new(BeaconBlockStore::new(db));
+        let mut fork_choice = LongestChain::new(store.clone());
+
+        let genesis = Hash256::from("genesis".as_bytes());
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+        let c = Hash256::from("c".as_bytes());
+
+        assert_eq!(fork_choice.find_head(&genesis, 0, &[]), Ok(genesis));
+
+        for (hash, parent, slot) in &[(a, genesis, 1), (b, a, 2), (c, a, 3)] {
+            store_block(&store, hash, parent, *slot);
+            fork_choice.add_block(hash, parent, *slot).unwrap();
+        }
+        fork_choice.add_attestation(0, 2, &b).unwrap();
+
+        assert_eq!(fork_choice.find_head(&genesis, 0, &[]), Ok(c));
     }
 }
@@ -0,0 +1,46 @@
+use active_validators::validator_is_active;
+use types::{BeaconState, Bitfield, ValidatorRecord};
+
+/// Returns the validator indices of the members of the `committee` with a bit set in the
+/// `attester_bitfield`.
+pub fn attesting_indices(committee: &[usize], attester_bitfield: &Bitfield) -> Vec<usize> {
+    committee
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| attester_bitfield.get(*i).unwrap_or(false))
+        .map(|(_, validator_index)| *validator_index)
+        .collect()
+}
+
+/// Returns the sum of the balances of the active validators with the given indices.
+///
+/// Indices which do not refer to a known validator are ignored.
+pub fn total_balance<'a, I>(validators: &[ValidatorRecord], validator_indices: I) -> u64
+where
+    I: Iterator<Item = &'a usize>,
+{
+    validator_indices
+        .filter_map(|i| validators.get(*i))
+        .filter(|v| validator_is_active(v))
+        .fold(0, |total, v| total.saturating_add(v.balance))
+}
+
+/// Returns `true` if the `attesting_balance` is at least 2/3 of the `total_balance`.
+///
+/// Always returns `false` if the `total_balance` is zero.
+pub fn is_supermajority(attesting_balance: u64, total_balance: u64) -> bool {
+    total_balance > 0 && attesting_balance.saturating_mul(3) >= total_balance.saturating_mul(2)
+}
+
+/// Returns the index of the validator assigned to propose the block at `slot`, if `slot` is
+/// covered by the `shard_and_committee_for_slots` of the `state`.
+pub fn block_proposer_index(state: &BeaconState, slot: u64) -> Option<usize> {
+    let i = slot.checked_sub(state.last_state_recalculation_slot)?;
+    let first_committee = &state
+        .shard_and_committee_for_slots
+        .get(i as usize)?
+        .get(0)?
+        .committee;
+    let proposer = (slot as usize).checked_rem(first_committee.len())?;
+    first_committee.get(proposer).cloned()
+}
@@ -0,0 +1,276 @@
+use super::attesters::{attesting_indices, is_supermajority, total_balance};
+use super::StateTransitionError;
+use std::collections::{BTreeMap, HashSet};
+use types::{BeaconState, CrosslinkRecord, Hash256, PendingAttestationRecord};
+
+/// Update the `crosslinks` of the `state` from the attestations to shard blocks during the
+/// cycle starting at the `last_state_recalculation_slot`.
+///
+/// For each committee in the cycle, the balance of the committee members which attested to each
+/// `shard_block_hash` is tallied. If the attesting balance for some hash is at least 2/3 of the
+/// balance of the committee, the crosslink for the shard is updated to that hash (unless the
+/// shard already has a crosslink from a later slot). Should several hashes reach 2/3 (i.e., some
+/// validators attested to more than one), the hash with the highest attesting balance is chosen,
+/// with ties going to the lowest hash.
+///
+/// The `recently_changed` flag is set only on the crosslinks updated during this cycle.
+///
+/// The attestations to the last slot of the cycle are included in the blocks of the following
+/// cycle, so the cycle should only be tallied once that cycle has passed (see
+/// `cycle_transition_due`).
+pub fn process_crosslinks(
+    state: &mut BeaconState,
+    pending_attestations: &[PendingAttestationRecord],
+    cycle_length: u8,
+) -> Result<(), StateTransitionError> {
+    let cycle_start = state.last_state_recalculation_slot;
+
+    for crosslink in &mut state.crosslinks {
+        crosslink.recently_changed = false;
+    }
+
+    for i in 0..u64::from(cycle_length) {
+        let slot = cycle_start.saturating_add(i);
+        let shard_and_committees = state
+            .shard_and_committee_for_slots
+            .get(i as usize)
+            .ok_or(StateTransitionError::InvalidShardAndCommitteeForSlots)?;
+
+        for sac in shard_and_committees {
+            /*
+             * Collect the committee members which attested to each shard block hash.
+             */
+            let mut attesters: BTreeMap<Hash256, HashSet<usize>> = BTreeMap::new();
+            for attestation in pending_attestations
+                .iter()
+                .map(|p| &p.attestation)
+                .filter(|a| a.slot == slot && a.shard_id == sac.shard)
+            {
+                attesters
+                    .entry(attestation.shard_block_hash)
+                    .or_insert_with(HashSet::new)
+                    .extend(attesting_indices(
+                        &sac.committee,
+                        &attestation.attester_bitfield,
+                    ));
+            }
+
+            let committee_balance = total_balance(&state.validators, sac.committee.iter());
+
+            /*
+             * The winning hash must not depend upon the iteration order of the tally, otherwise
+             * nodes could disagree upon the crosslink.
+             */
+            let winner = attesters
+                .iter()
+                .map(|(hash, attesters)| (total_balance(&state.validators, attesters.iter()), hash))
+                .filter(|(balance, _)| is_supermajority(*balance, committee_balance))
+                .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(a.1)));
+            let shard_block_hash = match winner {
+                Some((_, hash)) => *hash,
+                None => continue,
+            };
+
+            let crosslink = state
+                .crosslinks
+                .get_mut(usize::from(sac.shard))
+                .ok_or(StateTransitionError::InvalidCrosslinks)?;
+            if slot >= crosslink.slot {
+                *crosslink = CrosslinkRecord {
+                    recently_changed: true,
+                    slot,
+                    hash: shard_block_hash,
+                };
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{
+        AttestationRecord, Bitfield, ChainConfig, ShardAndCommittee, ValidatorRecord,
+        ValidatorStatus,
+    };
+
+    const CYCLE_LENGTH: u8 = 2;
+    const SHARD_COUNT: u16 = 2;
+
+    /// A state with four validators, where validators 0 and 1 form the committee for shard 0 at
+    /// slot 0, and validators 2 and 3 form the committee for shard 1 at slot 1.
+    fn test_state() -> BeaconState {
+        let validators: Vec<ValidatorRecord> = (0..4)
+            .map(|_| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = ValidatorStatus::Active as u8;
+                v.balance = 32;
+                v
+            }).collect();
+
+        let shard_and_committee_for_slots = vec![
+            vec![ShardAndCommittee {
+                shard: 0,
+                committee: vec![0, 1],
+            }],
+            vec![ShardAndCommittee {
+                shard: 1,
+                committee: vec![2, 3],
+            }],
+        ];
+
+        let mut config = ChainConfig::standard();
+        config.cycle_length = CYCLE_LENGTH;
+        config.shard_count = SHARD_COUNT;
+
+        BeaconState {
+            validators,
+            crosslinks: vec![CrosslinkRecord::zero(); config.shard_count as usize],
+            shard_and_committee_for_slots,
+            ..BeaconState::zero()
+        }
+    }
+
+    fn attestation(
+        slot: u64,
+        shard_id: u16,
+        hash: &Hash256,
+        bits: &[bool],
+    ) -> PendingAttestationRecord {
+        let mut a = AttestationRecord::zero();
+        a.slot = slot;
+        a.shard_id = shard_id;
+        a.shard_block_hash = *hash;
+        a.attester_bitfield = Bitfield::from_elem(bits.len(), false);
+        for (i, bit) in bits.iter().enumerate() {
+            a.attester_bitfield.set(i, *bit);
+        }
+        PendingAttestationRecord {
+            attestation: a,
+            slot_included: slot + 1,
+        }
+    }
+
+    #[test]
+    fn test_crosslinks_updated_at_supermajority() {
+        let mut state = test_state();
+        let hash = Hash256::from("shard_block".as_bytes());
+
+        /*
+         * Both members of the shard 0 committee attest in separate attestations, whilst only one
+         * member of the shard 1 committee attests.
+         */
+        let attestations = vec![
+            attestation(0, 0, &hash, &[true, false]),
+            attestation(0, 0, &hash, &[false, true]),
+            attestation(1, 1, &hash, &[true, false]),
+        ];
+
+        process_crosslinks(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        assert_eq!(
+            state.crosslinks[0],
+            CrosslinkRecord {
+                recently_changed: true,
+                slot: 0,
+                hash,
+            }
+        );
+        assert_eq!(state.crosslinks[1], CrosslinkRecord::zero());
+    }
+
+    #[test]
+    fn test_crosslinks_split_votes() {
+        let mut state = test_state();
+        let hash_a = Hash256::from("a".as_bytes());
+        let hash_b = Hash256::from("b".as_bytes());
+
+        let attestations = vec![
+            attestation(1, 1, &hash_a, &[true, false]),
+            attestation(1, 1, &hash_b, &[false, true]),
+        ];
+
+        process_crosslinks(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        assert_eq!(state.crosslinks[1], CrosslinkRecord::zero());
+    }
+
+    #[test]
+    fn test_crosslinks_equivocating_supermajorities() {
+        let mut state = test_state();
+        state.validators[1].balance = 64;
+        let hash_a = Hash256::from("a".as_bytes());
+        let hash_b = Hash256::from("b".as_bytes());
+
+        /*
+         * Validator 1 attests to both hashes, so both reach 2/3 of the committee balance. The
+         * hash with more attesting balance wins, regardless of the order of the attestations.
+         */
+        let mut attestations = vec![
+            attestation(0, 0, &hash_a, &[true, true]),
+            attestation(0, 0, &hash_b, &[false, true]),
+        ];
+        for _ in 0..2 {
+            let mut state = state.clone();
+            process_crosslinks(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+            assert_eq!(state.crosslinks[0].hash, hash_a);
+            attestations.reverse();
+        }
+
+        /*
+         * With equal attesting balances, the lowest hash wins.
+         */
+        let attestations = vec![
+            attestation(0, 0, &hash_b, &[true, true]),
+            attestation(0, 0, &hash_a, &[true, true]),
+        ];
+        process_crosslinks(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+        assert_eq!(state.crosslinks[0].hash, hash_a.min(hash_b));
+    }
+
+    #[test]
+    fn test_crosslinks_recently_changed_reset() {
+        let mut state = test_state();
+        state.crosslinks[1] = CrosslinkRecord {
+            recently_changed: true,
+            slot: 1,
+            hash: Hash256::from("old".as_bytes()),
+        };
+
+        let attestations = vec![attestation(
+            0,
+            0,
+            &Hash256::from("new".as_bytes()),
+            &[true, true],
+        )];
+
+        process_crosslinks(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        assert!(state.crosslinks[0].recently_changed);
+        assert!(!state.crosslinks[1].recently_changed);
+    }
+
+    #[test]
+    fn test_crosslinks_not_replaced_by_earlier_slot() {
+        let mut state = test_state();
+        let later = CrosslinkRecord {
+            recently_changed: false,
+            slot: 10,
+            hash: Hash256::from("later".as_bytes()),
+        };
+        state.crosslinks[0] = later.clone();
+
+        let attestations = vec![attestation(
+            0,
+            0,
+            &Hash256::from("earlier".as_bytes()),
+            &[true, true],
+        )];
+
+        process_crosslinks(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        assert_eq!(state.crosslinks[0], later);
+    }
+}
@@ -0,0 +1,183 @@
+use super::attesters::{attesting_indices, is_supermajority, total_balance};
+use super::StateTransitionError;
+use std::collections::HashSet;
+use types::{BeaconState, PendingAttestationRecord};
+
+/// Perform Casper FFG justification and finalization for the cycle starting at the
+/// `last_state_recalculation_slot` of the `state`.
+///
+/// For each slot in the cycle, the balance of the validators which attested to that slot (in the
+/// `pending_attestations`) is tallied against the total balance of the validators assigned to
+/// attest to that slot. If the attesting balance is at least 2/3 of the assigned balance, the slot
+/// is justified and the `justified_streak` is extended, otherwise the streak is reset.
+///
+/// Once the `justified_streak` exceeds `cycle_length`, the slot `cycle_length + 1` slots prior to
+/// the justified slot is finalized.
+pub fn process_justification(
+    state: &mut BeaconState,
+    pending_attestations: &[PendingAttestationRecord],
+    cycle_length: u8,
+) -> Result<(), StateTransitionError> {
+    let cycle_start = state.last_state_recalculation_slot;
+
+    for i in 0..u64::from(cycle_length) {
+        let slot = cycle_start.saturating_add(i);
+        let shard_and_committees = state
+            .shard_and_committee_for_slots
+            .get(i as usize)
+            .ok_or(StateTransitionError::InvalidShardAndCommitteeForSlots)?;
+
+        let assigned_balance = total_balance(
+            &state.validators,
+            shard_and_committees
+                .iter()
+                .flat_map(|sac| sac.committee.iter()),
+        );
+
+        /*
+         * Collect the set of validators which attested to this slot. A validator may be included in
+         * several attestations, but their balance is only counted once.
+         */
+        let mut attesters = HashSet::new();
+        for attestation in pending_attestations
+            .iter()
+            .map(|p| &p.attestation)
+            .filter(|a| a.slot == slot)
+        {
+            let committee = match shard_and_committees
+                .iter()
+                .find(|sac| sac.shard == attestation.shard_id)
+            {
+                Some(sac) => &sac.committee,
+                None => continue,
+            };
+            attesters.extend(attesting_indices(committee, &attestation.attester_bitfield));
+        }
+        let attesting_balance = total_balance(&state.validators, attesters.iter());
+
+        if is_supermajority(attesting_balance, assigned_balance) {
+            state.last_justified_slot = state.last_justified_slot.max(slot);
+            state.justified_streak = state.justified_streak.saturating_add(1);
+        } else {
+            state.justified_streak = 0;
+        }
+
+        if state.justified_streak > u64::from(cycle_length) {
+            let finalized_slot = slot.saturating_sub(u64::from(cycle_length) + 1);
+            state.last_finalized_slot = state.last_finalized_slot.max(finalized_slot);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{
+        AttestationRecord, Bitfield, ChainConfig, CrosslinkRecord, ValidatorRecord, ValidatorStatus,
+    };
+    use validator_shuffling::shard_and_committees_for_cycle;
+
+    const CYCLE_LENGTH: u8 = 4;
+
+    fn test_state(validator_count: usize) -> BeaconState {
+        let mut config = ChainConfig::standard();
+        config.cycle_length = CYCLE_LENGTH;
+        config.shard_count = 8;
+        config.min_committee_size = 2;
+
+        let validators: Vec<ValidatorRecord> = (0..validator_count)
+            .map(|_| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = ValidatorStatus::Active as u8;
+                v.balance = 32;
+                v
+            }).collect();
+
+        let shard_and_committee_for_slots = {
+            let mut a = shard_and_committees_for_cycle(&[0; 32], &validators, 0, &config).unwrap();
+            let mut b = a.clone();
+            a.append(&mut b);
+            a
+        };
+
+        BeaconState {
+            validators,
+            crosslinks: vec![CrosslinkRecord::zero(); config.shard_count as usize],
+            shard_and_committee_for_slots,
+            ..BeaconState::zero()
+        }
+    }
+
+    /// Generate an attestation for each committee in the cycle starting at the
+    /// `last_state_recalculation_slot`, where only the first `signers` of each committee have
+    /// attested.
+    fn cycle_attestations(state: &BeaconState, signers: usize) -> Vec<PendingAttestationRecord> {
+        let mut attestations = vec![];
+        for i in 0..usize::from(CYCLE_LENGTH) {
+            for sac in &state.shard_and_committee_for_slots[i] {
+                let mut a = AttestationRecord::zero();
+                a.slot = state.last_state_recalculation_slot + i as u64;
+                a.shard_id = sac.shard;
+                a.attester_bitfield = Bitfield::from_elem(sac.committee.len(), false);
+                for j in 0..signers.min(sac.committee.len()) {
+                    a.attester_bitfield.set(j, true);
+                }
+                attestations.push(PendingAttestationRecord {
+                    attestation: a,
+                    slot_included: state.last_state_recalculation_slot + i as u64 + 1,
+                });
+            }
+        }
+        attestations
+    }
+
+    #[test]
+    fn test_justification_full_participation() {
+        let mut state = test_state(16);
+        let attestations = cycle_attestations(&state, usize::max_value());
+
+        process_justification(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        assert_eq!(state.last_justified_slot, u64::from(CYCLE_LENGTH) - 1);
+        assert_eq!(state.justified_streak, u64::from(CYCLE_LENGTH));
+        assert_eq!(state.last_finalized_slot, 0);
+    }
+
+    #[test]
+    fn test_justification_insufficient_participation() {
+        let mut state = test_state(16);
+        state.justified_streak = 3;
+        let attestations = cycle_attestations(&state, 1);
+
+        process_justification(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        assert_eq!(state.last_justified_slot, 0);
+        assert_eq!(state.justified_streak, 0);
+        assert_eq!(state.last_finalized_slot, 0);
+    }
+
+    #[test]
+    fn test_finalization_after_streak() {
+        let mut state = test_state(16);
+
+        let attestations = cycle_attestations(&state, usize::max_value());
+        process_justification(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        /*
+         * Justify the following cycle as well.
+         */
+        state.last_state_recalculation_slot += u64::from(CYCLE_LENGTH);
+        let attestations = cycle_attestations(&state, usize::max_value());
+        process_justification(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        let last_slot = u64::from(CYCLE_LENGTH) * 2 - 1;
+        assert_eq!(state.last_justified_slot, last_slot);
+        assert_eq!(state.justified_streak, u64::from(CYCLE_LENGTH) * 2);
+        assert_eq!(
+            state.last_finalized_slot,
+            last_slot - u64::from(CYCLE_LENGTH) - 1
+        );
+    }
+}
@@ -1,103 +1,184 @@
+extern crate active_validators;
+extern crate ssz;
 extern crate types;
-
-use types::{ActiveState, BeaconBlock, Hash256};
+extern crate validator_change;
+extern crate validator_induction;
+extern crate validator_shuffling;
+
+mod attesters;
+mod crosslinks;
+mod justification;
+mod per_cycle_transition;
+mod persistent_committees;
+mod pow_receipt_roots;
+mod rewards;
+mod specials;
+mod validator_set;
+
+pub use attesters::block_proposer_index;
+pub use per_cycle_transition::{cycle_transition_due, per_cycle_transition, recalculate_state};
+pub use pow_receipt_roots::process_deposits;
+use pow_receipt_roots::record_pow_receipt_root_vote;
+use ssz::ssz_encode;
+use types::{
+    BeaconBlock, BeaconState, Hash256, PendingAttestationRecord, RandaoChangeSpecial, SpecialRecord,
+};
+use validator_change::UpdateValidatorSetError;
+use validator_shuffling::ValidatorAssignmentError;
 
 #[derive(Debug, PartialEq)]
 pub enum StateTransitionError {
     BlockSlotBeforeRecalcSlot,
     InvalidParentHashes,
+    InvalidShardAndCommitteeForSlots,
+    InvalidCrosslinks,
+    ArithmeticOverflow,
+    InvalidSpecialRecord,
+    NoBlockProposer,
+    UnknownPoWReceiptRoot,
+    ValidatorAssignmentFailed(ValidatorAssignmentError),
+    ValidatorSetUpdateFailed(UpdateValidatorSetError),
     DBError(String),
 }
 
-pub fn extend_active_state(
-    act_state: &ActiveState,
+impl From<ValidatorAssignmentError> for StateTransitionError {
+    fn from(e: ValidatorAssignmentError) -> Self {
+        StateTransitionError::ValidatorAssignmentFailed(e)
+    }
+}
+
+impl From<UpdateValidatorSetError> for StateTransitionError {
+    fn from(e: UpdateValidatorSetError) -> Self {
+        StateTransitionError::ValidatorSetUpdateFailed(e)
+    }
+}
+
+/// Apply the `block` to the `state`, returning the new state.
+///
+/// The `randao_reveal` of the block becomes the new `randao_commitment` of its proposer (the
+/// validator at `proposer_index`). As the validators may only change at a cycle boundary,
+/// this is queued as a `RandaoChange` special after the specials of the block, recording the
+/// slot of the block as that of the change.
+///
+/// The `pow_chain_reference` of the block is counted as a vote for that PoW receipt root.
+///
+/// The `parent_hash` of the block (rather than the hash of the block itself) is pushed into the
+/// `recent_block_hashes`, as in `get_new_recent_block_hashes` of the spec. The hash of the block
+/// commits to the root of the resulting state, so it cannot be included in that state.
+pub fn per_block_transition(
+    state: &BeaconState,
     block: &BeaconBlock,
-    block_hash: &Hash256,
-) -> Result<ActiveState, StateTransitionError> {
+    parent_hash: &Hash256,
+    proposer_index: usize,
+) -> Result<BeaconState, StateTransitionError> {
     /*
-     * Extend the pending attestations in the active state with the new attestations included
-     * in the block.
-     *
-     * Using the concat method to avoid reallocations.
+     * Extend the pending attestations in the state with the new attestations included
+     * in the block, recording the slot of the block which included them.
      */
-    let pending_attestations =
-        [&act_state.pending_attestations[..], &block.attestations[..]].concat();
+    let mut pending_attestations = state.pending_attestations.clone();
+    pending_attestations.extend(block.attestations.iter().map(|a| PendingAttestationRecord {
+        attestation: a.clone(),
+        slot_included: block.slot,
+    }));
 
     /*
-     * Extend the pending specials in the active state with the new specials included in the
-     * block.
+     * Extend the pending specials in the state with the new specials included in the
+     * block, followed by the change to the proposer's randao commitment.
      *
      * Using the concat method to avoid reallocations.
      */
-    let pending_specials = [&act_state.pending_specials[..], &block.specials[..]].concat();
+    let randao_change = RandaoChangeSpecial {
+        proposer_index,
+        new_randao_commitment: block.randao_reveal,
+        slot: block.slot,
+    };
+    let randao_change = [SpecialRecord::randao_change(&ssz_encode(&randao_change))];
+    let pending_specials = [
+        &state.pending_specials[..],
+        &block.specials[..],
+        &randao_change[..],
+    ].concat();
 
     /*
-     * Update the active state recent_block_hashes:
+     * Update the state recent_block_hashes:
      *
      * - Drop the hash from the earliest position.
-     * - Push the block_hash into the latest position.
+     * - Push the parent_hash into the latest position.
      *
      * Using the concat method to avoid reallocations.
      */
-    let (_first_hash, last_hashes) = act_state
+    let (_first_hash, last_hashes) = state
         .recent_block_hashes
         .split_first()
         .ok_or(StateTransitionError::InvalidParentHashes)?;
-    let new_hash = &[block_hash.clone()];
+    let new_hash = &[*parent_hash];
     let recent_block_hashes = [&last_hashes, &new_hash[..]].concat();
 
     /*
-     * The new `randao_mix` is set to the XOR of the previous active state randao mix and the
+     * The new `randao_mix` is set to the XOR of the previous state randao mix and the
      * randao reveal in this block.
      */
-    let randao_mix = act_state.randao_mix ^ block.randao_reveal;
+    let randao_mix = state.randao_mix ^ block.randao_reveal;
 
-    Ok(ActiveState {
+    let mut new_state = BeaconState {
         pending_attestations,
         pending_specials,
         recent_block_hashes,
         randao_mix,
-    })
+        ..state.clone()
+    };
+
+    /*
+     * The PoW chain reference of the block is a vote for that receipt root to be processed.
+     */
+    record_pow_receipt_root_vote(&mut new_state, &block.pow_chain_reference);
+
+    Ok(new_state)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use types::SpecialRecord;
-
-    fn empty_active_state() -> ActiveState {
-        ActiveState {
-            pending_attestations: vec![],
-            pending_specials: vec![],
-            recent_block_hashes: vec![],
-            randao_mix: Hash256::zero(),
-        }
+    use types::{AttestationRecord, CandidatePoWReceiptRootRecord};
+
+    const PROPOSER: usize = 3;
+
+    /// The special queued to record the `randao_reveal` of a block by `PROPOSER` at `slot`.
+    fn randao_change(randao_reveal: Hash256, slot: u64) -> SpecialRecord {
+        SpecialRecord::randao_change(&ssz_encode(&RandaoChangeSpecial {
+            proposer_index: PROPOSER,
+            new_randao_commitment: randao_reveal,
+            slot,
+        }))
     }
 
     #[test]
-    fn test_extend_active_state_minimal() {
-        let mut act_state = empty_active_state();
+    fn test_per_block_transition_minimal() {
+        let mut state = BeaconState::zero();
 
         let parent_hash = Hash256::from("parent_hash".as_bytes());
-        act_state.recent_block_hashes = vec![parent_hash];
+        state.recent_block_hashes = vec![parent_hash];
 
         let block = BeaconBlock::zero();
         let block_hash = Hash256::from("block_hash".as_bytes());
 
-        let new_act_state = extend_active_state(&act_state, &block, &block_hash).unwrap();
+        let new_state = per_block_transition(&state, &block, &block_hash, PROPOSER).unwrap();
 
-        assert_eq!(new_act_state.pending_attestations, vec![]);
-        assert_eq!(new_act_state.pending_specials, vec![]);
-        assert_eq!(new_act_state.recent_block_hashes, vec![block_hash]);
-        assert_eq!(new_act_state.randao_mix, Hash256::zero());
+        assert_eq!(new_state.pending_attestations, vec![]);
+        assert_eq!(
+            new_state.pending_specials,
+            vec![randao_change(Hash256::zero(), 0)]
+        );
+        assert_eq!(new_state.recent_block_hashes, vec![block_hash]);
+        assert_eq!(new_state.randao_mix, Hash256::zero());
     }
 
     #[test]
-    fn test_extend_active_state_specials() {
-        let mut act_state = empty_active_state();
+    fn test_per_block_transition_specials() {
+        let mut state = BeaconState::zero();
 
         let parent_hash = Hash256::from("parent_hash".as_bytes());
-        act_state.recent_block_hashes = vec![parent_hash];
+        state.recent_block_hashes = vec![parent_hash];
 
         let mut block = BeaconBlock::zero();
         let special = SpecialRecord {
@@ -109,86 +190,155 @@ mod tests {
 
         let block_hash = Hash256::from("block_hash".as_bytes());
 
-        let new_act_state = extend_active_state(&act_state, &block, &block_hash).unwrap();
+        let new_state = per_block_transition(&state, &block, &block_hash, PROPOSER).unwrap();
 
-        assert_eq!(new_act_state.pending_attestations, vec![]);
-        assert_eq!(new_act_state.pending_specials, vec![special.clone()]);
-        assert_eq!(new_act_state.recent_block_hashes, vec![block_hash]);
-        assert_eq!(new_act_state.randao_mix, Hash256::zero());
+        assert_eq!(new_state.pending_attestations, vec![]);
+        assert_eq!(
+            new_state.pending_specials,
+            vec![special.clone(), randao_change(Hash256::zero(), 0)]
+        );
+        assert_eq!(new_state.recent_block_hashes, vec![block_hash]);
+        assert_eq!(new_state.randao_mix, Hash256::zero());
 
-        let new_new_act_state = extend_active_state(&new_act_state, &block, &block_hash).unwrap();
+        let new_new_state =
+            per_block_transition(&new_state, &block, &block_hash, PROPOSER).unwrap();
 
-        assert_eq!(new_new_act_state.pending_attestations, vec![]);
+        assert_eq!(new_new_state.pending_attestations, vec![]);
         assert_eq!(
-            new_new_act_state.pending_specials,
-            vec![special.clone(), special.clone()]
+            new_new_state.pending_specials,
+            vec![
+                special.clone(),
+                randao_change(Hash256::zero(), 0),
+                special.clone(),
+                randao_change(Hash256::zero(), 0),
+            ]
         );
-        assert_eq!(new_new_act_state.recent_block_hashes, vec![block_hash]);
-        assert_eq!(new_new_act_state.randao_mix, Hash256::zero());
+        assert_eq!(new_new_state.recent_block_hashes, vec![block_hash]);
+        assert_eq!(new_new_state.randao_mix, Hash256::zero());
     }
 
     #[test]
-    fn test_extend_active_state_empty_recent_block_hashes() {
-        let act_state = empty_active_state();
+    fn test_per_block_transition_attestations() {
+        let mut state = BeaconState::zero();
+        state.recent_block_hashes = vec![Hash256::from("parent_hash".as_bytes())];
+
+        let mut block = BeaconBlock::zero();
+        block.slot = 5;
+        let mut attestation = AttestationRecord::zero();
+        attestation.slot = 4;
+        block.attestations.push(attestation.clone());
+
+        let block_hash = Hash256::from("block_hash".as_bytes());
+
+        let new_state = per_block_transition(&state, &block, &block_hash, PROPOSER).unwrap();
+
+        assert_eq!(
+            new_state.pending_attestations,
+            vec![PendingAttestationRecord {
+                attestation,
+                slot_included: 5,
+            }]
+        );
+        assert_eq!(
+            new_state.pending_specials,
+            vec![randao_change(Hash256::zero(), 5)]
+        );
+    }
+
+    #[test]
+    fn test_per_block_transition_empty_recent_block_hashes() {
+        let state = BeaconState::zero();
 
         let block = BeaconBlock::zero();
 
         let block_hash = Hash256::from("block_hash".as_bytes());
 
-        let result = extend_active_state(&act_state, &block, &block_hash);
+        let result = per_block_transition(&state, &block, &block_hash, PROPOSER);
 
         assert_eq!(result, Err(StateTransitionError::InvalidParentHashes));
     }
 
     #[test]
-    fn test_extend_active_recent_block_hashes() {
-        let mut act_state = empty_active_state();
+    fn test_per_block_transition_recent_block_hashes() {
+        let mut state = BeaconState::zero();
 
         let parent_hashes = vec![
             Hash256::from("one".as_bytes()),
             Hash256::from("two".as_bytes()),
             Hash256::from("three".as_bytes()),
         ];
-        act_state.recent_block_hashes = parent_hashes.clone();
+        state.recent_block_hashes = parent_hashes.clone();
 
         let block = BeaconBlock::zero();
 
         let block_hash = Hash256::from("four".as_bytes());
 
-        let new_act_state = extend_active_state(&act_state, &block, &block_hash).unwrap();
+        let new_state = per_block_transition(&state, &block, &block_hash, PROPOSER).unwrap();
 
-        assert_eq!(new_act_state.pending_attestations, vec![]);
-        assert_eq!(new_act_state.pending_specials, vec![]);
+        assert_eq!(new_state.pending_attestations, vec![]);
         assert_eq!(
-            new_act_state.recent_block_hashes,
+            new_state.pending_specials,
+            vec![randao_change(Hash256::zero(), 0)]
+        );
+        assert_eq!(
+            new_state.recent_block_hashes,
             vec![
                 Hash256::from("two".as_bytes()),
                 Hash256::from("three".as_bytes()),
                 Hash256::from("four".as_bytes()),
             ]
         );
-        assert_eq!(new_act_state.randao_mix, Hash256::zero());
+        assert_eq!(new_state.randao_mix, Hash256::zero());
     }
 
     #[test]
-    fn test_extend_active_state_randao() {
-        let mut act_state = empty_active_state();
+    fn test_per_block_transition_randao() {
+        let mut state = BeaconState::zero();
 
         let parent_hash = Hash256::from("parent_hash".as_bytes());
-        act_state.recent_block_hashes = vec![parent_hash];
+        state.recent_block_hashes = vec![parent_hash];
 
-        act_state.randao_mix = Hash256::from(0b00000000);
+        state.randao_mix = Hash256::from(0b00000000);
 
         let mut block = BeaconBlock::zero();
         block.randao_reveal = Hash256::from(0b00000001);
 
         let block_hash = Hash256::from("block_hash".as_bytes());
 
-        let new_act_state = extend_active_state(&act_state, &block, &block_hash).unwrap();
+        let new_state = per_block_transition(&state, &block, &block_hash, PROPOSER).unwrap();
+
+        assert_eq!(new_state.pending_attestations, vec![]);
+        /*
+         * The reveal is queued to become the new commitment of the proposer.
+         */
+        assert_eq!(
+            new_state.pending_specials,
+            vec![randao_change(Hash256::from(0b00000001), 0)]
+        );
+        assert_eq!(new_state.recent_block_hashes, vec![block_hash]);
+        assert_eq!(new_state.randao_mix, Hash256::from(0b00000001));
+    }
+
+    #[test]
+    fn test_per_block_transition_pow_receipt_root_vote() {
+        let mut state = BeaconState::zero();
+        state.recent_block_hashes = vec![Hash256::from("parent_hash".as_bytes())];
+
+        let mut block = BeaconBlock::zero();
+        block.pow_chain_reference = Hash256::from("pow_chain".as_bytes());
+
+        let block_hash = Hash256::from("block_hash".as_bytes());
+
+        let new_state = per_block_transition(&state, &block, &block_hash, PROPOSER).unwrap();
+        let new_new_state =
+            per_block_transition(&new_state, &block, &block_hash, PROPOSER).unwrap();
 
-        assert_eq!(new_act_state.pending_attestations, vec![]);
-        assert_eq!(new_act_state.pending_specials, vec![]);
-        assert_eq!(new_act_state.recent_block_hashes, vec![block_hash]);
-        assert_eq!(new_act_state.randao_mix, Hash256::from(0b00000001));
+        assert_eq!(
+            new_new_state.candidate_pow_receipt_roots,
+            vec![CandidatePoWReceiptRootRecord {
+                candidate_pow_receipt_root: block.pow_chain_reference,
+                votes: 2,
+            }]
+        );
     }
 }
@@ -0,0 +1,610 @@
+use super::crosslinks::process_crosslinks;
+use super::justification::process_justification;
+use super::persistent_committees::process_persistent_committees;
+use super::pow_receipt_roots::process_pow_receipt_roots;
+use super::rewards::process_rewards;
+use super::specials::process_specials;
+use super::validator_set::process_validator_set_change;
+use super::StateTransitionError;
+use std::mem;
+use types::{BeaconState, ChainConfig};
+use validator_change::ValidatorChangeRecord;
+use validator_shuffling::shard_and_committees_for_cycle;
+
+/// Returns `true` if the `state` must be recalculated before a block at `block_slot` may be
+/// applied to it.
+///
+/// The cycle starting at the `last_state_recalculation_slot` is only tallied once the following
+/// cycle has also passed. An attestation may only be included in a block after the slot it
+/// attests to, so the attestations to the last slots of a cycle are included in the blocks of
+/// the following cycle.
+pub fn cycle_transition_due(state: &BeaconState, block_slot: u64, cycle_length: u8) -> bool {
+    block_slot.saturating_sub(state.last_state_recalculation_slot)
+        >= u64::from(cycle_length).saturating_mul(2)
+}
+
+/// Perform the cycle-boundary recalculation of a `BeaconState`.
+///
+/// One recalculation is performed for each cycle starting at the
+/// `last_state_recalculation_slot` of the supplied `state` which ended at least a full cycle
+/// before the `block_slot` (see `cycle_transition_due`). Each recalculation:
+///
+/// - Tallies the pending attestations for the cycle starting at `last_state_recalculation_slot`,
+/// updating the justified and finalized slots (see `process_justification`).
+/// - Tallies the attestations to shard blocks for the same cycle, updating the `crosslinks` of
+/// any shard with a 2/3 supermajority (see `process_crosslinks`).
+/// - Rewards and penalizes the validators assigned to the cycle according to their participation,
+/// rewarding proposers for including attestations (see `process_rewards`).
+/// - Advances `last_state_recalculation_slot` by `cycle_length`.
+/// - If a PoW receipt root voting period has ended, adopts the candidate with a majority of votes
+/// as the `processed_pow_receipt_root` (see `process_pow_receipt_roots`). The deposits up to the
+/// new root are _not_ inducted, as they are not known to the state; see `process_deposits`.
+/// - Applies the pending specials to the validators (see `process_specials`).
+/// - If a validator set change is due, activates and exits validators, extending the
+/// `validator_set_delta_hash_chain` (see `process_validator_set_change`).
+/// - Moves validators between persistent committees and randomly selects further validators to
+/// be moved in future (see `process_persistent_committees`).
+/// - Shifts `shard_and_committee_for_slots` forward by one cycle, generating a new cycle of
+/// assignments from the (possibly changed) validator set, seeded with the `randao_mix`.
+/// - Drops any pending attestations for slots prior to the new `last_state_recalculation_slot`
+/// and clears all pending specials.
+///
+/// Returns the new `BeaconState`, with its `recent_block_hashes` trimmed to `cycle_length * 2`.
+/// The block at `block_slot` is _not_ applied to the returned state, this should be done with
+/// `per_block_transition`.
+pub fn per_cycle_transition(
+    state: &BeaconState,
+    block_slot: u64,
+    config: &ChainConfig,
+) -> Result<BeaconState, StateTransitionError> {
+    recalculate_state(state, block_slot, config).map(|(state, _)| state)
+}
+
+/// Perform the recalculations of `per_cycle_transition`, returning the new state along with the
+/// validator set changes made by them, as pairs of the slot of each change and the records
+/// appended to the `validator_set_delta_hash_chain` by it.
+///
+/// The changes are not recorded in the state, so they should be stored by the caller should they
+/// be required later (e.g., to serve light clients).
+pub fn recalculate_state(
+    state: &BeaconState,
+    block_slot: u64,
+    config: &ChainConfig,
+) -> Result<(BeaconState, Vec<(u64, Vec<ValidatorChangeRecord>)>), StateTransitionError> {
+    if block_slot < state.last_state_recalculation_slot {
+        return Err(StateTransitionError::BlockSlotBeforeRecalcSlot);
+    }
+
+    let cycle_length = u64::from(config.cycle_length);
+
+    let mut state = state.clone();
+    let mut validator_changes = vec![];
+
+    while cycle_transition_due(&state, block_slot, config.cycle_length) {
+        /*
+         * Take the pending attestations and specials from the state, so they may be processed
+         * against it.
+         */
+        let mut pending_attestations = mem::replace(&mut state.pending_attestations, vec![]);
+        let pending_specials = mem::replace(&mut state.pending_specials, vec![]);
+
+        /*
+         * Justify (and possibly finalize) the slots of the cycle. Every attestation to the cycle
+         * which was included in time is pending, as a further cycle has passed since it ended.
+         */
+        process_justification(&mut state, &pending_attestations, config.cycle_length)?;
+
+        /*
+         * Crosslink any shard blocks which were attested to by a supermajority of their committee.
+         */
+        process_crosslinks(&mut state, &pending_attestations, config.cycle_length)?;
+
+        /*
+         * Apply the validator balance changes for the cycle.
+         */
+        process_rewards(&mut state, &pending_attestations, config.cycle_length)?;
+
+        let last_state_recalculation_slot = state
+            .last_state_recalculation_slot
+            .saturating_add(cycle_length);
+        state.last_state_recalculation_slot = last_state_recalculation_slot;
+
+        /*
+         * Process the PoW receipt root with a majority of votes, if the voting period has ended.
+         */
+        process_pow_receipt_roots(&mut state, last_state_recalculation_slot, config);
+
+        /*
+         * Apply the logouts, slashings and randao changes included in blocks since the last
+         * recalculation.
+         */
+        process_specials(&mut state, &pending_specials, last_state_recalculation_slot)?;
+
+        /*
+         * Activate and exit validators, if permitted.
+         */
+        if let Some(records) =
+            process_validator_set_change(&mut state, last_state_recalculation_slot, config)?
+        {
+            validator_changes.push((last_state_recalculation_slot, records));
+        }
+
+        /*
+         * Reassign validators to new persistent committees.
+         */
+        process_persistent_committees(&mut state, last_state_recalculation_slot, config)?;
+
+        /*
+         * The next cycle of crosslinking starts at the shard following the last shard assigned
+         * in the present `shard_and_committee_for_slots`.
+         */
+        let crosslinking_shard_start = state
+            .shard_and_committee_for_slots
+            .last()
+            .and_then(|slot| slot.last())
+            .and_then(|sac| sac.shard.checked_add(1))
+            .and_then(|shard| shard.checked_rem(config.shard_count))
+            .unwrap_or(0);
+
+        /*
+         * Drop the first cycle of shard and committee assignments and append a new cycle to the
+         * end.
+         */
+        let shard_and_committee_for_slots = {
+            let mut sac = state
+                .shard_and_committee_for_slots
+                .get(cycle_length as usize..)
+                .ok_or(StateTransitionError::InvalidShardAndCommitteeForSlots)?
+                .to_vec();
+            let mut new_cycle = shard_and_committees_for_cycle(
+                &state.randao_mix[..],
+                &state.validators,
+                crosslinking_shard_start,
+                config,
+            )?;
+            sac.append(&mut new_cycle);
+            sac
+        };
+        state.shard_and_committee_for_slots = shard_and_committee_for_slots;
+
+        /*
+         * Return all attestations which are not older than the new recalculation slot to the
+         * state. The specials have all been applied, so they are dropped.
+         */
+        pending_attestations.retain(|p| p.attestation.slot >= last_state_recalculation_slot);
+        state.pending_attestations = pending_attestations;
+    }
+
+    /*
+     * Only the latest `cycle_length * 2` block hashes are required to validate attestations.
+     */
+    let max_recent_block_hashes = (cycle_length * 2) as usize;
+    let recent_block_hashes_len = state.recent_block_hashes.len();
+    if recent_block_hashes_len > max_recent_block_hashes {
+        state.recent_block_hashes = state
+            .recent_block_hashes
+            .split_off(recent_block_hashes_len - max_recent_block_hashes);
+    }
+
+    Ok((state, validator_changes))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate ssz;
+
+    use self::ssz::ssz_encode;
+    use super::super::attesters::block_proposer_index;
+    use super::super::per_block_transition;
+    use super::super::rewards::BASE_REWARD_QUOTIENT;
+    use super::*;
+    use types::{
+        AttestationRecord, BeaconBlock, Bitfield, CandidatePoWReceiptRootRecord, CrosslinkRecord,
+        Hash256, PendingAttestationRecord, RandaoChangeSpecial, ShardAndCommittee, SpecialRecord,
+        ValidatorRecord, ValidatorStatus,
+    };
+    use validator_change::VALIDATOR_FLAG_ENTRY;
+    use validator_shuffling::initial_persistent_committees;
+
+    fn test_config() -> ChainConfig {
+        let mut config = ChainConfig::standard();
+        config.cycle_length = 4;
+        config.shard_count = 8;
+        config.min_committee_size = 2;
+        config
+    }
+
+    fn test_state(config: &ChainConfig, validator_count: usize) -> BeaconState {
+        let validators: Vec<ValidatorRecord> = (0..validator_count)
+            .map(|_| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = ValidatorStatus::Active as u8;
+                v
+            }).collect();
+
+        let shard_and_committee_for_slots = {
+            let mut a = shard_and_committees_for_cycle(&[0; 32], &validators, 0, config).unwrap();
+            let mut b = a.clone();
+            a.append(&mut b);
+            a
+        };
+
+        BeaconState {
+            validators,
+            crosslinks: vec![CrosslinkRecord::zero(); config.shard_count as usize],
+            shard_and_committee_for_slots,
+            recent_block_hashes: vec![Hash256::zero(); config.cycle_length as usize * 2],
+            randao_mix: Hash256::from("randao_mix".as_bytes()),
+            ..BeaconState::zero()
+        }
+    }
+
+    fn attestation_at_slot(slot: u64) -> PendingAttestationRecord {
+        let mut a = AttestationRecord::zero();
+        a.slot = slot;
+        PendingAttestationRecord {
+            attestation: a,
+            slot_included: slot + 1,
+        }
+    }
+
+    /// Generate an attestation by every member of each committee assigned to `slot`.
+    fn slot_attestations(state: &BeaconState, slot: u64) -> Vec<AttestationRecord> {
+        let i = (slot - state.last_state_recalculation_slot) as usize;
+        state.shard_and_committee_for_slots[i]
+            .iter()
+            .map(|sac| {
+                let mut a = AttestationRecord::zero();
+                a.slot = slot;
+                a.shard_id = sac.shard;
+                a.shard_block_hash = Hash256::from(u64::from(sac.shard) + 1);
+                a.attester_bitfield = Bitfield::from_elem(sac.committee.len(), true);
+                a
+            }).collect()
+    }
+
+    /// Apply a block at each slot following the `state` up to and including `last_slot`, where
+    /// each block includes the attestations of every committee assigned to the preceding slot.
+    ///
+    /// The state is recalculated before each block where required, as it is by the chain.
+    fn apply_blocks(state: &BeaconState, last_slot: u64, config: &ChainConfig) -> BeaconState {
+        let mut state = state.clone();
+        for slot in 1..last_slot + 1 {
+            state = per_cycle_transition(&state, slot, config).unwrap();
+
+            let mut block = BeaconBlock::zero();
+            block.slot = slot;
+            block.attestations = slot_attestations(&state, slot - 1);
+            let proposer_index = block_proposer_index(&state, slot).unwrap();
+            state =
+                per_block_transition(&state, &block, &Hash256::from(slot), proposer_index).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_per_cycle_transition_single_cycle() {
+        let config = test_config();
+        let state = test_state(&config, 16);
+        let cycle_length = config.cycle_length as usize;
+
+        let new_state = per_cycle_transition(&state, 8, &config).unwrap();
+
+        assert_eq!(new_state.last_state_recalculation_slot, 4);
+        assert_eq!(
+            new_state.shard_and_committee_for_slots.len(),
+            cycle_length * 2
+        );
+        assert_eq!(
+            new_state.shard_and_committee_for_slots[0..cycle_length],
+            state.shard_and_committee_for_slots[cycle_length..]
+        );
+        assert_eq!(new_state.validators, state.validators);
+    }
+
+    #[test]
+    fn test_per_cycle_transition_skipped_cycles() {
+        let config = test_config();
+        let state = test_state(&config, 16);
+
+        let new_state = per_cycle_transition(&state, 17, &config).unwrap();
+
+        assert_eq!(new_state.last_state_recalculation_slot, 12);
+        assert_eq!(
+            new_state.shard_and_committee_for_slots.len(),
+            config.cycle_length as usize * 2
+        );
+    }
+
+    #[test]
+    fn test_per_cycle_transition_resets_pending() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+
+        state.pending_attestations = vec![
+            attestation_at_slot(1),
+            attestation_at_slot(3),
+            attestation_at_slot(4),
+            attestation_at_slot(5),
+        ];
+        let randao_change = RandaoChangeSpecial {
+            proposer_index: 0,
+            new_randao_commitment: Hash256::from("commitment".as_bytes()),
+            slot: 3,
+        };
+        state.pending_specials = vec![SpecialRecord::randao_change(&ssz_encode(&randao_change))];
+        state.recent_block_hashes = (0..12).map(|i| Hash256::from(i as u64)).collect();
+
+        let new_state = per_cycle_transition(&state, 8, &config).unwrap();
+
+        assert_eq!(
+            new_state.pending_attestations,
+            vec![attestation_at_slot(4), attestation_at_slot(5)]
+        );
+        assert_eq!(new_state.pending_specials, vec![]);
+        assert_eq!(
+            new_state.validators[0].randao_commitment,
+            randao_change.new_randao_commitment
+        );
+        assert_eq!(new_state.validators[0].randao_last_change, 3);
+        assert_eq!(
+            new_state.recent_block_hashes,
+            (4..12)
+                .map(|i| Hash256::from(i as u64))
+                .collect::<Vec<Hash256>>()
+        );
+        assert_eq!(new_state.randao_mix, state.randao_mix);
+    }
+
+    #[test]
+    fn test_per_cycle_transition_justifies() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+        for v in state.validators.iter_mut() {
+            v.balance = 32;
+        }
+
+        /*
+         * Every validator attests to every slot of the first cycle.
+         */
+        for (slot, shard_and_committees) in state.shard_and_committee_for_slots
+            [0..config.cycle_length as usize]
+            .iter()
+            .enumerate()
+        {
+            for sac in shard_and_committees {
+                let mut a = attestation_at_slot(slot as u64);
+                a.attestation.shard_id = sac.shard;
+                a.attestation.attester_bitfield = Bitfield::from_elem(sac.committee.len(), true);
+                state.pending_attestations.push(a);
+            }
+        }
+
+        let new_state = per_cycle_transition(&state, 8, &config).unwrap();
+
+        assert_eq!(new_state.last_justified_slot, 3);
+        assert_eq!(new_state.justified_streak, 4);
+        assert_eq!(new_state.last_finalized_slot, 0);
+    }
+
+    #[test]
+    fn test_per_cycle_transition_finalizes_from_blocks() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+        for v in state.validators.iter_mut() {
+            v.balance = 32;
+        }
+
+        /*
+         * The attestations to the last slot of the first cycle are only included at slot 4, so
+         * the first cycle is tallied at slot 8.
+         */
+        let new_state = apply_blocks(&state, 7, &config);
+        assert_eq!(new_state.last_state_recalculation_slot, 0);
+        assert_eq!(new_state.last_justified_slot, 0);
+
+        let new_state = apply_blocks(&state, 8, &config);
+        assert_eq!(new_state.last_state_recalculation_slot, 4);
+        assert_eq!(new_state.last_justified_slot, 3);
+        assert_eq!(new_state.justified_streak, 4);
+        assert_eq!(new_state.last_finalized_slot, 0);
+
+        /*
+         * Once the streak exceeds a cycle, the slot `cycle_length + 1` slots prior to the last
+         * justified slot is finalized.
+         */
+        let new_state = apply_blocks(&state, 12, &config);
+        assert_eq!(new_state.last_state_recalculation_slot, 8);
+        assert_eq!(new_state.last_justified_slot, 7);
+        assert_eq!(new_state.justified_streak, 8);
+        assert_eq!(new_state.last_finalized_slot, 2);
+    }
+
+    #[test]
+    fn test_per_cycle_transition_crosslinks_from_blocks() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+        for v in state.validators.iter_mut() {
+            v.balance = 32;
+        }
+
+        /*
+         * Every committee of the first cycle attests to a shard block, including those assigned
+         * to the last slot of the cycle, whose attestations are only included at slot 4.
+         */
+        let last_slot = u64::from(config.cycle_length) - 1;
+        let new_state = apply_blocks(&state, 8, &config);
+
+        for sac in &state.shard_and_committee_for_slots[last_slot as usize] {
+            assert_eq!(
+                new_state.crosslinks[usize::from(sac.shard)],
+                CrosslinkRecord {
+                    recently_changed: true,
+                    slot: last_slot,
+                    hash: Hash256::from(u64::from(sac.shard) + 1),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_per_cycle_transition_rewards_from_blocks() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+        let balance = BASE_REWARD_QUOTIENT * 1_000;
+        for v in state.validators.iter_mut() {
+            v.balance = balance;
+        }
+
+        /*
+         * Every validator attests on time, including those assigned to the last slot of the
+         * first cycle, so none are penalized and each receives at least the base reward.
+         */
+        let new_state = apply_blocks(&state, 8, &config);
+
+        for v in &new_state.validators {
+            assert!(v.balance >= balance + balance / BASE_REWARD_QUOTIENT);
+        }
+    }
+
+    #[test]
+    fn test_per_cycle_transition_validator_set_change() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+        let cycle_length = config.cycle_length as usize;
+
+        /*
+         * A slot and every shard have been finalized and crosslinked since the last change.
+         */
+        state.last_finalized_slot = 2;
+        for crosslink in state.crosslinks.iter_mut() {
+            crosslink.slot = 1;
+        }
+        let (new_validator, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+        state.validators.push(new_validator);
+
+        let (new_state, changes) = recalculate_state(&state, 8, &config).unwrap();
+
+        assert_eq!(
+            new_state.validators[16].status,
+            ValidatorStatus::Active as u8
+        );
+        assert_eq!(new_state.validator_set_change_slot, 4);
+        assert!(!new_state.validator_set_delta_hash_chain.is_zero());
+        assert_eq!(
+            changes,
+            vec![(
+                4,
+                vec![ValidatorChangeRecord {
+                    index: 16,
+                    pubkey: new_state.validators[16].pubkey.clone(),
+                    flag: VALIDATOR_FLAG_ENTRY,
+                }]
+            )]
+        );
+
+        /*
+         * The new validator is assigned a committee in the new cycle only.
+         */
+        let assigned = |slots: &[Vec<ShardAndCommittee>]| {
+            slots
+                .iter()
+                .flat_map(|slot| slot.iter())
+                .any(|sac| sac.committee.contains(&16))
+        };
+        assert!(!assigned(
+            &new_state.shard_and_committee_for_slots[..cycle_length]
+        ));
+        assert!(assigned(
+            &new_state.shard_and_committee_for_slots[cycle_length..]
+        ));
+    }
+
+    #[test]
+    fn test_per_cycle_transition_persistent_committees() {
+        let mut config = test_config();
+        config.shard_persistent_committee_change_period = 4;
+        let mut state = test_state(&config, 16);
+        state.persistent_committees =
+            initial_persistent_committees(&[0; 32], &state.validators, config.shard_count).unwrap();
+
+        /*
+         * One validator in four is selected for reassignment, to take effect in four slots.
+         */
+        let new_state = per_cycle_transition(&state, 8, &config).unwrap();
+
+        assert_eq!(new_state.persistent_committees, state.persistent_committees);
+        let reassignments = new_state.persistent_committee_reassignments.clone();
+        assert_eq!(reassignments.len(), 4);
+        assert!(reassignments.iter().all(|r| r.slot == 8));
+
+        /*
+         * At the next recalculation the reassignments are applied and a new batch is selected.
+         */
+        let new_state = per_cycle_transition(&new_state, 12, &config).unwrap();
+
+        for r in &reassignments {
+            assert_eq!(
+                new_state.persistent_committee_shard(r.validator_index as usize),
+                Some(r.shard as u16)
+            );
+        }
+        assert_eq!(new_state.persistent_committee_reassignments.len(), 4);
+        assert!(new_state
+            .persistent_committee_reassignments
+            .iter()
+            .all(|r| r.slot == 12));
+    }
+
+    #[test]
+    fn test_per_cycle_transition_pow_receipt_roots() {
+        let mut config = test_config();
+        config.pow_receipt_root_voting_period = 8;
+        let mut state = test_state(&config, 16);
+        let root = Hash256::from("pow_receipt_root".as_bytes());
+        state.candidate_pow_receipt_roots = vec![CandidatePoWReceiptRootRecord {
+            candidate_pow_receipt_root: root,
+            votes: 5,
+        }];
+
+        /*
+         * The candidates are retained until the voting period ends.
+         */
+        let new_state = per_cycle_transition(&state, 8, &config).unwrap();
+
+        assert_eq!(new_state.processed_pow_receipt_root, Hash256::zero());
+        assert_eq!(
+            new_state.candidate_pow_receipt_roots,
+            state.candidate_pow_receipt_roots
+        );
+
+        let new_state = per_cycle_transition(&new_state, 12, &config).unwrap();
+
+        assert_eq!(new_state.processed_pow_receipt_root, root);
+        assert_eq!(new_state.candidate_pow_receipt_roots, vec![]);
+    }
+
+    #[test]
+    fn test_per_cycle_transition_within_cycle() {
+        let config = test_config();
+        let state = test_state(&config, 16);
+
+        /*
+         * The first cycle is not tallied until the second cycle has also passed.
+         */
+        let new_state = per_cycle_transition(&state, 7, &config).unwrap();
+
+        assert_eq!(new_state, state);
+    }
+
+    #[test]
+    fn test_per_cycle_transition_block_before_recalc() {
+        let config = test_config();
+        let mut state = test_state(&config, 16);
+        state.last_state_recalculation_slot = 8;
+
+        let result = per_cycle_transition(&state, 4, &config);
+
+        assert_eq!(result, Err(StateTransitionError::BlockSlotBeforeRecalcSlot));
+    }
+}
@@ -0,0 +1,41 @@
+use super::StateTransitionError;
+use active_validators::active_validator_indices;
+use types::{BeaconState, ChainConfig};
+use validator_shuffling::{
+    apply_persistent_committee_reassignments, persistent_committee_reassignments,
+};
+
+/// Apply the persistent committee reassignments of the `state` which take effect at or before
+/// `slot`, then schedule a new batch of reassignments.
+///
+/// One active validator in every `shard_persistent_committee_change_period` is randomly selected
+/// (seeded with the `randao_mix` of the `state`) and reassigned to a random shard, taking effect
+/// `shard_persistent_committee_change_period` slots after `slot`.
+pub fn process_persistent_committees(
+    state: &mut BeaconState,
+    slot: u64,
+    config: &ChainConfig,
+) -> Result<(), StateTransitionError> {
+    apply_persistent_committee_reassignments(
+        &mut state.persistent_committees,
+        &mut state.persistent_committee_reassignments,
+        slot,
+    );
+
+    let change_period = config.shard_persistent_committee_change_period;
+    let reassignment_count = (active_validator_indices(&state.validators).len() as u64)
+        .checked_div(change_period)
+        .unwrap_or(0);
+    let mut reassignments = persistent_committee_reassignments(
+        &state.randao_mix[..],
+        &state.validators,
+        config.shard_count,
+        reassignment_count as usize,
+        slot.saturating_add(change_period),
+    )?;
+    state
+        .persistent_committee_reassignments
+        .append(&mut reassignments);
+
+    Ok(())
+}
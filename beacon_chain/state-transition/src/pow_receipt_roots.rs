@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use std::mem;
+use types::{
+    BeaconState, CandidatePoWReceiptRootRecord, ChainConfig, Hash256, ValidatorRegistration,
+    ValidatorStatus,
+};
+use validator_induction::ValidatorInductor;
+
+/// Count the `pow_chain_reference` of a block as a vote for that PoW receipt root to become the
+/// `processed_pow_receipt_root` of the `state`.
+pub fn record_pow_receipt_root_vote(state: &mut BeaconState, pow_chain_reference: &Hash256) {
+    let existing = state
+        .candidate_pow_receipt_roots
+        .iter_mut()
+        .find(|c| c.candidate_pow_receipt_root == *pow_chain_reference);
+
+    match existing {
+        Some(candidate) => candidate.votes = candidate.votes.saturating_add(1),
+        None => state
+            .candidate_pow_receipt_roots
+            .push(CandidatePoWReceiptRootRecord {
+                candidate_pow_receipt_root: *pow_chain_reference,
+                votes: 1,
+            }),
+    }
+}
+
+/// If `slot` ends a PoW receipt root voting period, adopt the candidate which received votes from
+/// a majority of the blocks in the period as the `processed_pow_receipt_root` and clear the
+/// candidates for the next period.
+///
+/// If no candidate has a majority, the `processed_pow_receipt_root` is left unchanged.
+pub fn process_pow_receipt_roots(state: &mut BeaconState, slot: u64, config: &ChainConfig) {
+    let voting_period = config.pow_receipt_root_voting_period;
+    if slot.checked_rem(voting_period) != Some(0) {
+        return;
+    }
+
+    let candidates = mem::replace(&mut state.candidate_pow_receipt_roots, vec![]);
+    if let Some(winner) = candidates
+        .iter()
+        .find(|c| c.votes.saturating_mul(2) > voting_period)
+    {
+        state.processed_pow_receipt_root = winner.candidate_pow_receipt_root;
+    }
+}
+
+/// Induct the validators which made the given `deposits` into the `state`, with a status of
+/// `PendingActivation`. They will become active during a subsequent validator set change.
+///
+/// Deposits with an invalid registration, or for a pubkey which is already registered, are
+/// ignored.
+pub fn process_deposits(
+    state: &mut BeaconState,
+    deposits: &[ValidatorRegistration],
+    slot: u64,
+    config: &ChainConfig,
+) {
+    let validators = mem::replace(&mut state.validators, vec![]);
+    let mut registered: HashSet<Vec<u8>> = validators.iter().map(|v| v.pubkey.as_bytes()).collect();
+    let mut inductor = ValidatorInductor::new(slot, config.shard_count, validators);
+    for deposit in deposits {
+        let pubkey = deposit.pubkey.as_bytes();
+        if registered.contains(&pubkey) {
+            continue;
+        }
+        if inductor
+            .induct(deposit, ValidatorStatus::PendingActivation)
+            .is_ok()
+        {
+            registered.insert(pubkey);
+        }
+    }
+    state.validators = inductor.to_vec();
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bls;
+
+    use self::bls::{create_proof_of_possession, Keypair};
+    use super::*;
+    use types::{Address, DomainType, ForkData};
+
+    fn test_config() -> ChainConfig {
+        let mut config = ChainConfig::standard();
+        config.cycle_length = 4;
+        config.pow_receipt_root_voting_period = 8;
+        config
+    }
+
+    fn candidate(root: &Hash256, votes: u64) -> CandidatePoWReceiptRootRecord {
+        CandidatePoWReceiptRootRecord {
+            candidate_pow_receipt_root: *root,
+            votes,
+        }
+    }
+
+    fn pop_domain() -> u64 {
+        ForkData::genesis().domain(0, DomainType::ProofOfPossession)
+    }
+
+    fn registration(keypair: &Keypair) -> ValidatorRegistration {
+        ValidatorRegistration {
+            pubkey: keypair.pk.clone(),
+            withdrawal_shard: 0,
+            withdrawal_address: Address::random(),
+            randao_commitment: Hash256::random(),
+            proof_of_possession: create_proof_of_possession(keypair, pop_domain()),
+        }
+    }
+
+    #[test]
+    fn test_record_pow_receipt_root_votes() {
+        let mut state = BeaconState::zero();
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+
+        record_pow_receipt_root_vote(&mut state, &a);
+        record_pow_receipt_root_vote(&mut state, &b);
+        record_pow_receipt_root_vote(&mut state, &a);
+
+        assert_eq!(
+            state.candidate_pow_receipt_roots,
+            vec![candidate(&a, 2), candidate(&b, 1)]
+        );
+    }
+
+    #[test]
+    fn test_process_pow_receipt_roots_majority() {
+        let config = test_config();
+        let mut state = BeaconState::zero();
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+        state.candidate_pow_receipt_roots = vec![candidate(&a, 3), candidate(&b, 5)];
+
+        /*
+         * The votes are not tallied until the end of the voting period.
+         */
+        process_pow_receipt_roots(&mut state, 4, &config);
+        assert_eq!(state.processed_pow_receipt_root, Hash256::zero());
+        assert_eq!(state.candidate_pow_receipt_roots.len(), 2);
+
+        process_pow_receipt_roots(&mut state, 8, &config);
+        assert_eq!(state.processed_pow_receipt_root, b);
+        assert_eq!(state.candidate_pow_receipt_roots, vec![]);
+    }
+
+    #[test]
+    fn test_process_pow_receipt_roots_no_majority() {
+        let config = test_config();
+        let mut state = BeaconState::zero();
+        let a = Hash256::from("a".as_bytes());
+        let b = Hash256::from("b".as_bytes());
+        state.processed_pow_receipt_root = a;
+        state.candidate_pow_receipt_roots = vec![candidate(&a, 4), candidate(&b, 4)];
+
+        process_pow_receipt_roots(&mut state, 8, &config);
+
+        assert_eq!(state.processed_pow_receipt_root, a);
+        assert_eq!(state.candidate_pow_receipt_roots, vec![]);
+    }
+
+    #[test]
+    fn test_process_deposits() {
+        let config = test_config();
+        let mut state = BeaconState::zero();
+
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::random()).collect();
+        let mut deposits: Vec<ValidatorRegistration> = keypairs.iter().map(registration).collect();
+        /*
+         * Give the last deposit a proof-of-possession for the wrong key.
+         */
+        deposits[2].proof_of_possession = create_proof_of_possession(&keypairs[0], pop_domain());
+        /*
+         * Repeat the deposit of the first key.
+         */
+        deposits.push(registration(&keypairs[0]));
+
+        process_deposits(&mut state, &deposits, 16, &config);
+
+        assert_eq!(state.validators.len(), 2);
+        for (validator, keypair) in state.validators.iter().zip(keypairs.iter()) {
+            assert_eq!(validator.pubkey, keypair.pk);
+            assert_eq!(validator.status, ValidatorStatus::PendingActivation as u8);
+            assert_eq!(validator.randao_last_change, 16);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use super::attesters::{attesting_indices, block_proposer_index, is_supermajority, total_balance};
+use super::StateTransitionError;
+use active_validators::validator_is_active;
+use std::collections::HashMap;
+use types::{BeaconState, PendingAttestationRecord};
+
+/// The divisor applied to the balance of a validator to determine the amount by which it is
+/// rewarded (or penalized) for attesting (or failing to attest) to a slot.
+pub const BASE_REWARD_QUOTIENT: u64 = 1 << 15;
+
+/// The divisor applied to the base reward of an attester to determine the reward for the proposer
+/// which included the attestation in the very next slot.
+pub const INCLUDER_REWARD_QUOTIENT: u64 = 1 << 3;
+
+/// Apply the rewards and penalties for the cycle starting at the `last_state_recalculation_slot`
+/// of the `state`.
+///
+/// For each slot in the cycle, each active validator assigned to attest to the slot is:
+///
+/// - Rewarded `balance / BASE_REWARD_QUOTIENT` if it attested and the slot was justified by a 2/3
+/// supermajority of the assigned balance.
+/// - Penalized `balance / BASE_REWARD_QUOTIENT` if it did not attest.
+///
+/// Additionally, the proposer of the block which first included each attester's attestation is
+/// rewarded `base_reward / INCLUDER_REWARD_QUOTIENT / inclusion_distance`, where the inclusion
+/// distance is the number of slots between the attestation and the block.
+///
+/// The attestations to the last slot of the cycle are included in the blocks of the following
+/// cycle, so the cycle should only be tallied once that cycle has passed (see
+/// `cycle_transition_due`), otherwise the validators assigned to that slot are penalized.
+///
+/// Returns an error if any balance would overflow.
+pub fn process_rewards(
+    state: &mut BeaconState,
+    pending_attestations: &[PendingAttestationRecord],
+    cycle_length: u8,
+) -> Result<(), StateTransitionError> {
+    let cycle_start = state.last_state_recalculation_slot;
+
+    for i in 0..u64::from(cycle_length) {
+        let slot = cycle_start.saturating_add(i);
+        let shard_and_committees = state
+            .shard_and_committee_for_slots
+            .get(i as usize)
+            .ok_or(StateTransitionError::InvalidShardAndCommitteeForSlots)?;
+
+        /*
+         * Map each validator which attested to this slot to the earliest slot in which their
+         * attestation was included.
+         */
+        let mut attesters: HashMap<usize, u64> = HashMap::new();
+        for pending in pending_attestations
+            .iter()
+            .filter(|p| p.attestation.slot == slot)
+        {
+            let committee = match shard_and_committees
+                .iter()
+                .find(|sac| sac.shard == pending.attestation.shard_id)
+            {
+                Some(sac) => &sac.committee,
+                None => continue,
+            };
+            for index in attesting_indices(committee, &pending.attestation.attester_bitfield) {
+                let slot_included = attesters.entry(index).or_insert(pending.slot_included);
+                *slot_included = (*slot_included).min(pending.slot_included);
+            }
+        }
+
+        let assigned: Vec<usize> = shard_and_committees
+            .iter()
+            .flat_map(|sac| sac.committee.iter().cloned())
+            .collect();
+        let assigned_balance = total_balance(&state.validators, assigned.iter());
+        let attesting_balance = total_balance(&state.validators, attesters.keys());
+        let justified = is_supermajority(attesting_balance, assigned_balance);
+
+        /*
+         * Determine all rewards and penalties for the slot before applying any of them, so the
+         * base reward of each validator is determined from its balance at the start of the slot.
+         */
+        let mut rewards: Vec<(usize, u64)> = vec![];
+        let mut penalties: Vec<(usize, u64)> = vec![];
+        for index in &assigned {
+            let validator = match state.validators.get(*index) {
+                Some(v) if validator_is_active(v) => v,
+                _ => continue,
+            };
+            let base_reward = validator.balance / BASE_REWARD_QUOTIENT;
+
+            match attesters.get(index) {
+                Some(slot_included) => {
+                    if justified {
+                        rewards.push((*index, base_reward));
+                    }
+                    if let Some(proposer) = block_proposer_index(state, *slot_included) {
+                        let inclusion_distance = slot_included.saturating_sub(slot).max(1);
+                        rewards.push((
+                            proposer,
+                            base_reward / INCLUDER_REWARD_QUOTIENT / inclusion_distance,
+                        ));
+                    }
+                }
+                None => penalties.push((*index, base_reward)),
+            }
+        }
+
+        for (index, reward) in rewards {
+            if let Some(v) = state.validators.get_mut(index) {
+                v.balance = v
+                    .balance
+                    .checked_add(reward)
+                    .ok_or(StateTransitionError::ArithmeticOverflow)?;
+            }
+        }
+        for (index, penalty) in penalties {
+            if let Some(v) = state.validators.get_mut(index) {
+                v.balance = v
+                    .balance
+                    .checked_sub(penalty)
+                    .ok_or(StateTransitionError::ArithmeticOverflow)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{
+        AttestationRecord, Bitfield, CrosslinkRecord, ShardAndCommittee, ValidatorRecord,
+        ValidatorStatus,
+    };
+
+    const CYCLE_LENGTH: u8 = 2;
+    const BALANCE: u64 = BASE_REWARD_QUOTIENT * 1_000;
+    const BASE_REWARD: u64 = 1_000;
+
+    /// A state with six validators, where validators 0, 1 and 2 form the committee for shard 0 at
+    /// even slots, and validators 3, 4 and 5 form the committee for shard 1 at odd slots.
+    fn test_state() -> BeaconState {
+        let validators: Vec<ValidatorRecord> = (0..6)
+            .map(|_| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = ValidatorStatus::Active as u8;
+                v.balance = BALANCE;
+                v
+            }).collect();
+
+        let even = vec![ShardAndCommittee {
+            shard: 0,
+            committee: vec![0, 1, 2],
+        }];
+        let odd = vec![ShardAndCommittee {
+            shard: 1,
+            committee: vec![3, 4, 5],
+        }];
+
+        BeaconState {
+            validators,
+            crosslinks: vec![CrosslinkRecord::zero(); 2],
+            shard_and_committee_for_slots: vec![even.clone(), odd.clone(), even, odd],
+            ..BeaconState::zero()
+        }
+    }
+
+    fn pending(
+        slot: u64,
+        shard_id: u16,
+        bits: &[bool],
+        slot_included: u64,
+    ) -> PendingAttestationRecord {
+        let mut a = AttestationRecord::zero();
+        a.slot = slot;
+        a.shard_id = shard_id;
+        a.attester_bitfield = Bitfield::from_elem(bits.len(), false);
+        for (i, bit) in bits.iter().enumerate() {
+            a.attester_bitfield.set(i, *bit);
+        }
+        PendingAttestationRecord {
+            attestation: a,
+            slot_included,
+        }
+    }
+
+    fn balances(state: &BeaconState) -> Vec<u64> {
+        state.validators.iter().map(|v| v.balance).collect()
+    }
+
+    #[test]
+    fn test_rewards_and_penalties() {
+        let mut state = test_state();
+
+        /*
+         * Slot 0 is justified by its full committee and included by the proposer of slot 1
+         * (validator 4). Slot 1 is attested by only validator 3 and included by the proposer of
+         * slot 2 (validator 2).
+         */
+        let attestations = vec![
+            pending(0, 0, &[true, true, true], 1),
+            pending(1, 1, &[true, false, false], 2),
+        ];
+
+        process_rewards(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        let includer_reward = BASE_REWARD / INCLUDER_REWARD_QUOTIENT;
+        assert_eq!(
+            balances(&state),
+            vec![
+                BALANCE + BASE_REWARD,
+                BALANCE + BASE_REWARD,
+                BALANCE + BASE_REWARD + includer_reward,
+                BALANCE,
+                BALANCE - BASE_REWARD + includer_reward * 3,
+                BALANCE - BASE_REWARD,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rewards_inclusion_distance() {
+        let mut state = test_state();
+
+        /*
+         * Slot 0 is included two slots later by the proposer of slot 2 (validator 2). The first
+         * inclusion of validator 0's attestation is the one which is rewarded.
+         */
+        let attestations = vec![
+            pending(0, 0, &[true, true, true], 2),
+            pending(0, 0, &[true, false, false], 1),
+            pending(1, 1, &[true, true, true], 2),
+        ];
+
+        process_rewards(&mut state, &attestations, CYCLE_LENGTH).unwrap();
+
+        let includer_reward = BASE_REWARD / INCLUDER_REWARD_QUOTIENT;
+        assert_eq!(
+            balances(&state),
+            vec![
+                BALANCE + BASE_REWARD,
+                BALANCE + BASE_REWARD,
+                BALANCE + BASE_REWARD + includer_reward / 2 * 2 + includer_reward * 3,
+                BALANCE + BASE_REWARD,
+                BALANCE + BASE_REWARD + includer_reward,
+                BALANCE + BASE_REWARD,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rewards_overflow() {
+        let mut state = test_state();
+        state.validators[0].balance = u64::max_value();
+
+        let attestations = vec![pending(0, 0, &[true, true, true], 1)];
+
+        assert_eq!(
+            process_rewards(&mut state, &attestations, CYCLE_LENGTH),
+            Err(StateTransitionError::ArithmeticOverflow)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use super::StateTransitionError;
+use active_validators::validator_is_active;
+use types::{BeaconState, SpecialPayload, SpecialRecord, ValidatorStatus};
+
+/// The divisor applied to the balance of a slashed validator to determine its penalty.
+pub const SLASHING_PENALTY_QUOTIENT: u64 = 1 << 5;
+
+/// Apply the effects of the `pending_specials` to the validators of the `state`.
+///
+/// - `Logout`: an active validator is marked `PendingExit`.
+/// - `CasperSlashing`: each validator which signed both votes is marked `Penalized` and loses
+/// `balance / SLASHING_PENALTY_QUOTIENT`.
+/// - `RandaoChange`: the proposer's `randao_commitment` is replaced, the change being recorded as
+/// occurring at the slot of the block which revealed the new commitment.
+///
+/// Any exit is recorded as occurring at `slot`. Specials which refer to unknown
+/// validators, or validators which are not in the required state, are ignored.
+///
+/// The specials are assumed to have been validated when their blocks were processed, however
+/// an error is returned if a payload cannot be decoded.
+pub fn process_specials(
+    state: &mut BeaconState,
+    pending_specials: &[SpecialRecord],
+    slot: u64,
+) -> Result<(), StateTransitionError> {
+    for special in pending_specials {
+        let payload = special
+            .payload()
+            .map_err(|_| StateTransitionError::InvalidSpecialRecord)?;
+
+        match payload {
+            SpecialPayload::Logout(logout) => {
+                if let Some(v) = state.validators.get_mut(logout.validator_index) {
+                    if validator_is_active(v) {
+                        v.status = ValidatorStatus::PendingExit as u8;
+                        v.exit_slot = slot;
+                    }
+                }
+            }
+            SpecialPayload::CasperSlashing(slashing) => {
+                for index in slashing.slashable_indices() {
+                    if let Some(v) = state.validators.get_mut(index) {
+                        if v.status == ValidatorStatus::Penalized as u8 {
+                            continue;
+                        }
+                        let penalty = v.balance / SLASHING_PENALTY_QUOTIENT;
+                        v.balance = v
+                            .balance
+                            .checked_sub(penalty)
+                            .ok_or(StateTransitionError::ArithmeticOverflow)?;
+                        v.status = ValidatorStatus::Penalized as u8;
+                        v.exit_slot = slot;
+                    }
+                }
+            }
+            SpecialPayload::RandaoChange(randao_change) => {
+                if let Some(v) = state.validators.get_mut(randao_change.proposer_index) {
+                    v.randao_commitment = randao_change.new_randao_commitment;
+                    v.randao_last_change = randao_change.slot;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate bls;
+    extern crate ssz;
+
+    use self::bls::{AggregateSignature, Keypair, Signature};
+    use self::ssz::ssz_encode;
+    use super::*;
+    use types::{
+        CasperSlashingSpecial, CrosslinkRecord, Hash256, LogoutSpecial, RandaoChangeSpecial,
+        SlashableVote, ValidatorRecord, LOGOUT_MESSAGE,
+    };
+
+    const BALANCE: u64 = SLASHING_PENALTY_QUOTIENT * 100;
+
+    fn test_state(validator_count: usize) -> (BeaconState, Vec<Keypair>) {
+        let mut keypairs = vec![];
+        let validators: Vec<ValidatorRecord> = (0..validator_count)
+            .map(|_| {
+                let (mut v, keypair) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = ValidatorStatus::Active as u8;
+                v.balance = BALANCE;
+                keypairs.push(keypair);
+                v
+            }).collect();
+
+        let state = BeaconState {
+            validators,
+            crosslinks: vec![CrosslinkRecord::zero(); 2],
+            ..BeaconState::zero()
+        };
+        (state, keypairs)
+    }
+
+    fn vote(slot: u64, justified_slot: u64, aggregate_sig_indices: &[usize]) -> SlashableVote {
+        SlashableVote {
+            aggregate_sig_indices: aggregate_sig_indices.to_vec(),
+            slot,
+            parent_hashes: vec![],
+            shard_id: 0,
+            shard_block_hash: Hash256::zero(),
+            justified_slot,
+            aggregate_sig: AggregateSignature::new(),
+        }
+    }
+
+    #[test]
+    fn test_process_logout() {
+        let (mut state, keypairs) = test_state(2);
+        state.validators[1].status = ValidatorStatus::PendingActivation as u8;
+
+        let specials: Vec<SpecialRecord> = (0..2)
+            .map(|i| {
+                let logout = LogoutSpecial {
+                    validator_index: i,
+                    signature: Signature::new(LOGOUT_MESSAGE, &keypairs[i].sk),
+                };
+                SpecialRecord::logout(&ssz_encode(&logout))
+            }).collect();
+
+        process_specials(&mut state, &specials, 12).unwrap();
+
+        assert_eq!(
+            state.validators[0].status,
+            ValidatorStatus::PendingExit as u8
+        );
+        assert_eq!(state.validators[0].exit_slot, 12);
+        /*
+         * Only active validators may log out.
+         */
+        assert_eq!(
+            state.validators[1].status,
+            ValidatorStatus::PendingActivation as u8
+        );
+        assert_eq!(state.validators[1].exit_slot, 0);
+    }
+
+    #[test]
+    fn test_process_casper_slashing() {
+        let (mut state, _) = test_state(4);
+
+        let slashing = CasperSlashingSpecial {
+            vote_1: vote(8, 2, &[0, 1, 2]),
+            vote_2: vote(6, 4, &[1, 2, 3]),
+        };
+        let special = SpecialRecord::casper_slashing(&ssz_encode(&slashing));
+
+        /*
+         * Including the same evidence twice only penalizes the validators once.
+         */
+        process_specials(&mut state, &[special.clone(), special], 12).unwrap();
+
+        for i in 0..4 {
+            let v = &state.validators[i];
+            if i == 1 || i == 2 {
+                assert_eq!(v.status, ValidatorStatus::Penalized as u8);
+                assert_eq!(v.balance, BALANCE - BALANCE / SLASHING_PENALTY_QUOTIENT);
+                assert_eq!(v.exit_slot, 12);
+            } else {
+                assert_eq!(v.status, ValidatorStatus::Active as u8);
+                assert_eq!(v.balance, BALANCE);
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_randao_change() {
+        let (mut state, _) = test_state(2);
+        let commitment = Hash256::from("new_commitment".as_bytes());
+
+        let randao_change = RandaoChangeSpecial {
+            proposer_index: 1,
+            new_randao_commitment: commitment,
+            slot: 7,
+        };
+        let special = SpecialRecord::randao_change(&ssz_encode(&randao_change));
+
+        process_specials(&mut state, &[special], 12).unwrap();
+
+        /*
+         * The change is recorded at the slot of the reveal, rather than that of the processing.
+         */
+        assert_eq!(state.validators[0].randao_commitment, Hash256::zero());
+        assert_eq!(state.validators[1].randao_commitment, commitment);
+        assert_eq!(state.validators[1].randao_last_change, 7);
+    }
+
+    #[test]
+    fn test_process_bad_special() {
+        let (mut state, _) = test_state(2);
+
+        assert_eq!(
+            process_specials(&mut state, &[SpecialRecord::logout(&[42])], 12),
+            Err(StateTransitionError::InvalidSpecialRecord)
+        );
+    }
+}
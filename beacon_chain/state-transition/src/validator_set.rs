@@ -0,0 +1,151 @@
+use super::StateTransitionError;
+use types::{BeaconState, ChainConfig};
+use validator_change::{update_validator_set, ValidatorChangeRecord};
+
+/// Returns `true` if the validator set of the `state` may be changed.
+///
+/// A change requires that a slot after the last change has been finalized and that every shard
+/// assigned in the `shard_and_committee_for_slots` has been crosslinked since the last change.
+pub fn validator_set_change_due(state: &BeaconState) -> bool {
+    let change_slot = state.validator_set_change_slot;
+
+    state.last_finalized_slot > change_slot
+        && state
+            .shard_and_committee_for_slots
+            .iter()
+            .flat_map(|slot| slot.iter())
+            .all(|sac| {
+                state
+                    .crosslinks
+                    .get(sac.shard as usize)
+                    .map_or(false, |crosslink| crosslink.slot > change_slot)
+            })
+}
+
+/// If a validator set change is due, activate and exit validators (see `update_validator_set`),
+/// recording the change as occurring at `slot`.
+///
+/// Returns the changes appended to the `validator_set_delta_hash_chain`, or `None` if no change
+/// was due.
+pub fn process_validator_set_change(
+    state: &mut BeaconState,
+    slot: u64,
+    config: &ChainConfig,
+) -> Result<Option<Vec<ValidatorChangeRecord>>, StateTransitionError> {
+    if !validator_set_change_due(state) {
+        return Ok(None);
+    }
+
+    let (hash_chain, records) = update_validator_set(
+        &mut state.validators,
+        state.validator_set_delta_hash_chain,
+        slot,
+        config.deposit_size_gwei,
+        config.max_validator_churn_quotient,
+    )?;
+    state.validator_set_delta_hash_chain = hash_chain;
+    state.validator_set_change_slot = slot;
+
+    Ok(Some(records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{CrosslinkRecord, Hash256, ShardAndCommittee, ValidatorRecord, ValidatorStatus};
+    use validator_change::VALIDATOR_FLAG_ENTRY;
+
+    /// A state in which shards 0 and 1 are assigned, and a validator set change is due.
+    fn test_state() -> BeaconState {
+        let validators: Vec<ValidatorRecord> = (0..4)
+            .map(|i| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = if i < 3 {
+                    ValidatorStatus::Active as u8
+                } else {
+                    ValidatorStatus::PendingActivation as u8
+                };
+                v
+            }).collect();
+
+        let crosslink = CrosslinkRecord {
+            recently_changed: false,
+            slot: 8,
+            hash: Hash256::zero(),
+        };
+        let shard_and_committee_for_slots = (0..2)
+            .map(|shard| {
+                vec![ShardAndCommittee {
+                    shard,
+                    committee: vec![0, 1, 2],
+                }]
+            }).collect();
+
+        BeaconState {
+            validator_set_change_slot: 4,
+            validators,
+            crosslinks: vec![crosslink; 3],
+            last_state_recalculation_slot: 8,
+            last_finalized_slot: 6,
+            last_justified_slot: 6,
+            shard_and_committee_for_slots,
+            ..BeaconState::zero()
+        }
+    }
+
+    #[test]
+    fn test_validator_set_change_due() {
+        let state = test_state();
+        assert!(validator_set_change_due(&state));
+
+        /*
+         * No slot has been finalized since the last change.
+         */
+        let mut state = test_state();
+        state.last_finalized_slot = 4;
+        assert!(!validator_set_change_due(&state));
+
+        /*
+         * An assigned shard has not been crosslinked since the last change.
+         */
+        let mut state = test_state();
+        state.crosslinks[1].slot = 4;
+        assert!(!validator_set_change_due(&state));
+
+        /*
+         * An unassigned shard does not need to be crosslinked.
+         */
+        let mut state = test_state();
+        state.crosslinks[2].slot = 0;
+        assert!(validator_set_change_due(&state));
+    }
+
+    #[test]
+    fn test_process_validator_set_change() {
+        let config = ChainConfig::standard();
+
+        let mut state = test_state();
+        let pubkey = state.validators[3].pubkey.clone();
+        assert_eq!(
+            process_validator_set_change(&mut state, 8, &config),
+            Ok(Some(vec![ValidatorChangeRecord {
+                index: 3,
+                pubkey,
+                flag: VALIDATOR_FLAG_ENTRY,
+            }]))
+        );
+        assert_eq!(state.validators[3].status, ValidatorStatus::Active as u8);
+        assert_eq!(state.validator_set_change_slot, 8);
+        assert!(!state.validator_set_delta_hash_chain.is_zero());
+
+        /*
+         * A second change is not due until further finalization and crosslinking.
+         */
+        let before = state.clone();
+        assert_eq!(
+            process_validator_set_change(&mut state, 12, &config),
+            Ok(None)
+        );
+        assert_eq!(state, before);
+    }
+}
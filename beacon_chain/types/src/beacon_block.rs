@@ -8,12 +8,14 @@ pub const MIN_SSZ_BLOCK_LENGTH: usize = {
     32 +                // randao_reveal
     32 +                // pow_chain_reference
     4 +                 // ancestor hashes (assuming empty)
-    32 +                // active_state_root
-    32 +                // crystallized_state_root
+    32 +                // state_root
     4 +                 // attestations (assuming empty)
     4 // specials (assuming empty)
 };
 pub const MAX_SSZ_BLOCK_LENGTH: usize = MIN_SSZ_BLOCK_LENGTH + (1 << 24);
+/// The number of `ancestor_hashes` in a block. The `i`'th hash is the most recent ancestor with a
+/// slot that is a multiple of `2^i`.
+pub const ANCESTOR_HASHES_LEN: usize = 32;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct BeaconBlock {
@@ -21,8 +23,7 @@ pub struct BeaconBlock {
     pub randao_reveal: Hash256,
     pub pow_chain_reference: Hash256,
     pub ancestor_hashes: Vec<Hash256>,
-    pub active_state_root: Hash256,
-    pub crystallized_state_root: Hash256,
+    pub state_root: Hash256,
     pub attestations: Vec<AttestationRecord>,
     pub specials: Vec<SpecialRecord>,
 }
@@ -34,8 +35,7 @@ impl BeaconBlock {
             randao_reveal: Hash256::zero(),
             pow_chain_reference: Hash256::zero(),
             ancestor_hashes: vec![],
-            active_state_root: Hash256::zero(),
-            crystallized_state_root: Hash256::zero(),
+            state_root: Hash256::zero(),
             attestations: vec![],
             specials: vec![],
         }
@@ -49,14 +49,35 @@ impl BeaconBlock {
     }
 }
 
+/// Returns the `ancestor_hashes` for a child of the given parent block.
+///
+/// The `i`'th ancestor hash is the parent hash if the parent slot is a multiple of `2^i`,
+/// otherwise it is inherited from the parent.
+pub fn child_ancestor_hashes(
+    parent_hash: &Hash256,
+    parent_slot: u64,
+    parent_ancestor_hashes: &[Hash256],
+) -> Vec<Hash256> {
+    (0..ANCESTOR_HASHES_LEN)
+        .map(|i| {
+            if parent_slot % (1 << i) == 0 {
+                *parent_hash
+            } else {
+                parent_ancestor_hashes
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(Hash256::zero)
+            }
+        }).collect()
+}
+
 impl Encodable for BeaconBlock {
     fn ssz_append(&self, s: &mut SszStream) {
         s.append(&self.slot);
         s.append(&self.randao_reveal);
         s.append(&self.pow_chain_reference);
         s.append_vec(&self.ancestor_hashes);
-        s.append(&self.active_state_root);
-        s.append(&self.crystallized_state_root);
+        s.append(&self.state_root);
         s.append_vec(&self.attestations);
         s.append_vec(&self.specials);
     }
@@ -68,8 +89,7 @@ impl Decodable for BeaconBlock {
         let (randao_reveal, i) = Hash256::ssz_decode(bytes, i)?;
         let (pow_chain_reference, i) = Hash256::ssz_decode(bytes, i)?;
         let (ancestor_hashes, i) = Decodable::ssz_decode(bytes, i)?;
-        let (active_state_root, i) = Hash256::ssz_decode(bytes, i)?;
-        let (crystallized_state_root, i) = Hash256::ssz_decode(bytes, i)?;
+        let (state_root, i) = Hash256::ssz_decode(bytes, i)?;
         let (attestations, i) = Decodable::ssz_decode(bytes, i)?;
         let (specials, i) = Decodable::ssz_decode(bytes, i)?;
         let block = BeaconBlock {
@@ -77,8 +97,7 @@ impl Decodable for BeaconBlock {
             randao_reveal,
             pow_chain_reference,
             ancestor_hashes,
-            active_state_root,
-            crystallized_state_root,
+            state_root,
             attestations,
             specials,
         };
@@ -97,8 +116,7 @@ mod tests {
         assert!(b.randao_reveal.is_zero());
         assert!(b.pow_chain_reference.is_zero());
         assert_eq!(b.ancestor_hashes, vec![]);
-        assert!(b.active_state_root.is_zero());
-        assert!(b.crystallized_state_root.is_zero());
+        assert!(b.state_root.is_zero());
         assert_eq!(b.attestations.len(), 0);
         assert_eq!(b.specials.len(), 0);
     }
@@ -139,4 +157,27 @@ mod tests {
 
         assert_eq!(b.parent_hash().unwrap(), &Hash256::from("cats".as_bytes()));
     }
+
+    #[test]
+    fn test_child_ancestor_hashes() {
+        let parent_hash = Hash256::from("parent".as_bytes());
+        let parent_ancestor_hashes: Vec<Hash256> = (0..ANCESTOR_HASHES_LEN)
+            .map(|i| Hash256::from(i as u64))
+            .collect();
+
+        /*
+         * A parent at slot zero is a multiple of every power of two.
+         */
+        assert_eq!(
+            child_ancestor_hashes(&parent_hash, 0, &parent_ancestor_hashes),
+            vec![parent_hash; ANCESTOR_HASHES_LEN]
+        );
+
+        /*
+         * A parent at slot 6 (0b110) is a multiple of 1 and 2 only.
+         */
+        let ancestor_hashes = child_ancestor_hashes(&parent_hash, 6, &parent_ancestor_hashes);
+        assert_eq!(ancestor_hashes[0..2], [parent_hash, parent_hash]);
+        assert_eq!(ancestor_hashes[2..], parent_ancestor_hashes[2..]);
+    }
 }
@@ -1,7 +1,48 @@
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
 use super::Hash256;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CandidatePoWReceiptRootRecord {
     pub candidate_pow_receipt_root: Hash256,
     pub votes: u64,
 }
+
+impl Encodable for CandidatePoWReceiptRootRecord {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.candidate_pow_receipt_root);
+        s.append(&self.votes);
+    }
+}
+
+impl Decodable for CandidatePoWReceiptRootRecord {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (candidate_pow_receipt_root, i) = Hash256::ssz_decode(bytes, i)?;
+        let (votes, i) = u64::ssz_decode(bytes, i)?;
+
+        let record = Self {
+            candidate_pow_receipt_root,
+            votes,
+        };
+        Ok((record, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_pow_receipt_root_record_ssz_encode_decode() {
+        let original = CandidatePoWReceiptRootRecord {
+            candidate_pow_receipt_root: Hash256::from("receipt_root".as_bytes()),
+            votes: 42,
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) =
+            CandidatePoWReceiptRootRecord::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
@@ -0,0 +1,206 @@
+use super::bls::AggregateSignature;
+use super::ssz::{decode_ssz_list, Decodable, DecodeError, Encodable, SszStream};
+use super::Hash256;
+
+/// The signed fields of an attestation, along with the validators which signed them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlashableVote {
+    pub aggregate_sig_indices: Vec<usize>,
+    pub slot: u64,
+    pub parent_hashes: Vec<Hash256>,
+    pub shard_id: u16,
+    pub shard_block_hash: Hash256,
+    pub justified_slot: u64,
+    pub aggregate_sig: AggregateSignature,
+}
+
+impl SlashableVote {
+    /// Returns `true` if the signed fields of `self` and `other` are identical.
+    pub fn has_same_data(&self, other: &SlashableVote) -> bool {
+        self.slot == other.slot
+            && self.parent_hashes == other.parent_hashes
+            && self.shard_id == other.shard_id
+            && self.shard_block_hash == other.shard_block_hash
+            && self.justified_slot == other.justified_slot
+    }
+
+    /// Returns `true` if `self` is a vote from an earlier justified slot to a later slot than
+    /// `other`.
+    pub fn surrounds(&self, other: &SlashableVote) -> bool {
+        self.justified_slot < other.justified_slot && other.slot < self.slot
+    }
+}
+
+impl Encodable for SlashableVote {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append_vec(&self.aggregate_sig_indices);
+        s.append(&self.slot);
+        s.append_vec(&self.parent_hashes);
+        s.append(&self.shard_id);
+        s.append(&self.shard_block_hash);
+        s.append(&self.justified_slot);
+        s.append_vec(&self.aggregate_sig.as_bytes());
+    }
+}
+
+impl Decodable for SlashableVote {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (aggregate_sig_indices, i) = decode_ssz_list(bytes, i)?;
+        let (slot, i) = u64::ssz_decode(bytes, i)?;
+        let (parent_hashes, i) = decode_ssz_list(bytes, i)?;
+        let (shard_id, i) = u16::ssz_decode(bytes, i)?;
+        let (shard_block_hash, i) = Hash256::ssz_decode(bytes, i)?;
+        let (justified_slot, i) = u64::ssz_decode(bytes, i)?;
+        let (agg_sig_bytes, i) = decode_ssz_list(bytes, i)?;
+        let aggregate_sig =
+            AggregateSignature::from_bytes(&agg_sig_bytes).map_err(|_| DecodeError::TooShort)?;
+
+        let vote = Self {
+            aggregate_sig_indices,
+            slot,
+            parent_hashes,
+            shard_id,
+            shard_block_hash,
+            justified_slot,
+            aggregate_sig,
+        };
+        Ok((vote, i))
+    }
+}
+
+/// The payload of a `SpecialRecord` with the `CasperSlashing` kind.
+///
+/// Provides evidence that some validators have signed two votes which violate the Casper FFG
+/// slashing conditions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CasperSlashingSpecial {
+    pub vote_1: SlashableVote,
+    pub vote_2: SlashableVote,
+}
+
+impl CasperSlashingSpecial {
+    /// Returns `true` if the votes are different and either:
+    ///
+    /// - Both votes are for the same slot (a "double vote"), or
+    /// - One vote surrounds the other (a "surround vote").
+    pub fn is_slashable(&self) -> bool {
+        !self.vote_1.has_same_data(&self.vote_2)
+            && (self.vote_1.slot == self.vote_2.slot
+                || self.vote_1.surrounds(&self.vote_2)
+                || self.vote_2.surrounds(&self.vote_1))
+    }
+
+    /// Returns the (sorted, de-duplicated) indices of the validators which signed both votes.
+    pub fn slashable_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .vote_1
+            .aggregate_sig_indices
+            .iter()
+            .filter(|i| self.vote_2.aggregate_sig_indices.contains(i))
+            .cloned()
+            .collect();
+        indices.sort();
+        indices.dedup();
+        indices
+    }
+}
+
+impl Encodable for CasperSlashingSpecial {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.vote_1);
+        s.append(&self.vote_2);
+    }
+}
+
+impl Decodable for CasperSlashingSpecial {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (vote_1, i) = SlashableVote::ssz_decode(bytes, i)?;
+        let (vote_2, i) = SlashableVote::ssz_decode(bytes, i)?;
+        Ok((Self { vote_1, vote_2 }, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bls::{Keypair, Signature};
+    use super::*;
+
+    fn vote(slot: u64, justified_slot: u64, aggregate_sig_indices: &[usize]) -> SlashableVote {
+        let mut aggregate_sig = AggregateSignature::new();
+        aggregate_sig.add(&Signature::new(&[42], &Keypair::random().sk));
+        SlashableVote {
+            aggregate_sig_indices: aggregate_sig_indices.to_vec(),
+            slot,
+            parent_hashes: vec![Hash256::from("parent".as_bytes())],
+            shard_id: 3,
+            shard_block_hash: Hash256::from("shard_block".as_bytes()),
+            justified_slot,
+            aggregate_sig,
+        }
+    }
+
+    #[test]
+    fn test_casper_slashing_special_ssz_encode_decode() {
+        let original = CasperSlashingSpecial {
+            vote_1: vote(10, 5, &[1, 2, 3]),
+            vote_2: vote(10, 6, &[2, 3, 4]),
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = CasperSlashingSpecial::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_casper_slashing_special_is_slashable() {
+        /*
+         * Double vote.
+         */
+        let mut vote_2 = vote(10, 5, &[]);
+        vote_2.shard_block_hash = Hash256::from("other_shard_block".as_bytes());
+        let special = CasperSlashingSpecial {
+            vote_1: vote(10, 5, &[]),
+            vote_2,
+        };
+        assert!(special.is_slashable());
+
+        /*
+         * Surround vote, in either order.
+         */
+        let special = CasperSlashingSpecial {
+            vote_1: vote(12, 4, &[]),
+            vote_2: vote(10, 5, &[]),
+        };
+        assert!(special.is_slashable());
+        let special = CasperSlashingSpecial {
+            vote_1: vote(10, 5, &[]),
+            vote_2: vote(12, 4, &[]),
+        };
+        assert!(special.is_slashable());
+
+        /*
+         * Identical votes and consecutive votes are not slashable.
+         */
+        let special = CasperSlashingSpecial {
+            vote_1: vote(10, 5, &[]),
+            vote_2: vote(10, 5, &[]),
+        };
+        assert!(!special.is_slashable());
+        let special = CasperSlashingSpecial {
+            vote_1: vote(10, 5, &[]),
+            vote_2: vote(12, 10, &[]),
+        };
+        assert!(!special.is_slashable());
+    }
+
+    #[test]
+    fn test_casper_slashing_special_slashable_indices() {
+        let special = CasperSlashingSpecial {
+            vote_1: vote(10, 5, &[4, 1, 2, 3, 2]),
+            vote_2: vote(10, 6, &[2, 3, 5, 4]),
+        };
+        assert_eq!(special.slashable_indices(), vec![2, 3, 4]);
+    }
+}
@@ -7,6 +7,8 @@ pub struct ChainConfig {
     pub shard_count: u16,
     pub min_committee_size: u64,
     pub max_validator_churn_quotient: u64,
+    pub shard_persistent_committee_change_period: u64,
+    pub pow_receipt_root_voting_period: u64,
     pub genesis_time: u64,
     pub slot_duration_millis: u64,
     pub initial_validators: Vec<ValidatorRegistration>,
@@ -25,6 +27,8 @@ impl ChainConfig {
             shard_count: 1024,
             min_committee_size: 128,
             max_validator_churn_quotient: 32,
+            shard_persistent_committee_change_period: 1 << 17,
+            pow_receipt_root_voting_period: 1 << 10,
             genesis_time: TEST_GENESIS_TIME,
             slot_duration_millis: 16 * 1000,
             initial_validators: vec![],
@@ -40,6 +44,15 @@ impl ChainConfig {
             return false;
         }
 
+        // pow_receipt_root_voting_period must be a non-zero multiple of
+        // cycle_length otherwise voting periods will not end on a state
+        // recalculation.
+        if self.pow_receipt_root_voting_period == 0
+            || self.pow_receipt_root_voting_period % u64::from(self.cycle_length) != 0
+        {
+            return false;
+        }
+
         true
     }
 
@@ -51,6 +64,8 @@ impl ChainConfig {
             shard_count: 2,
             min_committee_size: 2,
             max_validator_churn_quotient: 32,
+            shard_persistent_committee_change_period: 2,
+            pow_receipt_root_voting_period: 2,
             genesis_time: TEST_GENESIS_TIME, // arbitrary
             slot_duration_millis: 16 * 1000,
             initial_validators: vec![],
@@ -1,3 +1,4 @@
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
 use super::Hash256;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -18,6 +19,28 @@ impl CrosslinkRecord {
     }
 }
 
+impl Encodable for CrosslinkRecord {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.recently_changed);
+        s.append(&self.slot);
+        s.append(&self.hash);
+    }
+}
+
+impl Decodable for CrosslinkRecord {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (recently_changed, i) = bool::ssz_decode(bytes, i)?;
+        let (slot, i) = u64::ssz_decode(bytes, i)?;
+        let (hash, i) = Hash256::ssz_decode(bytes, i)?;
+        let crosslink_record = Self {
+            recently_changed,
+            slot,
+            hash,
+        };
+        Ok((crosslink_record, i))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,4 +52,19 @@ mod tests {
         assert_eq!(c.slot, 0);
         assert!(c.hash.is_zero());
     }
+
+    #[test]
+    fn test_crosslink_record_ssz_encode_decode() {
+        let original = CrosslinkRecord {
+            recently_changed: true,
+            slot: 42,
+            hash: Hash256::from("crosslink".as_bytes()),
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = CrosslinkRecord::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
 }
@@ -0,0 +1,88 @@
+/// The fork version of the chain at genesis.
+pub const INITIAL_FORK_VERSION: u64 = 0;
+
+/// The type of a signed message, which forms part of the domain it is signed in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DomainType {
+    Attestation = 0,
+    ProofOfPossession = 2,
+    Logout = 3,
+}
+
+/// The fork versions of the chain, which determine the domain that messages at each slot are
+/// signed in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ForkData {
+    pub pre_fork_version: u64,
+    pub post_fork_version: u64,
+    pub fork_slot_number: u64,
+}
+
+impl ForkData {
+    /// Returns the fork data of the chain at genesis, where no fork has occurred.
+    pub fn genesis() -> Self {
+        Self {
+            pre_fork_version: INITIAL_FORK_VERSION,
+            post_fork_version: INITIAL_FORK_VERSION,
+            fork_slot_number: 0,
+        }
+    }
+
+    /// Returns the fork version in effect at the given `slot`.
+    pub fn version(&self, slot: u64) -> u64 {
+        if slot < self.fork_slot_number {
+            self.pre_fork_version
+        } else {
+            self.post_fork_version
+        }
+    }
+
+    /// Returns the domain in which a message of `domain_type` at the given `slot` is signed.
+    pub fn domain(&self, slot: u64, domain_type: DomainType) -> u64 {
+        signing_domain(self.version(slot), domain_type)
+    }
+}
+
+/// Returns the domain in which a message of `domain_type` is signed under `fork_version`.
+///
+/// Signatures are only valid within their domain, so they cannot be replayed across forks or
+/// message types.
+pub fn signing_domain(fork_version: u64, domain_type: DomainType) -> u64 {
+    (fork_version << 32) | domain_type as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fork_data_version() {
+        let fork_data = ForkData {
+            pre_fork_version: 1,
+            post_fork_version: 2,
+            fork_slot_number: 10,
+        };
+
+        assert_eq!(fork_data.version(0), 1);
+        assert_eq!(fork_data.version(9), 1);
+        assert_eq!(fork_data.version(10), 2);
+        assert_eq!(fork_data.version(11), 2);
+    }
+
+    #[test]
+    fn test_fork_data_domain() {
+        let fork_data = ForkData {
+            pre_fork_version: 1,
+            post_fork_version: 2,
+            fork_slot_number: 10,
+        };
+
+        assert_eq!(fork_data.domain(9, DomainType::Attestation), 1 << 32);
+        assert_eq!(fork_data.domain(9, DomainType::Logout), (1 << 32) + 3);
+        assert_eq!(fork_data.domain(10, DomainType::Attestation), 2 << 32);
+        assert_eq!(
+            ForkData::genesis().domain(42, DomainType::ProofOfPossession),
+            2
+        );
+    }
+}
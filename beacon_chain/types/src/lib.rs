@@ -1,15 +1,20 @@
 extern crate bls;
 extern crate boolean_bitfield;
 extern crate ethereum_types;
+extern crate hashing;
 extern crate ssz;
 
-pub mod active_state;
 pub mod attestation_record;
 pub mod beacon_block;
 pub mod candidate_pow_receipt_root_record;
+pub mod casper_slashing_special;
 pub mod chain_config;
 pub mod crosslink_record;
-pub mod crystallized_state;
+pub mod fork_data;
+pub mod logout_special;
+pub mod pending_attestation_record;
+pub mod pow_block;
+pub mod randao_change_special;
 pub mod shard_and_committee;
 pub mod shard_reassignment_record;
 pub mod special_record;
@@ -20,14 +25,21 @@ pub mod validator_registration;
 use self::ethereum_types::{H160, H256, U256};
 use std::collections::HashMap;
 
-pub use active_state::ActiveState;
 pub use attestation_record::AttestationRecord;
 pub use beacon_block::BeaconBlock;
+pub use candidate_pow_receipt_root_record::CandidatePoWReceiptRootRecord;
+pub use casper_slashing_special::{CasperSlashingSpecial, SlashableVote};
 pub use chain_config::ChainConfig;
 pub use crosslink_record::CrosslinkRecord;
-pub use crystallized_state::CrystallizedState;
+pub use fork_data::{signing_domain, DomainType, ForkData, INITIAL_FORK_VERSION};
+pub use logout_special::{LogoutSpecial, LOGOUT_MESSAGE};
+pub use pending_attestation_record::PendingAttestationRecord;
+pub use pow_block::PoWBlock;
+pub use randao_change_special::RandaoChangeSpecial;
 pub use shard_and_committee::ShardAndCommittee;
-pub use special_record::{SpecialRecord, SpecialRecordKind};
+pub use shard_reassignment_record::ShardReassignmentRecord;
+pub use special_record::{SpecialPayload, SpecialPayloadError, SpecialRecord, SpecialRecordKind};
+pub use state::BeaconState;
 pub use validator_record::{ValidatorRecord, ValidatorStatus};
 pub use validator_registration::ValidatorRegistration;
 
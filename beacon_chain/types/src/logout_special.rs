@@ -0,0 +1,58 @@
+use super::bls::Signature;
+use super::ssz::{decode_ssz_list, Decodable, DecodeError, Encodable, SszStream};
+
+/// The message which must be signed by a validator requesting to log out.
+pub const LOGOUT_MESSAGE: &[u8] = b"LOGOUT";
+
+/// The payload of a `SpecialRecord` with the `Logout` kind.
+///
+/// Requests that the validator at `validator_index` exits the validator set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogoutSpecial {
+    pub validator_index: usize,
+    /// The signature of `LOGOUT_MESSAGE` by the validator, in the logout domain of the slot of
+    /// the block which includes it.
+    pub signature: Signature,
+}
+
+impl Encodable for LogoutSpecial {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.validator_index);
+        s.append_vec(&self.signature.as_bytes());
+    }
+}
+
+impl Decodable for LogoutSpecial {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (validator_index, i) = usize::ssz_decode(bytes, i)?;
+        let (sig_bytes, i) = decode_ssz_list(bytes, i)?;
+        let signature = Signature::from_bytes(&sig_bytes).map_err(|_| DecodeError::TooShort)?;
+
+        let special = Self {
+            validator_index,
+            signature,
+        };
+        Ok((special, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bls::Keypair;
+    use super::*;
+
+    #[test]
+    fn test_logout_special_ssz_encode_decode() {
+        let keypair = Keypair::random();
+        let original = LogoutSpecial {
+            validator_index: 42,
+            signature: Signature::new(LOGOUT_MESSAGE, &keypair.sk),
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = LogoutSpecial::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
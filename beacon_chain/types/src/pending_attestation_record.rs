@@ -0,0 +1,64 @@
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
+use super::AttestationRecord;
+
+/// An attestation which has been included in a block but not yet processed during a cycle
+/// recalculation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingAttestationRecord {
+    pub attestation: AttestationRecord,
+    /// The slot of the block which included the attestation.
+    pub slot_included: u64,
+}
+
+impl PendingAttestationRecord {
+    pub fn zero() -> Self {
+        Self {
+            attestation: AttestationRecord::zero(),
+            slot_included: 0,
+        }
+    }
+}
+
+impl Encodable for PendingAttestationRecord {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.attestation);
+        s.append(&self.slot_included);
+    }
+}
+
+impl Decodable for PendingAttestationRecord {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (attestation, i) = AttestationRecord::ssz_decode(bytes, i)?;
+        let (slot_included, i) = u64::ssz_decode(bytes, i)?;
+
+        let record = Self {
+            attestation,
+            slot_included,
+        };
+        Ok((record, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bls::{Keypair, Signature};
+    use super::*;
+
+    #[test]
+    fn test_pending_attestation_record_ssz_encode_decode() {
+        let mut original = PendingAttestationRecord::zero();
+        original.attestation.slot = 7;
+        original.attestation.shard_id = 9;
+        original
+            .attestation
+            .aggregate_sig
+            .add(&Signature::new(&[42], &Keypair::random().sk));
+        original.slot_included = 11;
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = PendingAttestationRecord::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
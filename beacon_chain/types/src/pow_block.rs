@@ -0,0 +1,69 @@
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
+use super::Hash256;
+
+/// A block of the PoW chain, as recorded by a node following the deposit contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoWBlock {
+    pub hash: Hash256,
+    pub parent_hash: Hash256,
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+impl PoWBlock {
+    pub fn zero() -> Self {
+        Self {
+            hash: Hash256::zero(),
+            parent_hash: Hash256::zero(),
+            number: 0,
+            timestamp: 0,
+        }
+    }
+}
+
+impl Encodable for PoWBlock {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.hash);
+        s.append(&self.parent_hash);
+        s.append(&self.number);
+        s.append(&self.timestamp);
+    }
+}
+
+impl Decodable for PoWBlock {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (hash, i) = Hash256::ssz_decode(bytes, i)?;
+        let (parent_hash, i) = Hash256::ssz_decode(bytes, i)?;
+        let (number, i) = u64::ssz_decode(bytes, i)?;
+        let (timestamp, i) = u64::ssz_decode(bytes, i)?;
+
+        let block = Self {
+            hash,
+            parent_hash,
+            number,
+            timestamp,
+        };
+        Ok((block, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pow_block_ssz_encode_decode() {
+        let original = PoWBlock {
+            hash: Hash256::from("hash".as_bytes()),
+            parent_hash: Hash256::from("parent_hash".as_bytes()),
+            number: 42,
+            timestamp: 1_537_488_655,
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = PoWBlock::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
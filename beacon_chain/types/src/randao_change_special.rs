@@ -0,0 +1,58 @@
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
+use super::Hash256;
+
+/// The payload of a `SpecialRecord` with the `RandaoChange` kind.
+///
+/// Replaces the `randao_commitment` of a block proposer with the `randao_reveal` of their block.
+/// It is queued by the state transition when the block is applied, and is not valid within a
+/// block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandaoChangeSpecial {
+    pub proposer_index: usize,
+    pub new_randao_commitment: Hash256,
+    /// The slot of the block which revealed the `new_randao_commitment`.
+    pub slot: u64,
+}
+
+impl Encodable for RandaoChangeSpecial {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.proposer_index);
+        s.append(&self.new_randao_commitment);
+        s.append(&self.slot);
+    }
+}
+
+impl Decodable for RandaoChangeSpecial {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (proposer_index, i) = usize::ssz_decode(bytes, i)?;
+        let (new_randao_commitment, i) = Hash256::ssz_decode(bytes, i)?;
+        let (slot, i) = u64::ssz_decode(bytes, i)?;
+
+        let special = Self {
+            proposer_index,
+            new_randao_commitment,
+            slot,
+        };
+        Ok((special, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_randao_change_special_ssz_encode_decode() {
+        let original = RandaoChangeSpecial {
+            proposer_index: 7,
+            new_randao_commitment: Hash256::from("commitment".as_bytes()),
+            slot: 12,
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = RandaoChangeSpecial::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
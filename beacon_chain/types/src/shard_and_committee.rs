@@ -1,3 +1,5 @@
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ShardAndCommittee {
     pub shard: u16,
@@ -15,6 +17,21 @@ impl ShardAndCommittee {
     }
 }
 
+impl Encodable for ShardAndCommittee {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.shard);
+        s.append_vec(&self.committee);
+    }
+}
+
+impl Decodable for ShardAndCommittee {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (shard, i) = u16::ssz_decode(bytes, i)?;
+        let (committee, i) = Decodable::ssz_decode(bytes, i)?;
+        Ok((Self { shard, committee }, i))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,4 +42,18 @@ mod tests {
         assert_eq!(s.shard, 0);
         assert_eq!(s.committee.len(), 0);
     }
+
+    #[test]
+    fn test_shard_and_committee_ssz_encode_decode() {
+        let original = ShardAndCommittee {
+            shard: 7,
+            committee: vec![3, 1, 4, 1, 5],
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = ShardAndCommittee::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
 }
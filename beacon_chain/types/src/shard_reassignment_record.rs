@@ -1,6 +1,53 @@
-#[derive(Debug, PartialEq)]
+use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
+
+/// Records that the validator at `validator_index` is to move to the persistent committee of
+/// `shard` at `slot`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShardReassignmentRecord {
     pub validator_index: u64,
     pub shard: u64,
     pub slot: u64,
 }
+
+impl Encodable for ShardReassignmentRecord {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.validator_index);
+        s.append(&self.shard);
+        s.append(&self.slot);
+    }
+}
+
+impl Decodable for ShardReassignmentRecord {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (validator_index, i) = u64::ssz_decode(bytes, i)?;
+        let (shard, i) = u64::ssz_decode(bytes, i)?;
+        let (slot, i) = u64::ssz_decode(bytes, i)?;
+
+        let record = Self {
+            validator_index,
+            shard,
+            slot,
+        };
+        Ok((record, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shard_reassignment_record_ssz_encode_decode() {
+        let original = ShardReassignmentRecord {
+            validator_index: 7,
+            shard: 3,
+            slot: 42,
+        };
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = ShardReassignmentRecord::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
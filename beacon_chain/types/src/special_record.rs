@@ -1,4 +1,5 @@
 use super::ssz::{Decodable, DecodeError, Encodable, SszStream};
+use super::{CasperSlashingSpecial, LogoutSpecial, RandaoChangeSpecial};
 
 /// The value of the "type" field of SpecialRecord.
 ///
@@ -10,6 +11,26 @@ pub enum SpecialRecordKind {
     RandaoChange = 2,
 }
 
+/// The typed contents of the `data` field of a `SpecialRecord`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SpecialPayload {
+    Logout(LogoutSpecial),
+    CasperSlashing(CasperSlashingSpecial),
+    RandaoChange(RandaoChangeSpecial),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SpecialPayloadError {
+    UnknownKind,
+    DecodeError(DecodeError),
+}
+
+impl From<DecodeError> for SpecialPayloadError {
+    fn from(e: DecodeError) -> Self {
+        SpecialPayloadError::DecodeError(e)
+    }
+}
+
 /// The structure used in the `BeaconBlock.specials` field.
 #[derive(Debug, PartialEq, Clone)]
 pub struct SpecialRecord {
@@ -54,6 +75,36 @@ impl SpecialRecord {
             _ => None,
         }
     }
+
+    /// Decode `self.data` into the payload indicated by `self.kind`.
+    ///
+    /// Returns an error if `self.kind` is an unknown value or if `self.data` is not exactly the
+    /// SSZ encoding of a payload.
+    pub fn payload(&self) -> Result<SpecialPayload, SpecialPayloadError> {
+        let kind = self
+            .resolve_kind()
+            .ok_or(SpecialPayloadError::UnknownKind)?;
+        let payload = match kind {
+            SpecialRecordKind::Logout => SpecialPayload::Logout(decode_payload(&self.data)?),
+            SpecialRecordKind::CasperSlashing => {
+                SpecialPayload::CasperSlashing(decode_payload(&self.data)?)
+            }
+            SpecialRecordKind::RandaoChange => {
+                SpecialPayload::RandaoChange(decode_payload(&self.data)?)
+            }
+        };
+        Ok(payload)
+    }
+}
+
+/// Decode some `T` from the entirety of `data`.
+fn decode_payload<T: Decodable>(data: &[u8]) -> Result<T, DecodeError> {
+    let (payload, i) = T::ssz_decode(data, 0)?;
+    if i < data.len() {
+        Err(DecodeError::TooLong)
+    } else {
+        Ok(payload)
+    }
 }
 
 impl Encodable for SpecialRecord {
@@ -73,6 +124,8 @@ impl Decodable for SpecialRecord {
 
 #[cfg(test)]
 mod tests {
+    use super::super::ssz::ssz_encode;
+    use super::super::Hash256;
     use super::*;
 
     #[test]
@@ -112,6 +165,41 @@ mod tests {
         assert_eq!(s, s_decoded);
     }
 
+    #[test]
+    pub fn test_special_record_payload() {
+        let randao_change = RandaoChangeSpecial {
+            proposer_index: 3,
+            new_randao_commitment: Hash256::from("commitment".as_bytes()),
+            slot: 12,
+        };
+        let mut data = ssz_encode(&randao_change);
+
+        let s = SpecialRecord::randao_change(&data);
+        assert_eq!(s.payload(), Ok(SpecialPayload::RandaoChange(randao_change)));
+
+        /*
+         * The payload must match the kind of the record.
+         */
+        let s = SpecialRecord::logout(&data);
+        assert!(s.payload().is_err());
+
+        /*
+         * The payload may not have trailing bytes.
+         */
+        data.push(42);
+        let s = SpecialRecord::randao_change(&data);
+        assert_eq!(
+            s.payload(),
+            Err(SpecialPayloadError::DecodeError(DecodeError::TooLong))
+        );
+
+        let s = SpecialRecord {
+            kind: 88,
+            data: vec![],
+        };
+        assert_eq!(s.payload(), Err(SpecialPayloadError::UnknownKind));
+    }
+
     #[test]
     pub fn test_special_record_resolve_kind() {
         let s = SpecialRecord::logout(&vec![]);
@@ -1,54 +1,305 @@
-use super::attestation_record::AttestationRecord;
 use super::candidate_pow_receipt_root_record::CandidatePoWReceiptRootRecord;
 use super::crosslink_record::CrosslinkRecord;
+use super::fork_data::ForkData;
+use super::hashing::canonical_hash;
+use super::pending_attestation_record::PendingAttestationRecord;
 use super::shard_and_committee::ShardAndCommittee;
 use super::shard_reassignment_record::ShardReassignmentRecord;
+use super::special_record::SpecialRecord;
+use super::ssz::{ssz_encode, Decodable, DecodeError, Encodable, SszStream};
 use super::validator_record::ValidatorRecord;
 use super::Hash256;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct BeaconState {
     // Slot of last validator set change
-    validator_set_change_slot: u64,
+    pub validator_set_change_slot: u64,
     // List of validators
-    validators: Vec<ValidatorRecord>,
+    pub validators: Vec<ValidatorRecord>,
     // Most recent crosslink for each shard
-    crosslinks: Vec<CrosslinkRecord>,
+    pub crosslinks: Vec<CrosslinkRecord>,
     // Last cycle-boundary state recalculation
-    last_state_recalculation_slot: u64,
+    pub last_state_recalculation_slot: u64,
     // Last finalized slot
-    last_finalized_slot: u64,
+    pub last_finalized_slot: u64,
     // Last justified slot
-    last_justified_slot: u64,
+    pub last_justified_slot: u64,
     // Number of consecutive justified slots
-    justified_streak: u64,
+    pub justified_streak: u64,
     // Committee members and their assigned shard, per slot
-    shard_and_committee_for_slots: Vec<Vec<ShardAndCommittee>>,
+    pub shard_and_committee_for_slots: Vec<Vec<ShardAndCommittee>>,
     // Persistent shard committees
-    persistent_committees: Vec<Vec<u32>>,
-    persistent_committee_reassignments: Vec<ShardReassignmentRecord>,
+    pub persistent_committees: Vec<Vec<u32>>,
+    pub persistent_committee_reassignments: Vec<ShardReassignmentRecord>,
     // Randao seed used for next shuffling
-    next_shuffling_seed: Hash256,
+    pub next_shuffling_seed: Hash256,
     // Total deposits penalized in the given withdrawal period
-    deposits_penalized_in_period: Vec<u64>,
+    pub deposits_penalized_in_period: Vec<u64>,
     // Hash chain of validator set changes (for light clients to easily track deltas)
-    validator_set_delta_hash_chain: Hash256,
+    pub validator_set_delta_hash_chain: Hash256,
     // Current sequence number for withdrawals
-    current_exit_seq: u64,
+    pub current_exit_seq: u64,
     // Genesis time
-    genesis_time: u64,
+    pub genesis_time: u64,
     // PoW receipt root
-    processed_pow_receipt_root: Hash256,
-    candidate_pow_receipt_roots: Vec<CandidatePoWReceiptRootRecord>,
+    pub processed_pow_receipt_root: Hash256,
+    pub candidate_pow_receipt_roots: Vec<CandidatePoWReceiptRootRecord>,
     // Parameters relevant to hard forks / versioning.
     // Should be updated only by hard forks.
-    pre_fork_version: u64,
-    post_fork_version: u64,
-    fork_slot_number: u64,
+    pub pre_fork_version: u64,
+    pub post_fork_version: u64,
+    pub fork_slot_number: u64,
     // Attestations not yet processed
-    pending_attestations: Vec<AttestationRecord>,
+    pub pending_attestations: Vec<PendingAttestationRecord>,
+    // Specials not yet processed
+    pub pending_specials: Vec<SpecialRecord>,
     // recent beacon block hashes needed to process attestations, older to newer
-    recent_block_hashes: Vec<Hash256>,
+    pub recent_block_hashes: Vec<Hash256>,
     // RANDAO state
-    randao_mix: Hash256,
+    pub randao_mix: Hash256,
+}
+
+impl BeaconState {
+    /// Returns a new instance where all fields are empty or zero.
+    pub fn zero() -> Self {
+        Self {
+            validator_set_change_slot: 0,
+            validators: vec![],
+            crosslinks: vec![],
+            last_state_recalculation_slot: 0,
+            last_finalized_slot: 0,
+            last_justified_slot: 0,
+            justified_streak: 0,
+            shard_and_committee_for_slots: vec![],
+            persistent_committees: vec![],
+            persistent_committee_reassignments: vec![],
+            next_shuffling_seed: Hash256::zero(),
+            deposits_penalized_in_period: vec![],
+            validator_set_delta_hash_chain: Hash256::zero(),
+            current_exit_seq: 0,
+            genesis_time: 0,
+            processed_pow_receipt_root: Hash256::zero(),
+            candidate_pow_receipt_roots: vec![],
+            pre_fork_version: 0,
+            post_fork_version: 0,
+            fork_slot_number: 0,
+            pending_attestations: vec![],
+            pending_specials: vec![],
+            recent_block_hashes: vec![],
+            randao_mix: Hash256::zero(),
+        }
+    }
+
+    /// Returns the canonical hash of the SSZ encoding of this state.
+    pub fn canonical_root(&self) -> Hash256 {
+        Hash256::from(&canonical_hash(&ssz_encode(self))[..])
+    }
+
+    /// Returns the shard of the persistent committee which includes the validator at
+    /// `validator_index`, if any.
+    pub fn persistent_committee_shard(&self, validator_index: usize) -> Option<u16> {
+        self.persistent_committees
+            .iter()
+            .position(|committee| committee.iter().any(|i| *i as usize == validator_index))
+            .map(|shard| shard as u16)
+    }
+
+    /// Returns the fork versions of the state, which determine the domains that messages are
+    /// signed in.
+    pub fn fork_data(&self) -> ForkData {
+        ForkData {
+            pre_fork_version: self.pre_fork_version,
+            post_fork_version: self.post_fork_version,
+            fork_slot_number: self.fork_slot_number,
+        }
+    }
+}
+
+impl Encodable for BeaconState {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&self.validator_set_change_slot);
+        s.append_vec(&self.validators);
+        s.append_vec(&self.crosslinks);
+        s.append(&self.last_state_recalculation_slot);
+        s.append(&self.last_finalized_slot);
+        s.append(&self.last_justified_slot);
+        s.append(&self.justified_streak);
+        s.append_vec(&self.shard_and_committee_for_slots);
+        s.append_vec(&self.persistent_committees);
+        s.append_vec(&self.persistent_committee_reassignments);
+        s.append(&self.next_shuffling_seed);
+        s.append_vec(&self.deposits_penalized_in_period);
+        s.append(&self.validator_set_delta_hash_chain);
+        s.append(&self.current_exit_seq);
+        s.append(&self.genesis_time);
+        s.append(&self.processed_pow_receipt_root);
+        s.append_vec(&self.candidate_pow_receipt_roots);
+        s.append(&self.pre_fork_version);
+        s.append(&self.post_fork_version);
+        s.append(&self.fork_slot_number);
+        s.append_vec(&self.pending_attestations);
+        s.append_vec(&self.pending_specials);
+        s.append_vec(&self.recent_block_hashes);
+        s.append(&self.randao_mix);
+    }
+}
+
+impl Decodable for BeaconState {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (validator_set_change_slot, i) = u64::ssz_decode(bytes, i)?;
+        let (validators, i) = Decodable::ssz_decode(bytes, i)?;
+        let (crosslinks, i) = Decodable::ssz_decode(bytes, i)?;
+        let (last_state_recalculation_slot, i) = u64::ssz_decode(bytes, i)?;
+        let (last_finalized_slot, i) = u64::ssz_decode(bytes, i)?;
+        let (last_justified_slot, i) = u64::ssz_decode(bytes, i)?;
+        let (justified_streak, i) = u64::ssz_decode(bytes, i)?;
+        let (shard_and_committee_for_slots, i) = Decodable::ssz_decode(bytes, i)?;
+        let (persistent_committees, i) = Decodable::ssz_decode(bytes, i)?;
+        let (persistent_committee_reassignments, i) = Decodable::ssz_decode(bytes, i)?;
+        let (next_shuffling_seed, i) = Hash256::ssz_decode(bytes, i)?;
+        let (deposits_penalized_in_period, i) = Decodable::ssz_decode(bytes, i)?;
+        let (validator_set_delta_hash_chain, i) = Hash256::ssz_decode(bytes, i)?;
+        let (current_exit_seq, i) = u64::ssz_decode(bytes, i)?;
+        let (genesis_time, i) = u64::ssz_decode(bytes, i)?;
+        let (processed_pow_receipt_root, i) = Hash256::ssz_decode(bytes, i)?;
+        let (candidate_pow_receipt_roots, i) = Decodable::ssz_decode(bytes, i)?;
+        let (pre_fork_version, i) = u64::ssz_decode(bytes, i)?;
+        let (post_fork_version, i) = u64::ssz_decode(bytes, i)?;
+        let (fork_slot_number, i) = u64::ssz_decode(bytes, i)?;
+        let (pending_attestations, i) = Decodable::ssz_decode(bytes, i)?;
+        let (pending_specials, i) = Decodable::ssz_decode(bytes, i)?;
+        let (recent_block_hashes, i) = Decodable::ssz_decode(bytes, i)?;
+        let (randao_mix, i) = Hash256::ssz_decode(bytes, i)?;
+
+        let state = Self {
+            validator_set_change_slot,
+            validators,
+            crosslinks,
+            last_state_recalculation_slot,
+            last_finalized_slot,
+            last_justified_slot,
+            justified_streak,
+            shard_and_committee_for_slots,
+            persistent_committees,
+            persistent_committee_reassignments,
+            next_shuffling_seed,
+            deposits_penalized_in_period,
+            validator_set_delta_hash_chain,
+            current_exit_seq,
+            genesis_time,
+            processed_pow_receipt_root,
+            candidate_pow_receipt_roots,
+            pre_fork_version,
+            post_fork_version,
+            fork_slot_number,
+            pending_attestations,
+            pending_specials,
+            recent_block_hashes,
+            randao_mix,
+        };
+        Ok((state, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bls::{Keypair, Signature};
+    use super::super::AttestationRecord;
+    use super::*;
+
+    fn test_beacon_state() -> BeaconState {
+        let (validator, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+        let keypair = Keypair::random();
+        let mut attestation = AttestationRecord::zero();
+        attestation
+            .aggregate_sig
+            .add(&Signature::new(&[42], &keypair.sk));
+
+        BeaconState {
+            validator_set_change_slot: 1,
+            validators: vec![validator],
+            crosslinks: vec![CrosslinkRecord::zero(), CrosslinkRecord::zero()],
+            last_state_recalculation_slot: 2,
+            last_finalized_slot: 3,
+            last_justified_slot: 4,
+            justified_streak: 5,
+            shard_and_committee_for_slots: vec![
+                vec![ShardAndCommittee {
+                    shard: 0,
+                    committee: vec![0],
+                }],
+                vec![],
+            ],
+            persistent_committees: vec![vec![0, 2], vec![], vec![1]],
+            persistent_committee_reassignments: vec![ShardReassignmentRecord {
+                validator_index: 2,
+                shard: 1,
+                slot: 11,
+            }],
+            next_shuffling_seed: Hash256::from("seed".as_bytes()),
+            deposits_penalized_in_period: vec![6, 7],
+            validator_set_delta_hash_chain: Hash256::from("delta".as_bytes()),
+            current_exit_seq: 12,
+            genesis_time: 13,
+            processed_pow_receipt_root: Hash256::from("processed".as_bytes()),
+            candidate_pow_receipt_roots: vec![CandidatePoWReceiptRootRecord {
+                candidate_pow_receipt_root: Hash256::from("candidate".as_bytes()),
+                votes: 14,
+            }],
+            pre_fork_version: 8,
+            post_fork_version: 9,
+            fork_slot_number: 10,
+            pending_attestations: vec![PendingAttestationRecord {
+                attestation,
+                slot_included: 1,
+            }],
+            pending_specials: vec![SpecialRecord::logout(&[42, 43])],
+            recent_block_hashes: vec![
+                Hash256::from("one".as_bytes()),
+                Hash256::from("two".as_bytes()),
+            ],
+            randao_mix: Hash256::from("randao_mix".as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_beacon_state_ssz_encode_decode() {
+        let original = test_beacon_state();
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = BeaconState::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+
+        let zero = BeaconState::zero();
+        let (decoded, _) = BeaconState::ssz_decode(&ssz_encode(&zero), 0).unwrap();
+        assert_eq!(zero, decoded);
+    }
+
+    #[test]
+    fn test_beacon_state_canonical_root() {
+        let a = test_beacon_state();
+        let mut b = a.clone();
+
+        assert!(!a.canonical_root().is_zero());
+        assert_eq!(a.canonical_root(), b.canonical_root());
+
+        b.last_justified_slot += 1;
+        assert_ne!(a.canonical_root(), b.canonical_root());
+
+        let mut c = a.clone();
+        c.randao_mix = Hash256::from("other_randao_mix".as_bytes());
+        assert_ne!(a.canonical_root(), c.canonical_root());
+    }
+
+    #[test]
+    fn test_beacon_state_persistent_committee_shard() {
+        let a = test_beacon_state();
+
+        assert_eq!(a.persistent_committee_shard(0), Some(0));
+        assert_eq!(a.persistent_committee_shard(1), Some(2));
+        assert_eq!(a.persistent_committee_shard(2), Some(0));
+        assert_eq!(a.persistent_committee_shard(3), None);
+    }
 }
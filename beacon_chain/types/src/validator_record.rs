@@ -1,4 +1,5 @@
 use super::bls::{Keypair, PublicKey};
+use super::ssz::{decode_ssz_list, Decodable, DecodeError, Encodable, SszStream};
 use super::{Address, Hash256};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -44,6 +45,45 @@ impl ValidatorRecord {
     }
 }
 
+impl Encodable for ValidatorRecord {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append_vec(&self.pubkey.as_bytes());
+        s.append(&self.withdrawal_shard);
+        s.append(&self.withdrawal_address);
+        s.append(&self.randao_commitment);
+        s.append(&self.randao_last_change);
+        s.append(&self.balance);
+        s.append(&self.status);
+        s.append(&self.exit_slot);
+    }
+}
+
+impl Decodable for ValidatorRecord {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (pubkey_bytes, i) = decode_ssz_list(bytes, i)?;
+        let pubkey = PublicKey::from_bytes(&pubkey_bytes).map_err(|_| DecodeError::TooShort)?;
+        let (withdrawal_shard, i) = u16::ssz_decode(bytes, i)?;
+        let (withdrawal_address, i) = Address::ssz_decode(bytes, i)?;
+        let (randao_commitment, i) = Hash256::ssz_decode(bytes, i)?;
+        let (randao_last_change, i) = u64::ssz_decode(bytes, i)?;
+        let (balance, i) = u64::ssz_decode(bytes, i)?;
+        let (status, i) = u8::ssz_decode(bytes, i)?;
+        let (exit_slot, i) = u64::ssz_decode(bytes, i)?;
+
+        let validator_record = Self {
+            pubkey,
+            withdrawal_shard,
+            withdrawal_address,
+            randao_commitment,
+            randao_last_change,
+            balance,
+            status,
+            exit_slot,
+        };
+        Ok((validator_record, i))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,4 +99,22 @@ mod tests {
         assert_eq!(v.status, 0);
         assert_eq!(v.exit_slot, 0);
     }
+
+    #[test]
+    fn test_validator_record_ssz_encode_decode() {
+        let (mut original, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+        original.withdrawal_shard = 3;
+        original.withdrawal_address = Address::from("withdrawal_address".as_bytes());
+        original.randao_commitment = Hash256::from("randao_commitment".as_bytes());
+        original.randao_last_change = 7;
+        original.balance = 32;
+        original.status = ValidatorStatus::Active as u8;
+        original.exit_slot = 11;
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = ValidatorRecord::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
 }
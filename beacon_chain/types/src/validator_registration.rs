@@ -1,4 +1,5 @@
-use super::{Address, Hash256};
+use super::ssz::{decode_ssz_list, Decodable, DecodeError, Encodable, SszStream};
+use super::{Address, DomainType, ForkData, Hash256};
 use bls::{create_proof_of_possession, Keypair, PublicKey, Signature};
 
 /// The information gathered from the PoW chain validator registration function.
@@ -12,15 +13,66 @@ pub struct ValidatorRegistration {
 }
 
 impl ValidatorRegistration {
+    /// Returns a registration for a random keypair, with a proof-of-possession in the genesis
+    /// signing domain.
     pub fn random() -> Self {
         let keypair = Keypair::random();
+        let domain = ForkData::genesis().domain(0, DomainType::ProofOfPossession);
 
         Self {
             pubkey: keypair.pk.clone(),
             withdrawal_shard: 0,
             withdrawal_address: Address::random(),
             randao_commitment: Hash256::random(),
-            proof_of_possession: create_proof_of_possession(&keypair),
+            proof_of_possession: create_proof_of_possession(&keypair, domain),
         }
     }
 }
+
+impl Encodable for ValidatorRegistration {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append_vec(&self.pubkey.as_bytes());
+        s.append(&self.withdrawal_shard);
+        s.append(&self.withdrawal_address);
+        s.append(&self.randao_commitment);
+        s.append_vec(&self.proof_of_possession.as_bytes());
+    }
+}
+
+impl Decodable for ValidatorRegistration {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (pubkey_bytes, i) = decode_ssz_list(bytes, i)?;
+        let pubkey = PublicKey::from_bytes(&pubkey_bytes).map_err(|_| DecodeError::TooShort)?;
+        let (withdrawal_shard, i) = u16::ssz_decode(bytes, i)?;
+        let (withdrawal_address, i) = Address::ssz_decode(bytes, i)?;
+        let (randao_commitment, i) = Hash256::ssz_decode(bytes, i)?;
+        let (sig_bytes, i) = decode_ssz_list(bytes, i)?;
+        let proof_of_possession =
+            Signature::from_bytes(&sig_bytes).map_err(|_| DecodeError::TooShort)?;
+
+        let registration = Self {
+            pubkey,
+            withdrawal_shard,
+            withdrawal_address,
+            randao_commitment,
+            proof_of_possession,
+        };
+        Ok((registration, i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validator_registration_ssz_encode_decode() {
+        let original = ValidatorRegistration::random();
+
+        let mut ssz_stream = SszStream::new();
+        ssz_stream.append(&original);
+
+        let (decoded, _) = ValidatorRegistration::ssz_decode(&ssz_stream.drain(), 0).unwrap();
+        assert_eq!(original, decoded);
+    }
+}
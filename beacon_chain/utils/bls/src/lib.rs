@@ -12,14 +12,23 @@ pub const BLS_AGG_SIG_BYTE_SIZE: usize = 97;
 
 use hashing::proof_of_possession_hash;
 
+/// Returns the `message` prefixed with the big-endian bytes of the signing `domain`.
+///
+/// A signature across the returned message is only valid within the given domain.
+pub fn message_with_domain(message: &[u8], domain: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = (0..8).rev().map(|i| (domain >> (i * 8)) as u8).collect();
+    bytes.extend_from_slice(message);
+    bytes
+}
+
 /// For some signature and public key, ensure that the signature message was the public key and it
-/// was signed by the secret key that corresponds to that public key.
-pub fn verify_proof_of_possession(sig: &Signature, pubkey: &PublicKey) -> bool {
-    let hash = proof_of_possession_hash(&pubkey.as_bytes());
+/// was signed (in the given `domain`) by the secret key that corresponds to that public key.
+pub fn verify_proof_of_possession(sig: &Signature, pubkey: &PublicKey, domain: u64) -> bool {
+    let hash = proof_of_possession_hash(&message_with_domain(&pubkey.as_bytes(), domain));
     sig.verify_hashed(&hash, &pubkey)
 }
 
-pub fn create_proof_of_possession(keypair: &Keypair) -> Signature {
-    let hash = proof_of_possession_hash(&keypair.pk.as_bytes());
+pub fn create_proof_of_possession(keypair: &Keypair, domain: u64) -> Signature {
+    let hash = proof_of_possession_hash(&message_with_domain(&keypair.pk.as_bytes(), domain));
     Signature::new_hashed(&hash, &keypair.sk)
 }
@@ -1,7 +1,7 @@
 extern crate bytes;
 
 use self::bytes::{BufMut, BytesMut};
-use super::ethereum_types::H256;
+use super::ethereum_types::{H160, H256};
 use super::{Encodable, SszStream};
 
 /*
@@ -46,12 +46,33 @@ impl_encodable_for_uint!(u32, 32);
 impl_encodable_for_uint!(u64, 64);
 impl_encodable_for_uint!(usize, 64);
 
+impl Encodable for bool {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&(*self as u8));
+    }
+}
+
 impl Encodable for H256 {
     fn ssz_append(&self, s: &mut SszStream) {
         s.append_encoded_raw(&self.to_vec());
     }
 }
 
+impl Encodable for H160 {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append_encoded_raw(&self.to_vec());
+    }
+}
+
+impl<T> Encodable for Vec<T>
+where
+    T: Encodable,
+{
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append_vec(self);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,6 +85,33 @@ mod tests {
         assert_eq!(ssz.drain(), vec![0; 32]);
     }
 
+    #[test]
+    fn test_ssz_encode_h160() {
+        let h = H160::zero();
+        let mut ssz = SszStream::new();
+        ssz.append(&h);
+        assert_eq!(ssz.drain(), vec![0; 20]);
+    }
+
+    #[test]
+    fn test_ssz_encode_bool() {
+        let mut ssz = SszStream::new();
+        ssz.append(&true);
+        ssz.append(&false);
+        assert_eq!(ssz.drain(), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_ssz_encode_nested_vec() {
+        let v: Vec<Vec<u16>> = vec![vec![1], vec![2, 3]];
+        let mut ssz = SszStream::new();
+        ssz.append(&v);
+        assert_eq!(
+            ssz.drain(),
+            vec![0, 0, 0, 14, 0, 0, 0, 2, 0, 1, 0, 0, 0, 4, 0, 2, 0, 3]
+        );
+    }
+
     #[test]
     fn test_ssz_encode_u8() {
         let x: u8 = 0;
@@ -16,8 +16,7 @@ const SLOT_BYTES: usize = 8;
 const HASH_SIZE: usize = 32;
 const RANDAO_REVEAL_BYTES: usize = HASH_SIZE;
 const POW_CHAIN_REF_BYTES: usize = HASH_SIZE;
-const ACTIVE_STATE_BYTES: usize = HASH_SIZE;
-const CRYSTALLIZED_STATE_BYTES: usize = HASH_SIZE;
+const STATE_ROOT_BYTES: usize = HASH_SIZE;
 
 /// Allows for reading of block values directly from serialized ssz bytes.
 ///
@@ -83,8 +82,7 @@ impl<'a> SszBeaconBlock<'a> {
          * Determine how many bytes are used to store attestation records.
          */
         let attestations_position = ancestors_position + LENGTH_PREFIX_BYTES + ancestors_len +     // end of ancestor bytes
-            ACTIVE_STATE_BYTES +
-            CRYSTALLIZED_STATE_BYTES;
+            STATE_ROOT_BYTES;
         let attestations_len =
             decode_length(untrimmed_ssz, attestations_position, LENGTH_PREFIX_BYTES)
                 .map_err(|_| SszBeaconBlockError::TooShort)?;
@@ -180,17 +178,10 @@ impl<'a> SszBeaconBlock<'a> {
         &self.ssz[start..(start + self.ancestors_len + LENGTH_PREFIX_BYTES)]
     }
 
-    /// Return the `active_state_root` field.
-    pub fn act_state_root(&self) -> &[u8] {
+    /// Return the `state_root` field.
+    pub fn state_root(&self) -> &[u8] {
         let start = self.ancestors_position + LENGTH_PREFIX_BYTES + self.ancestors_len;
-        &self.ssz[start..(start + 32)]
-    }
-
-    /// Return the `active_state_root` field.
-    pub fn cry_state_root(&self) -> &[u8] {
-        let start =
-            self.ancestors_position + LENGTH_PREFIX_BYTES + self.ancestors_len + ACTIVE_STATE_BYTES;
-        &self.ssz[start..(start + 32)]
+        &self.ssz[start..(start + STATE_ROOT_BYTES)]
     }
 
     /// Return the serialized `attestations` bytes, including length prefix.
@@ -288,15 +279,23 @@ mod tests {
         let serialized = get_block_ssz(&block);
         let ssz_block = SszBeaconBlock::from_slice(&serialized).unwrap();
         let hash = ssz_block.block_hash();
-        // Note: this hash was not generated by some external program,
-        // it was simply printed then copied into the code. This test
-        // will tell us if the hash changes, not that it matches some
-        // canonical reference.
-        let expected_hash = [
-            11, 181, 149, 114, 248, 15, 46, 0, 106, 135, 158, 31, 15, 194, 149, 176, 43, 110, 154,
-            26, 253, 67, 18, 139, 250, 84, 144, 219, 3, 208, 50, 145,
-        ];
+        /*
+         * The canonical hash of a block is the hash of its entire ssz encoding, so the expected
+         * hash is computed from the serialized block rather than being hard-coded (which would
+         * go stale whenever the block encoding changes).
+         */
+        let expected_hash = canonical_hash(&serialized);
         assert_eq!(hash, expected_hash);
+        assert_eq!(hash.len(), HASH_SIZE);
+
+        /*
+         * Test that the hash covers the contents of the block.
+         */
+        let mut other_block = block.clone();
+        other_block.slot = 1;
+        let other_serialized = get_block_ssz(&other_block);
+        let other_ssz_block = SszBeaconBlock::from_slice(&other_serialized).unwrap();
+        assert_ne!(other_ssz_block.block_hash(), expected_hash);
 
         /*
          * Test if you give the SszBeaconBlock too many ssz bytes
@@ -453,28 +452,15 @@ mod tests {
     }
 
     #[test]
-    fn test_ssz_block_act_state_root() {
-        let mut block = BeaconBlock::zero();
-        block.attestations.push(AttestationRecord::zero());
-        let reference_hash = Hash256::from([42_u8; 32]);
-        block.active_state_root = reference_hash.clone();
-
-        let serialized = get_block_ssz(&block);
-        let ssz_block = SszBeaconBlock::from_slice(&serialized).unwrap();
-
-        assert_eq!(ssz_block.act_state_root(), &reference_hash.to_vec()[..]);
-    }
-
-    #[test]
-    fn test_ssz_block_cry_state_root() {
+    fn test_ssz_block_state_root() {
         let mut block = BeaconBlock::zero();
         block.attestations.push(AttestationRecord::zero());
         let reference_hash = Hash256::from([42_u8; 32]);
-        block.crystallized_state_root = reference_hash.clone();
+        block.state_root = reference_hash.clone();
 
         let serialized = get_block_ssz(&block);
         let ssz_block = SszBeaconBlock::from_slice(&serialized).unwrap();
 
-        assert_eq!(ssz_block.cry_state_root(), &reference_hash.to_vec()[..]);
+        assert_eq!(ssz_block.state_root(), &reference_hash.to_vec()[..]);
     }
 }
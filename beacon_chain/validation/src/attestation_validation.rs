@@ -6,7 +6,7 @@ use super::signature_verification::{
     verify_aggregate_signature_for_indices, SignatureVerificationError,
 };
 use super::types::Hash256;
-use super::types::{AttestationRecord, AttesterMap};
+use super::types::{AttestationRecord, AttesterMap, ForkData};
 use std::collections::HashSet;
 use std::sync::Arc;
 
@@ -56,6 +56,8 @@ where
     pub validator_store: Arc<ValidatorStore<T>>,
     /// A map of (slot, shard_id) to the attestation set of validation indices.
     pub attester_map: Arc<AttesterMap>,
+    /// The fork versions which determine the domain the attestation was signed in.
+    pub fork_data: ForkData,
 }
 
 impl<T> AttestationValidationContext<T>
@@ -178,6 +180,7 @@ where
                 a.shard_id,
                 &a.shard_block_hash,
                 a.justified_slot,
+                &self.fork_data,
             )
         };
 
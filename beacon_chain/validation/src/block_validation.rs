@@ -5,26 +5,35 @@ use self::rayon::prelude::*;
 use super::attestation_validation::{AttestationValidationContext, AttestationValidationError};
 use super::db::stores::{BeaconBlockStore, PoWChainStore, ValidatorStore};
 use super::db::{ClientDB, DBError};
+use super::randao_verification::verify_randao_reveal;
+use super::special_validation::{SpecialValidationContext, SpecialValidationError};
 use super::ssz::{Decodable, DecodeError};
 use super::ssz_helpers::attestation_ssz_splitter::{
     split_all_attestations, split_one_attestation, AttestationSplitError,
 };
 use super::ssz_helpers::ssz_beacon_block::{SszBeaconBlock, SszBeaconBlockError};
+use super::types::beacon_block::child_ancestor_hashes;
 use super::types::Hash256;
-use super::types::{AttestationRecord, AttesterMap, BeaconBlock, ProposerMap};
+use super::types::{
+    AttestationRecord, AttesterMap, BeaconBlock, BeaconState, ProposerMap, SpecialRecord,
+};
 use std::sync::{Arc, RwLock};
 
 #[derive(Debug, PartialEq)]
 pub enum SszBeaconBlockValidationError {
     FutureSlot,
     SlotAlreadyFinalized,
+    NotDescendantOfFinalized,
     UnknownPoWChainRef,
     UnknownParentHash,
     BadAttestationSsz,
     BadAncestorHashesSsz,
+    InvalidAncestorHashes,
     BadSpecialsSsz,
     ParentSlotHigherThanBlockSlot,
+    InvalidRandaoReveal,
     AttestationValidationError(AttestationValidationError),
+    SpecialValidationError(SpecialValidationError),
     AttestationSignatureFailed,
     ProposerAttestationHasObliqueHashes,
     NoProposerSignature,
@@ -42,16 +51,25 @@ where
     pub present_slot: u64,
     /// The cycle_length as determined by the chain configuration.
     pub cycle_length: u8,
-    /// The last justified slot as per the client's view of the canonical chain.
-    pub last_justified_slot: u64,
+    /// The state of the parent block, which provides the last justified slot and the recent
+    /// block hashes.
+    pub parent_state: Arc<BeaconState>,
+    /// The state to which the block will be applied: the `parent_state` after any cycle
+    /// transition which is due at the slot of the block. It provides the randao commitment of
+    /// the block proposer.
+    pub pre_block_state: Arc<BeaconState>,
     /// The last justified block hash as per the client's view of the canonical chain.
     pub last_justified_block_hash: Hash256,
     /// The last finalized slot as per the client's view of the canonical chain.
     pub last_finalized_slot: u64,
-    /// A vec of the hashes of the blocks preceeding the present slot.
-    pub recent_block_hashes: Arc<Vec<Hash256>>,
-    /// A map of slots to a block proposer validation index.
+    /// The hash of the latest block at or before the `last_finalized_slot` in the client's view
+    /// of the canonical chain.
+    pub last_finalized_block_hash: Hash256,
+    /// A map of slots to a block proposer validation index, generated from the `parent_state`.
     pub proposer_map: Arc<ProposerMap>,
+    /// A map of slots to a block proposer validation index, generated from the
+    /// `pre_block_state`.
+    pub block_proposer_map: Arc<ProposerMap>,
     /// A map of (slot, shard_id) to the attestation set of validation indices.
     pub attester_map: Arc<AttesterMap>,
     /// The store containing block information.
@@ -75,9 +93,6 @@ where
     ///
     /// This function will determine if the block is new, already known or invalid (either
     /// intrinsically or due to some application error.)
-    ///
-    /// Note: this function does not implement randao_reveal checking as it is not in the
-    /// specification.
     #[allow(dead_code)]
     pub fn validate_ssz_block(
         &self,
@@ -86,16 +101,7 @@ where
     where
         T: ClientDB + Sized,
     {
-        /*
-         * If the block slot corresponds to a slot in the future, return immediately with an error.
-         *
-         * It is up to the calling fn to determine what should be done with "future" blocks (e.g.,
-         * cache or discard).
-         */
         let block_slot = b.slot();
-        if block_slot > self.present_slot {
-            return Err(SszBeaconBlockValidationError::FutureSlot);
-        }
 
         /*
          * If the block is unknown (assumed unknown because we checked the db earlier in this
@@ -103,28 +109,13 @@ where
          *
          * If a slot is finalized, there's no point in considering any other blocks for that slot.
          *
-         * TODO: We can more strongly throw away blocks based on the `last_finalized_block` related
-         * to this `last_finalized_slot`. Namely, any block in a future slot must include the
-         * `last_finalized_block` in it's chain.
+         * Blocks in later slots which do not include the `last_finalized_block_hash` in their
+         * chain are dropped once the parent is known (see below).
          */
         if block_slot <= self.last_finalized_slot {
             return Err(SszBeaconBlockValidationError::SlotAlreadyFinalized);
         }
 
-        /*
-         * If the PoW chain hash is not known to us, drop it.
-         *
-         * We only accept blocks that reference a known PoW hash.
-         *
-         * Note: it is not clear what a "known" PoW chain ref is. Likely it means the block hash is
-         * "sufficienty deep in the canonical PoW chain". This should be clarified as the spec
-         * crystallizes.
-         */
-        let pow_chain_reference = b.pow_chain_reference();
-        if !self.pow_store.block_hash_exists(b.pow_chain_reference())? {
-            return Err(SszBeaconBlockValidationError::UnknownPoWChainRef);
-        }
-
         /*
          * Store a slice of the serialized attestations from the block SSZ.
          */
@@ -161,13 +152,16 @@ where
         let parent_hash = b
             .parent_hash()
             .ok_or(SszBeaconBlockValidationError::BadAncestorHashesSsz)?;
-        let parent_block_slot = match self.block_store.get_serialized_block(&parent_hash)? {
-            None => return Err(SszBeaconBlockValidationError::UnknownParentHash),
-            Some(ssz) => {
-                let parent_block = SszBeaconBlock::from_slice(&ssz[..])?;
-                parent_block.slot()
-            }
-        };
+        let (parent_block_slot, parent_ancestor_hashes): (u64, Vec<Hash256>) =
+            match self.block_store.get_serialized_block(&parent_hash)? {
+                None => return Err(SszBeaconBlockValidationError::UnknownParentHash),
+                Some(ssz) => {
+                    let parent_block = SszBeaconBlock::from_slice(&ssz[..])?;
+                    let (hashes, _) = Decodable::ssz_decode(&parent_block.ancestor_hashes(), 0)
+                        .map_err(|_| SszBeaconBlockValidationError::BadAncestorHashesSsz)?;
+                    (parent_block.slot(), hashes)
+                }
+            };
 
         /*
          * The parent block slot must be less than the block slot.
@@ -178,6 +172,63 @@ where
             return Err(SszBeaconBlockValidationError::ParentSlotHigherThanBlockSlot);
         }
 
+        /*
+         * The block must include the last finalized block in its chain.
+         *
+         * A block which does not can never become part of the canonical chain, as the finalized
+         * block will never be reverted.
+         */
+        /*
+         * The ancestor hashes must be those derived from the parent block.
+         *
+         * The skip links are followed when checking for the last finalized block (below), so a
+         * block which could choose its own ancestor hashes could claim to descend from any block.
+         * Each stored block has passed this check, so the links of its ancestors are also sound.
+         */
+        let (ancestor_hashes, _): (Vec<Hash256>, usize) =
+            Decodable::ssz_decode(&b.ancestor_hashes(), 0)
+                .map_err(|_| SszBeaconBlockValidationError::BadAncestorHashesSsz)?;
+        let expected_ancestor_hashes = child_ancestor_hashes(
+            &Hash256::from(parent_hash),
+            parent_block_slot,
+            &parent_ancestor_hashes,
+        );
+        if ancestor_hashes != expected_ancestor_hashes {
+            return Err(SszBeaconBlockValidationError::InvalidAncestorHashes);
+        }
+        if !self.descends_from_finalized(block_slot, &ancestor_hashes)? {
+            return Err(SszBeaconBlockValidationError::NotDescendantOfFinalized);
+        }
+
+        /*
+         * Load the proposer of this block from the proposer map and the validators of the state
+         * to which the block will be applied. Return with an error if it fails.
+         *
+         * The parent state is not used here, as it does not assign a proposer to the slot of a
+         * block which follows a skipped cycle.
+         */
+        let proposer = self
+            .block_proposer_map
+            .get(&block_slot)
+            .and_then(|i| self.pre_block_state.validators.get(*i))
+            .ok_or(SszBeaconBlockValidationError::BadProposerMap)?;
+
+        /*
+         * The randao reveal must be a pre-image of the randao commitment of the block proposer.
+         *
+         * The reveal is hashed once for each layer of the proposer's hash chain which has become
+         * available since the commitment was last changed.
+         */
+        let randao_reveal = Hash256::from(b.randao_reveal());
+        if !verify_randao_reveal(
+            &randao_reveal,
+            &proposer.randao_commitment,
+            block_slot,
+            proposer.randao_last_change,
+        ) {
+            return Err(SszBeaconBlockValidationError::InvalidRandaoReveal);
+        }
+
         /*
          * Generate the context in which attestations will be validated.
          */
@@ -185,11 +236,12 @@ where
             block_slot,
             parent_block_slot,
             cycle_length: self.cycle_length,
-            last_justified_slot: self.last_justified_slot,
-            recent_block_hashes: self.recent_block_hashes.clone(),
+            last_justified_slot: self.parent_state.last_justified_slot,
+            recent_block_hashes: Arc::new(self.parent_state.recent_block_hashes.clone()),
             block_store: self.block_store.clone(),
             validator_store: self.validator_store.clone(),
             attester_map: self.attester_map.clone(),
+            fork_data: self.parent_state.fork_data(),
         });
 
         /*
@@ -213,6 +265,32 @@ where
             return Err(SszBeaconBlockValidationError::NoProposerSignature);
         }
 
+        /*
+         * If the block slot corresponds to a slot in the future, return with an error.
+         *
+         * This is only checked once the randao reveal and the signature of the parent block
+         * proposer have been verified, so a "future" block which is returned with this error is
+         * known to come from the proposer of its slot. It is up to the calling fn to determine
+         * what should be done with such blocks (e.g., cache or discard).
+         */
+        if block_slot > self.present_slot {
+            return Err(SszBeaconBlockValidationError::FutureSlot);
+        }
+
+        /*
+         * If the PoW chain hash is not known to us, drop it.
+         *
+         * We only accept blocks that reference a known PoW hash.
+         *
+         * Note: it is not clear what a "known" PoW chain ref is. Likely it means the block hash is
+         * "sufficienty deep in the canonical PoW chain". This should be clarified as the spec
+         * crystallizes.
+         */
+        let pow_chain_reference = b.pow_chain_reference();
+        if !self.pow_store.block_hash_exists(b.pow_chain_reference())? {
+            return Err(SszBeaconBlockValidationError::UnknownPoWChainRef);
+        }
+
         /*
          * Split the remaining attestations into a vector of slices, each containing
          * a single serialized attestation record.
@@ -300,27 +378,104 @@ where
          */
         deserialized_attestations.insert(0, first_attestation);
 
-        let (ancestor_hashes, _) = Decodable::ssz_decode(&b.ancestor_hashes(), 0)
-            .map_err(|_| SszBeaconBlockValidationError::BadAncestorHashesSsz)?;
-        let (specials, _) = Decodable::ssz_decode(&b.specials(), 0)
+        let (specials, _): (Vec<SpecialRecord>, usize) = Decodable::ssz_decode(&b.specials(), 0)
             .map_err(|_| SszBeaconBlockValidationError::BadSpecialsSsz)?;
 
+        /*
+         * Validate the payload of each special record.
+         */
+        let special_validation_context = SpecialValidationContext {
+            block_slot,
+            validator_store: self.validator_store.clone(),
+            fork_data: self.parent_state.fork_data(),
+        };
+        for special in &specials {
+            special_validation_context.validate_special(special)?;
+        }
+
         /*
          * If we have reached this point, the block is a new valid block that is worthy of
          * processing.
          */
         let block = BeaconBlock {
             slot: block_slot,
-            randao_reveal: Hash256::from(b.randao_reveal()),
+            randao_reveal,
             pow_chain_reference: Hash256::from(pow_chain_reference),
             ancestor_hashes,
-            active_state_root: Hash256::from(b.act_state_root()),
-            crystallized_state_root: Hash256::from(b.cry_state_root()),
+            state_root: Hash256::from(b.state_root()),
             attestations: deserialized_attestations,
             specials,
         };
         Ok(block)
     }
+
+    /// Returns `true` if the `last_finalized_block_hash` is in the chain of a block with the given
+    /// `slot` and `ancestor_hashes`.
+    ///
+    /// Rather than reading each block in the chain from the database, the `ancestor_hashes` skip
+    /// links are followed to jump back as far as possible without passing the
+    /// `last_finalized_slot`.
+    fn descends_from_finalized(
+        &self,
+        slot: u64,
+        ancestor_hashes: &[Hash256],
+    ) -> Result<bool, SszBeaconBlockValidationError> {
+        let mut slot = slot;
+        let mut ancestor_hashes = ancestor_hashes.to_vec();
+        loop {
+            if ancestor_hashes.contains(&self.last_finalized_block_hash) {
+                return Ok(true);
+            }
+            if slot <= self.last_finalized_slot {
+                return Ok(false);
+            }
+
+            let mut next = None;
+            for (i, ancestor_hash) in ancestor_hashes.iter().enumerate().rev() {
+                /*
+                 * The `i`'th ancestor is the latest ancestor with a slot which is a multiple of
+                 * `2^i`, so there is no need to read it if that multiple is before the last
+                 * finalized slot.
+                 */
+                let step = match 1u64.checked_shl(i as u32) {
+                    Some(step) => step,
+                    None => continue,
+                };
+                if (slot - 1) / step * step < self.last_finalized_slot {
+                    continue;
+                }
+
+                let ssz = self
+                    .block_store
+                    .get_serialized_block(&ancestor_hash[..])?
+                    .ok_or_else(|| {
+                        SszBeaconBlockValidationError::DBError("Missing ancestor block".to_string())
+                    })?;
+                let ancestor = SszBeaconBlock::from_slice(&ssz[..])?;
+
+                /*
+                 * Slots may be skipped, so the ancestor may still be before the last finalized
+                 * slot. If so, try a shorter jump.
+                 */
+                let ancestor_slot = ancestor.slot();
+                if ancestor_slot >= self.last_finalized_slot && ancestor_slot < slot {
+                    let (hashes, _): (Vec<Hash256>, usize) =
+                        Decodable::ssz_decode(&ancestor.ancestor_hashes(), 0)
+                            .map_err(|_| SszBeaconBlockValidationError::BadAncestorHashesSsz)?;
+                    next = Some((ancestor_slot, hashes));
+                    break;
+                }
+            }
+
+            match next {
+                Some((ancestor_slot, hashes)) => {
+                    slot = ancestor_slot;
+                    ancestor_hashes = hashes;
+                }
+                None => return Ok(false),
+            }
+        }
+    }
 }
 
 impl From<DBError> for SszBeaconBlockValidationError {
@@ -365,6 +520,12 @@ impl From<AttestationValidationError> for SszBeaconBlockValidationError {
     }
 }
 
+impl From<SpecialValidationError> for SszBeaconBlockValidationError {
+    fn from(e: SpecialValidationError) -> Self {
+        SszBeaconBlockValidationError::SpecialValidationError(e)
+    }
+}
+
 /*
  * Tests for block validation are contained in the root directory "tests" directory (AKA
  * "integration tests directory").
@@ -5,8 +5,10 @@ extern crate ssz;
 extern crate ssz_helpers;
 extern crate types;
 
-mod attestation_parent_hashes;
+pub mod attestation_parent_hashes;
 pub mod attestation_validation;
 pub mod block_validation;
-mod message_generation;
+pub mod message_generation;
+pub mod randao_verification;
 mod signature_verification;
+pub mod special_validation;
@@ -1,16 +1,21 @@
+use super::bls::message_with_domain;
 use super::hashing::canonical_hash;
 use super::ssz::SszStream;
-use super::types::Hash256;
+use super::types::{DomainType, ForkData, Hash256};
 
 /// Generates the message used to validate the signature provided with an AttestationRecord.
 ///
 /// Ensures that the signer of the message has a view of the chain that is compatible with ours.
+///
+/// The message is signed in the attestation domain of the fork version at `slot`, so it cannot be
+/// replayed on another fork.
 pub fn generate_signed_message(
     slot: u64,
     parent_hashes: &[Hash256],
     shard_id: u16,
     shard_block_hash: &Hash256,
     justified_slot: u64,
+    fork_data: &ForkData,
 ) -> Vec<u8> {
     /*
      * Note: it's a little risky here to use SSZ, because the encoding is not necessarily SSZ
@@ -28,7 +33,8 @@ pub fn generate_signed_message(
     ssz_stream.append(shard_block_hash);
     ssz_stream.append(&justified_slot);
     let bytes = ssz_stream.drain();
-    canonical_hash(&bytes)
+    let domain = fork_data.domain(slot, DomainType::Attestation);
+    canonical_hash(&message_with_domain(&bytes, domain))
 }
 
 #[cfg(test)]
@@ -42,6 +48,11 @@ mod tests {
         let shard_id = 15;
         let shard_block_hash = Hash256::from("shard_block_hash".as_bytes());
         let justified_slot = 18;
+        let fork_data = ForkData {
+            pre_fork_version: 1,
+            post_fork_version: 2,
+            fork_slot_number: 100,
+        };
 
         let output = generate_signed_message(
             slot,
@@ -49,6 +60,7 @@ mod tests {
             shard_id,
             &shard_block_hash,
             justified_slot,
+            &fork_data,
         );
 
         /*
@@ -58,10 +70,28 @@ mod tests {
          * Once well-known test vectors are established, they should be placed here.
          */
         let expected = vec![
-            149, 99, 94, 229, 72, 144, 233, 14, 164, 16, 143, 53, 94, 48, 118, 179, 33, 181, 172,
-            215, 2, 191, 176, 18, 188, 172, 137, 178, 236, 66, 74, 120,
+            190, 15, 45, 148, 126, 203, 74, 74, 200, 76, 152, 254, 159, 218, 243, 13, 99, 164, 223,
+            43, 118, 175, 220, 85, 220, 203, 146, 15, 232, 138, 158, 82,
         ];
 
         assert_eq!(output, expected);
+
+        /*
+         * Once the fork has occurred, the same message is signed in a different domain.
+         */
+        let fork_data = ForkData {
+            fork_slot_number: slot,
+            ..fork_data
+        };
+        let output = generate_signed_message(
+            slot,
+            &parent_hashes,
+            shard_id,
+            &shard_block_hash,
+            justified_slot,
+            &fork_data,
+        );
+
+        assert_ne!(output, expected);
     }
 }
@@ -0,0 +1,64 @@
+use super::hashing::canonical_hash;
+use super::types::Hash256;
+
+/// The number of slots for which each layer of a validator's randao hash chain is valid.
+pub const RANDAO_SLOTS_PER_LAYER: u64 = 1;
+
+/// Returns the number of times the `randao_reveal` of a block at `block_slot` must be hashed to
+/// produce the `randao_commitment` of its proposer, given that the commitment was last changed at
+/// `randao_last_change`.
+pub fn randao_layers(block_slot: u64, randao_last_change: u64) -> u64 {
+    block_slot.saturating_sub(randao_last_change) / RANDAO_SLOTS_PER_LAYER + 1
+}
+
+/// Hash the `value` `n` times.
+pub fn repeat_hash(value: &Hash256, n: u64) -> Hash256 {
+    let mut hash = *value;
+    for _ in 0..n {
+        hash = Hash256::from(&canonical_hash(&hash)[..]);
+    }
+    hash
+}
+
+/// Returns `true` if the `randao_reveal` of a block at `block_slot` is a valid pre-image of the
+/// `randao_commitment` of its proposer.
+pub fn verify_randao_reveal(
+    randao_reveal: &Hash256,
+    randao_commitment: &Hash256,
+    block_slot: u64,
+    randao_last_change: u64,
+) -> bool {
+    let layers = randao_layers(block_slot, randao_last_change);
+    repeat_hash(randao_reveal, layers) == *randao_commitment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_randao_layers() {
+        assert_eq!(randao_layers(10, 10), 1);
+        assert_eq!(randao_layers(12, 10), 3);
+        /*
+         * A commitment changed after the block slot is treated as changed at the block slot.
+         */
+        assert_eq!(randao_layers(8, 10), 1);
+    }
+
+    #[test]
+    fn test_verify_randao_reveal() {
+        let secret = Hash256::from("secret".as_bytes());
+        let commitment = repeat_hash(&secret, 10);
+
+        let reveal = repeat_hash(&secret, 7);
+        assert!(verify_randao_reveal(&reveal, &commitment, 12, 10));
+
+        /*
+         * The reveal must be hashed exactly the required number of times.
+         */
+        assert!(!verify_randao_reveal(&reveal, &commitment, 13, 10));
+        assert!(!verify_randao_reveal(&reveal, &commitment, 11, 10));
+        assert!(!verify_randao_reveal(&commitment, &commitment, 10, 10));
+    }
+}
@@ -0,0 +1,365 @@
+use super::bls::{message_with_domain, AggregatePublicKey, PublicKey};
+use super::db::stores::{ValidatorStore, ValidatorStoreError};
+use super::db::ClientDB;
+use super::message_generation::generate_signed_message;
+use super::types::{
+    DomainType, ForkData, SlashableVote, SpecialPayload, SpecialPayloadError, SpecialRecord,
+    LOGOUT_MESSAGE,
+};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+pub enum SpecialValidationError {
+    UnknownKind,
+    BadPayloadSsz,
+    NoPublicKeyForValidator,
+    PublicKeyCorrupt,
+    BadLogoutSignature,
+    NotSlashable,
+    NoSlashableValidators,
+    BadCasperVoteSignature,
+    RandaoChangeNotPermitted,
+    DBError(String),
+}
+
+/// The context against which some special record should be validated.
+pub struct SpecialValidationContext<T>
+where
+    T: ClientDB + Sized,
+{
+    /// The slot of the block that contained the special.
+    pub block_slot: u64,
+    /// The store containing validator information.
+    pub validator_store: Arc<ValidatorStore<T>>,
+    /// The fork versions which determine the domains the specials were signed in.
+    pub fork_data: ForkData,
+}
+
+impl<T> SpecialValidationContext<T>
+where
+    T: ClientDB,
+{
+    /// Validate a SpecialRecord against this context, returning its decoded payload.
+    ///
+    /// - A `Logout` must be signed by the validator logging out, in the logout domain of the block
+    /// slot.
+    /// - A `CasperSlashing` must contain two correctly signed votes which violate a slashing
+    /// condition and share at least one signer.
+    /// - A `RandaoChange` is not permitted, as the change of the proposer's randao commitment is
+    /// queued from the `randao_reveal` of the block when it is applied to the state.
+    ///
+    /// Note: the state of the validators involved (e.g., whether they are active) is not checked
+    /// here, specials for validators in the wrong state will have no effect when processed.
+    pub fn validate_special(
+        &self,
+        special: &SpecialRecord,
+    ) -> Result<SpecialPayload, SpecialValidationError> {
+        let payload = special.payload()?;
+
+        match &payload {
+            SpecialPayload::Logout(logout) => {
+                let pubkey = self.public_key(logout.validator_index)?;
+                let domain = self.fork_data.domain(self.block_slot, DomainType::Logout);
+                let message = message_with_domain(LOGOUT_MESSAGE, domain);
+                if !logout.signature.verify(&message, &pubkey) {
+                    return Err(SpecialValidationError::BadLogoutSignature);
+                }
+            }
+            SpecialPayload::CasperSlashing(slashing) => {
+                if !slashing.is_slashable() {
+                    return Err(SpecialValidationError::NotSlashable);
+                }
+                if slashing.slashable_indices().is_empty() {
+                    return Err(SpecialValidationError::NoSlashableValidators);
+                }
+                self.verify_vote_signature(&slashing.vote_1)?;
+                self.verify_vote_signature(&slashing.vote_2)?;
+            }
+            SpecialPayload::RandaoChange(_) => {
+                return Err(SpecialValidationError::RandaoChangeNotPermitted);
+            }
+        }
+
+        Ok(payload)
+    }
+
+    /// Verify that the aggregate signature of the vote was produced by exactly the validators
+    /// listed in the vote.
+    fn verify_vote_signature(&self, vote: &SlashableVote) -> Result<(), SpecialValidationError> {
+        let mut agg_pub_key = AggregatePublicKey::new();
+        for i in &vote.aggregate_sig_indices {
+            agg_pub_key.add(&self.public_key(*i)?);
+        }
+
+        let message = generate_signed_message(
+            vote.slot,
+            &vote.parent_hashes,
+            vote.shard_id,
+            &vote.shard_block_hash,
+            vote.justified_slot,
+            &self.fork_data,
+        );
+
+        if vote.aggregate_sig.verify(&message, &agg_pub_key) {
+            Ok(())
+        } else {
+            Err(SpecialValidationError::BadCasperVoteSignature)
+        }
+    }
+
+    fn public_key(&self, validator_index: usize) -> Result<PublicKey, SpecialValidationError> {
+        self.validator_store
+            .get_public_key_by_index(validator_index)?
+            .ok_or(SpecialValidationError::NoPublicKeyForValidator)
+    }
+}
+
+impl From<SpecialPayloadError> for SpecialValidationError {
+    fn from(e: SpecialPayloadError) -> Self {
+        match e {
+            SpecialPayloadError::UnknownKind => SpecialValidationError::UnknownKind,
+            SpecialPayloadError::DecodeError(_) => SpecialValidationError::BadPayloadSsz,
+        }
+    }
+}
+
+impl From<ValidatorStoreError> for SpecialValidationError {
+    fn from(e: ValidatorStoreError) -> Self {
+        match e {
+            ValidatorStoreError::DBError(s) => SpecialValidationError::DBError(s),
+            ValidatorStoreError::DecodeError => SpecialValidationError::PublicKeyCorrupt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bls::{AggregateSignature, Keypair, Signature};
+    use super::super::db::MemoryDB;
+    use super::super::ssz::ssz_encode;
+    use super::super::types::{CasperSlashingSpecial, Hash256, LogoutSpecial, RandaoChangeSpecial};
+    use super::*;
+
+    const BLOCK_SLOT: u64 = 10;
+
+    fn test_context(keypairs: &[Keypair]) -> SpecialValidationContext<MemoryDB> {
+        let db = Arc::new(MemoryDB::open());
+        let validator_store = Arc::new(ValidatorStore::new(db));
+        for (i, keypair) in keypairs.iter().enumerate() {
+            validator_store
+                .put_public_key_by_index(i, &keypair.pk)
+                .unwrap();
+        }
+
+        SpecialValidationContext {
+            block_slot: BLOCK_SLOT,
+            validator_store,
+            fork_data: test_fork_data(),
+        }
+    }
+
+    /// Fork data where the fork occurs before `BLOCK_SLOT`, so that signatures from either side
+    /// of the fork are tested.
+    fn test_fork_data() -> ForkData {
+        ForkData {
+            pre_fork_version: 1,
+            post_fork_version: 2,
+            fork_slot_number: 8,
+        }
+    }
+
+    fn logout_message(slot: u64) -> Vec<u8> {
+        let domain = test_fork_data().domain(slot, DomainType::Logout);
+        message_with_domain(LOGOUT_MESSAGE, domain)
+    }
+
+    fn keypairs(n: usize) -> Vec<Keypair> {
+        (0..n).map(|_| Keypair::random()).collect()
+    }
+
+    /// Generate a vote for `slot` and `justified_slot`, signed by each of the `signers`.
+    fn vote(
+        slot: u64,
+        justified_slot: u64,
+        keypairs: &[Keypair],
+        signers: &[usize],
+    ) -> SlashableVote {
+        let parent_hashes = vec![Hash256::from("parent".as_bytes())];
+        let shard_block_hash = Hash256::from("shard_block".as_bytes());
+        let message = generate_signed_message(
+            slot,
+            &parent_hashes,
+            0,
+            &shard_block_hash,
+            justified_slot,
+            &test_fork_data(),
+        );
+
+        let mut aggregate_sig = AggregateSignature::new();
+        for i in signers {
+            aggregate_sig.add(&Signature::new(&message, &keypairs[*i].sk));
+        }
+
+        SlashableVote {
+            aggregate_sig_indices: signers.to_vec(),
+            slot,
+            parent_hashes,
+            shard_id: 0,
+            shard_block_hash,
+            justified_slot,
+            aggregate_sig,
+        }
+    }
+
+    #[test]
+    fn test_validate_logout() {
+        let keypairs = keypairs(2);
+        let context = test_context(&keypairs);
+
+        let logout = LogoutSpecial {
+            validator_index: 1,
+            signature: Signature::new(&logout_message(BLOCK_SLOT), &keypairs[1].sk),
+        };
+        let special = SpecialRecord::logout(&ssz_encode(&logout));
+        assert_eq!(
+            context.validate_special(&special),
+            Ok(SpecialPayload::Logout(logout))
+        );
+
+        /*
+         * Signed in the domain of the previous fork.
+         */
+        let logout = LogoutSpecial {
+            validator_index: 1,
+            signature: Signature::new(&logout_message(0), &keypairs[1].sk),
+        };
+        let special = SpecialRecord::logout(&ssz_encode(&logout));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::BadLogoutSignature)
+        );
+
+        /*
+         * Signed by the wrong validator.
+         */
+        let logout = LogoutSpecial {
+            validator_index: 1,
+            signature: Signature::new(&logout_message(BLOCK_SLOT), &keypairs[0].sk),
+        };
+        let special = SpecialRecord::logout(&ssz_encode(&logout));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::BadLogoutSignature)
+        );
+
+        /*
+         * Unknown validator.
+         */
+        let logout = LogoutSpecial {
+            validator_index: 2,
+            signature: Signature::new(&logout_message(BLOCK_SLOT), &keypairs[0].sk),
+        };
+        let special = SpecialRecord::logout(&ssz_encode(&logout));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::NoPublicKeyForValidator)
+        );
+    }
+
+    #[test]
+    fn test_validate_casper_slashing() {
+        let keypairs = keypairs(4);
+        let context = test_context(&keypairs);
+
+        /*
+         * Validators 1 and 2 surround their own vote.
+         */
+        let slashing = CasperSlashingSpecial {
+            vote_1: vote(8, 2, &keypairs, &[0, 1, 2]),
+            vote_2: vote(6, 4, &keypairs, &[1, 2, 3]),
+        };
+        let special = SpecialRecord::casper_slashing(&ssz_encode(&slashing));
+        assert!(context.validate_special(&special).is_ok());
+
+        /*
+         * Consecutive votes are not slashable.
+         */
+        let slashing = CasperSlashingSpecial {
+            vote_1: vote(4, 2, &keypairs, &[0, 1]),
+            vote_2: vote(6, 4, &keypairs, &[0, 1]),
+        };
+        let special = SpecialRecord::casper_slashing(&ssz_encode(&slashing));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::NotSlashable)
+        );
+
+        /*
+         * Disjoint signers are not slashable.
+         */
+        let slashing = CasperSlashingSpecial {
+            vote_1: vote(8, 2, &keypairs, &[0, 1]),
+            vote_2: vote(6, 4, &keypairs, &[2, 3]),
+        };
+        let special = SpecialRecord::casper_slashing(&ssz_encode(&slashing));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::NoSlashableValidators)
+        );
+
+        /*
+         * A vote claiming a signer which did not sign.
+         */
+        let mut vote_2 = vote(6, 4, &keypairs, &[1, 2]);
+        vote_2.aggregate_sig_indices.push(3);
+        let slashing = CasperSlashingSpecial {
+            vote_1: vote(8, 2, &keypairs, &[1, 2]),
+            vote_2,
+        };
+        let special = SpecialRecord::casper_slashing(&ssz_encode(&slashing));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::BadCasperVoteSignature)
+        );
+    }
+
+    #[test]
+    fn test_validate_randao_change() {
+        let keypairs = keypairs(4);
+        let context = test_context(&keypairs);
+
+        /*
+         * A randao change is rejected, even for the proposer of the block.
+         */
+        let randao_change = RandaoChangeSpecial {
+            proposer_index: 2,
+            new_randao_commitment: Hash256::from("commitment".as_bytes()),
+            slot: 1,
+        };
+        let special = SpecialRecord::randao_change(&ssz_encode(&randao_change));
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::RandaoChangeNotPermitted)
+        );
+    }
+
+    #[test]
+    fn test_validate_bad_payload() {
+        let context = test_context(&keypairs(1));
+
+        let special = SpecialRecord::randao_change(&[42]);
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::BadPayloadSsz)
+        );
+
+        let special = SpecialRecord {
+            kind: 88,
+            data: vec![],
+        };
+        assert_eq!(
+            context.validate_special(&special),
+            Err(SpecialValidationError::UnknownKind)
+        );
+    }
+}
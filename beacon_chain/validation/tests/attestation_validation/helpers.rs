@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
-use super::bls::{AggregateSignature, Keypair, SecretKey, Signature};
+use super::bls::{message_with_domain, AggregateSignature, Keypair, SecretKey, Signature};
 use super::db::stores::{BeaconBlockStore, ValidatorStore};
 use super::db::MemoryDB;
 use super::hashing::canonical_hash;
 use super::ssz::SszStream;
-use super::types::{AttestationRecord, AttesterMap, BeaconBlock, Bitfield, Hash256};
+use super::types::{
+    AttestationRecord, AttesterMap, BeaconBlock, Bitfield, DomainType, ForkData, Hash256,
+};
 use super::validation::attestation_validation::AttestationValidationContext;
 
 pub struct TestStore {
@@ -40,6 +42,7 @@ fn generate_message_hash(
     shard_id: u16,
     shard_block_hash: &Hash256,
     justified_slot: u64,
+    fork_data: &ForkData,
 ) -> Vec<u8> {
     let mut stream = SszStream::new();
     stream.append(&slot);
@@ -48,7 +51,8 @@ fn generate_message_hash(
     stream.append(shard_block_hash);
     stream.append(&justified_slot);
     let bytes = stream.drain();
-    canonical_hash(&bytes)
+    let domain = fork_data.domain(slot, DomainType::Attestation);
+    canonical_hash(&message_with_domain(&bytes, domain))
 }
 
 pub fn generate_attestation(
@@ -62,6 +66,7 @@ pub fn generate_attestation(
     parent_hashes: &[Hash256],
     signing_keys: &[Option<SecretKey>],
     block_store: &BeaconBlockStore<MemoryDB>,
+    fork_data: &ForkData,
 ) -> AttestationRecord {
     let mut attester_bitfield = Bitfield::from_elem(signing_keys.len(), false);
     let mut aggregate_sig = AggregateSignature::new();
@@ -87,6 +92,7 @@ pub fn generate_attestation(
         shard_id,
         shard_block_hash,
         justified_slot,
+        fork_data,
     );
 
     for (i, secret_key) in signing_keys.iter().enumerate() {
@@ -187,6 +193,15 @@ pub fn setup_attestation_validation_test(shard_id: u16, attester_count: usize) -
     }
     attester_map.insert((attestation_slot, shard_id), attesters);
 
+    /*
+     * Place the attestation slot after a fork, so the post-fork domain is used.
+     */
+    let fork_data = ForkData {
+        pre_fork_version: 1,
+        post_fork_version: 2,
+        fork_slot_number: attestation_slot,
+    };
+
     let context: AttestationValidationContext<MemoryDB> = AttestationValidationContext {
         block_slot,
         parent_block_slot,
@@ -196,6 +211,7 @@ pub fn setup_attestation_validation_test(shard_id: u16, attester_count: usize) -
         block_store: stores.block.clone(),
         validator_store: stores.validator.clone(),
         attester_map: Arc::new(attester_map),
+        fork_data,
     };
     let attestation = generate_attestation(
         shard_id,
@@ -208,6 +224,7 @@ pub fn setup_attestation_validation_test(shard_id: u16, attester_count: usize) -
         &parent_hashes.clone(),
         &signing_keys,
         &stores.block,
+        &fork_data,
     );
 
     TestRig {
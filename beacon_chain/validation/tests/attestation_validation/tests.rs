@@ -239,3 +239,20 @@ fn test_attestation_validation_invalid_empty_signature() {
         Err(AttestationValidationError::BadAggregateSignature)
     );
 }
+
+#[test]
+fn test_attestation_validation_invalid_pre_fork_domain() {
+    let mut rig = generic_rig();
+
+    /*
+     * Move the fork beyond the attestation slot, so the attestation should have been signed in
+     * the pre-fork domain.
+     */
+    rig.context.fork_data.fork_slot_number = rig.attestation.slot + 1;
+
+    let result = rig.context.validate_attestation(&rig.attestation);
+    assert_eq!(
+        result,
+        Err(AttestationValidationError::BadAggregateSignature)
+    );
+}
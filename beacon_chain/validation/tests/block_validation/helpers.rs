@@ -6,7 +6,11 @@ use super::db::stores::{BeaconBlockStore, PoWChainStore, ValidatorStore};
 use super::db::MemoryDB;
 use super::ssz::SszStream;
 use super::ssz_helpers::ssz_beacon_block::SszBeaconBlock;
-use super::types::{AttestationRecord, AttesterMap, BeaconBlock, Hash256, ProposerMap};
+use super::types::beacon_block::{child_ancestor_hashes, ANCESTOR_HASHES_LEN};
+use super::types::{
+    Address, AttestationRecord, AttesterMap, BeaconBlock, BeaconState, Hash256, PoWBlock,
+    ProposerMap, ValidatorRecord, ValidatorStatus,
+};
 use super::validation::block_validation::{
     BeaconBlockValidationContext, SszBeaconBlockValidationError,
 };
@@ -21,10 +25,15 @@ pub struct BeaconBlockTestParams {
     pub block_slot: u64,
     pub attestations_justified_slot: u64,
     pub parent_proposer_index: usize,
+    pub block_proposer_index: usize,
     pub validation_context_slot: u64,
     pub validation_context_justified_slot: u64,
     pub validation_context_justified_block_hash: Hash256,
     pub validation_context_finalized_slot: u64,
+    pub validation_context_finalized_block_hash: Hash256,
+    pub randao_reveal: Hash256,
+    pub validation_context_randao_commitment: Hash256,
+    pub validation_context_randao_last_change: u64,
 }
 
 pub struct TestStore {
@@ -49,15 +58,13 @@ impl TestStore {
     }
 }
 
-type ParentHashes = Vec<Hash256>;
-
 /// Setup for a block validation function, without actually executing the
 /// block validation function.
 pub fn setup_block_validation_scenario(
     params: &BeaconBlockTestParams,
 ) -> (
     BeaconBlock,
-    ParentHashes,
+    BeaconState,
     AttesterMap,
     ProposerMap,
     TestStore,
@@ -74,12 +81,10 @@ pub fn setup_block_validation_scenario(
         .map(|i| Hash256::from(i as u64))
         .collect();
     let parent_hash = Hash256::from("parent_hash".as_bytes());
-    let ancestor_hashes = vec![parent_hash.clone(); 32];
-    let randao_reveal = Hash256::from("randao_reveal".as_bytes());
+    let grandparent_hash = Hash256::from("grandparent_hash".as_bytes());
     let justified_block_hash = Hash256::from("justified_hash".as_bytes());
     let pow_chain_ref = Hash256::from("pow_chain".as_bytes());
-    let active_state_root = Hash256::from("active_state".as_bytes());
-    let crystallized_state_root = Hash256::from("cry_state".as_bytes());
+    let state_root = Hash256::from("state".as_bytes());
     let shard_block_hash = Hash256::from("shard_block_hash".as_bytes());
 
     /*
@@ -87,15 +92,28 @@ pub fn setup_block_validation_scenario(
      */
     stores
         .pow_chain
-        .put_block_hash(pow_chain_ref.as_ref())
-        .unwrap();
+        .put_block(&PoWBlock {
+            hash: pow_chain_ref,
+            ..PoWBlock::zero()
+        }).unwrap();
 
     /*
-     * Generate a minimum viable parent block and store it in the database.
+     * Generate a minimum viable grandparent and parent block and store them in the database.
      */
+    let mut grandparent_block = BeaconBlock::zero();
+    grandparent_block.slot = block_slot - 2;
+    stores
+        .block
+        .put_serialized_block(
+            grandparent_hash.as_ref(),
+            &serialize_block(&grandparent_block),
+        )
+        .unwrap();
+
     let mut parent_block = BeaconBlock::zero();
     let parent_attestation = AttestationRecord::zero();
     parent_block.slot = block_slot - 1;
+    parent_block.ancestor_hashes = vec![grandparent_hash; ANCESTOR_HASHES_LEN];
     parent_block.attestations.push(parent_attestation);
     let parent_block_ssz = serialize_block(&parent_block);
     stores
@@ -103,18 +121,25 @@ pub fn setup_block_validation_scenario(
         .put_serialized_block(parent_hash.as_ref(), &parent_block_ssz)
         .unwrap();
 
+    let ancestor_hashes = child_ancestor_hashes(
+        &parent_hash,
+        parent_block.slot,
+        &parent_block.ancestor_hashes,
+    );
+
     let proposer_map = {
         let mut proposer_map = ProposerMap::new();
         proposer_map.insert(parent_block.slot, params.parent_proposer_index);
+        proposer_map.insert(block_slot, params.block_proposer_index);
         proposer_map
     };
 
-    let (attester_map, attestations, _keypairs) = {
+    let (attester_map, attestations, validators) = {
         let mut i = 0;
         let attestation_slot = block_slot - 1;
         let mut attester_map = AttesterMap::new();
         let mut attestations = vec![];
-        let mut keypairs = vec![];
+        let mut validators = vec![];
 
         /*
          * Insert the required justified_block_hash into parent_hashes
@@ -132,12 +157,21 @@ pub fn setup_block_validation_scenario(
             let mut signing_keys = vec![];
             let mut attesters = vec![];
             /*
-             * Generate a random keypair for each validator and clone it into the
-             * list of keypairs. Store it in the database.
+             * Generate a random keypair for each validator and create a validator record with the
+             * randao commitment of the validation context. Store the public key in the database.
              */
             for _ in 0..validators_per_shard {
                 let keypair = Keypair::random();
-                keypairs.push(keypair.clone());
+                validators.push(ValidatorRecord {
+                    pubkey: keypair.pk.clone(),
+                    withdrawal_shard: 0,
+                    withdrawal_address: Address::zero(),
+                    randao_commitment: params.validation_context_randao_commitment,
+                    randao_last_change: params.validation_context_randao_last_change,
+                    balance: 0,
+                    status: ValidatorStatus::Active as u8,
+                    exit_slot: 0,
+                });
                 stores
                     .validator
                     .put_public_key_by_index(i, &keypair.pk)
@@ -159,24 +193,32 @@ pub fn setup_block_validation_scenario(
                 &parent_hashes,
                 &signing_keys[..],
                 &stores.block,
+                &BeaconState::zero().fork_data(),
             );
             attestations.push(attestation);
         }
-        (attester_map, attestations, keypairs)
+        (attester_map, attestations, validators)
     };
 
+    /*
+     * Generate the state of the parent block.
+     */
+    let mut parent_state = BeaconState::zero();
+    parent_state.validators = validators;
+    parent_state.last_justified_slot = params.validation_context_justified_slot;
+    parent_state.recent_block_hashes = parent_hashes;
+
     let block = BeaconBlock {
         slot: block_slot,
-        randao_reveal,
+        randao_reveal: params.randao_reveal,
         pow_chain_reference: pow_chain_ref,
         ancestor_hashes,
-        active_state_root,
-        crystallized_state_root,
+        state_root,
         attestations,
         specials: vec![],
     };
 
-    (block, parent_hashes, attester_map, proposer_map, stores)
+    (block, parent_state, attester_map, proposer_map, stores)
 }
 
 /// Helper function to take some BeaconBlock and SSZ serialize it.
@@ -197,7 +239,7 @@ where
     F: FnOnce(BeaconBlock, AttesterMap, ProposerMap, TestStore)
         -> (BeaconBlock, AttesterMap, ProposerMap, TestStore),
 {
-    let (block, parent_hashes, attester_map, proposer_map, stores) =
+    let (block, parent_state, attester_map, proposer_map, stores) =
         setup_block_validation_scenario(&params);
 
     let (block, attester_map, proposer_map, stores) =
@@ -206,14 +248,22 @@ where
     let ssz_bytes = serialize_block(&block);
     let ssz_block = SszBeaconBlock::from_slice(&ssz_bytes[..]).unwrap();
 
+    /*
+     * The scenarios do not cross a cycle boundary, so the block is applied to the parent state.
+     */
+    let parent_state = Arc::new(parent_state);
+    let proposer_map = Arc::new(proposer_map);
+
     let context = BeaconBlockValidationContext {
         present_slot: params.validation_context_slot,
         cycle_length: params.cycle_length,
-        last_justified_slot: params.validation_context_justified_slot,
+        parent_state: parent_state.clone(),
+        pre_block_state: parent_state,
         last_justified_block_hash: params.validation_context_justified_block_hash,
         last_finalized_slot: params.validation_context_finalized_slot,
-        recent_block_hashes: Arc::new(parent_hashes),
-        proposer_map: Arc::new(proposer_map),
+        last_finalized_block_hash: params.validation_context_finalized_block_hash,
+        proposer_map: proposer_map.clone(),
+        block_proposer_map: proposer_map,
         attester_map: Arc::new(attester_map),
         block_store: stores.block.clone(),
         validator_store: stores.validator.clone(),
@@ -1,12 +1,19 @@
-use super::bls::AggregateSignature;
+use super::bls::{message_with_domain, AggregateSignature, Keypair, Signature};
 use super::hashing::canonical_hash;
 use super::helpers::{
     run_block_validation_scenario, serialize_block, BeaconBlockTestParams, TestStore,
 };
+use super::ssz::{ssz_encode, Decodable};
 use super::ssz_helpers::ssz_beacon_block::SszBeaconBlock;
-use super::types::{BeaconBlock, Hash256, ProposerMap};
+use super::types::beacon_block::child_ancestor_hashes;
+use super::types::{
+    BeaconBlock, BeaconState, DomainType, Hash256, LogoutSpecial, ProposerMap, RandaoChangeSpecial,
+    SpecialRecord, LOGOUT_MESSAGE,
+};
 use super::validation::attestation_validation::AttestationValidationError;
 use super::validation::block_validation::SszBeaconBlockValidationError;
+use super::validation::randao_verification::{randao_layers, repeat_hash};
+use super::validation::special_validation::SpecialValidationError;
 
 fn get_simple_params() -> BeaconBlockTestParams {
     let validators_per_shard: usize = 5;
@@ -17,11 +24,22 @@ fn get_simple_params() -> BeaconBlockTestParams {
     let block_slot = u64::from(cycle_length) * 10000;
     let attestations_justified_slot = block_slot - u64::from(cycle_length);
     let parent_proposer_index = 0;
+    let block_proposer_index = 1;
 
     let validation_context_slot = block_slot;
     let validation_context_justified_slot = attestations_justified_slot;
     let validation_context_justified_block_hash = Hash256::from("justified_hash".as_bytes());
     let validation_context_finalized_slot = 0;
+    /*
+     * The parent block is the only ancestor known to the database.
+     */
+    let validation_context_finalized_block_hash = Hash256::from("parent_hash".as_bytes());
+    let randao_reveal = Hash256::from("randao_reveal".as_bytes());
+    let validation_context_randao_last_change = block_slot - u64::from(cycle_length);
+    let validation_context_randao_commitment = repeat_hash(
+        &randao_reveal,
+        randao_layers(block_slot, validation_context_randao_last_change),
+    );
 
     BeaconBlockTestParams {
         total_validators,
@@ -30,12 +48,17 @@ fn get_simple_params() -> BeaconBlockTestParams {
         shards_per_slot,
         validators_per_shard,
         parent_proposer_index,
+        block_proposer_index,
         block_slot,
         attestations_justified_slot,
         validation_context_slot,
         validation_context_justified_slot,
         validation_context_justified_block_hash,
         validation_context_finalized_slot,
+        validation_context_finalized_block_hash,
+        randao_reveal,
+        validation_context_randao_commitment,
+        validation_context_randao_last_change,
     }
 }
 
@@ -103,10 +126,14 @@ fn test_block_validation_parent_slot_too_high() {
 
 #[test]
 fn test_block_validation_invalid_future_slot() {
-    let params = get_simple_params();
+    let mut params = get_simple_params();
 
-    let mutator = |mut block: BeaconBlock, attester_map, proposer_map, stores| {
-        block.slot = block.slot + 1;
+    params.validation_context_slot = params.block_slot - 1;
+
+    let mutator = |block, attester_map, proposer_map, stores| {
+        /*
+         * Do not mutate
+         */
         (block, attester_map, proposer_map, stores)
     };
 
@@ -138,6 +165,97 @@ fn test_block_validation_invalid_slot_already_finalized() {
     );
 }
 
+#[test]
+fn test_block_validation_invalid_not_descendant_of_finalized() {
+    let mut params = get_simple_params();
+
+    params.validation_context_finalized_block_hash = Hash256::from("other_fork".as_bytes());
+
+    let mutator = |block, attester_map, proposer_map, stores| {
+        /*
+         * Do not mutate
+         */
+        (block, attester_map, proposer_map, stores)
+    };
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert_eq!(
+        status,
+        Err(SszBeaconBlockValidationError::NotDescendantOfFinalized)
+    );
+}
+
+#[test]
+fn test_block_validation_valid_finalized_via_skip_link() {
+    let mut params = get_simple_params();
+
+    /*
+     * The finalized block is not in the database, so the block can only be found to descend
+     * from it by following the skip link to the ancestor at `block_slot - 4`, which the block
+     * inherits from its parent.
+     */
+    let finalized_hash = Hash256::from("finalized_hash".as_bytes());
+    let ancestor_hash = Hash256::from("ancestor_hash".as_bytes());
+    params.validation_context_finalized_slot = params.block_slot - 6;
+    params.validation_context_finalized_block_hash = finalized_hash;
+
+    let mutator = |mut block: BeaconBlock, attester_map, proposer_map, stores: TestStore| {
+        let mut ancestor = BeaconBlock::zero();
+        ancestor.slot = block.slot - 4;
+        ancestor.ancestor_hashes = vec![finalized_hash; 32];
+        stores
+            .block
+            .put_serialized_block(ancestor_hash.as_ref(), &serialize_block(&ancestor))
+            .unwrap();
+
+        let parent_hash = *block.parent_hash().unwrap();
+        let ssz = stores
+            .block
+            .get_serialized_block(parent_hash.as_ref())
+            .unwrap()
+            .unwrap();
+        let (mut parent, _) = BeaconBlock::ssz_decode(&ssz, 0).unwrap();
+        parent.ancestor_hashes[2] = ancestor_hash;
+        stores
+            .block
+            .put_serialized_block(parent_hash.as_ref(), &serialize_block(&parent))
+            .unwrap();
+        block.ancestor_hashes =
+            child_ancestor_hashes(&parent_hash, parent.slot, &parent.ancestor_hashes);
+        (block, attester_map, proposer_map, stores)
+    };
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert!(status.is_ok());
+}
+
+#[test]
+fn test_block_validation_invalid_forged_ancestor_hashes() {
+    let mut params = get_simple_params();
+
+    /*
+     * The block claims a skip link to the finalized block, which is not an ancestor of its
+     * parent.
+     */
+    let finalized_hash = Hash256::from("finalized_hash".as_bytes());
+    params.validation_context_finalized_slot = params.block_slot - 6;
+    params.validation_context_finalized_block_hash = finalized_hash;
+
+    let mutator = |mut block: BeaconBlock, attester_map, proposer_map, stores| {
+        block.ancestor_hashes[2] = finalized_hash;
+        (block, attester_map, proposer_map, stores)
+    };
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert_eq!(
+        status,
+        Err(SszBeaconBlockValidationError::InvalidAncestorHashes)
+    );
+}
+
 #[test]
 fn test_block_validation_invalid_unknown_pow_hash() {
     let params = get_simple_params();
@@ -262,3 +380,106 @@ fn test_block_validation_invalid_2nd_attestation_signature() {
         ))
     );
 }
+
+#[test]
+fn test_block_validation_invalid_randao_reveal() {
+    let params = get_simple_params();
+
+    let mutator = |mut block: BeaconBlock, attester_map, proposer_map, stores| {
+        /*
+         * Reveal the commitment itself, rather than its pre-image.
+         */
+        block.randao_reveal = params.validation_context_randao_commitment;
+        (block, attester_map, proposer_map, stores)
+    };
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert_eq!(
+        status,
+        Err(SszBeaconBlockValidationError::InvalidRandaoReveal)
+    );
+}
+
+#[test]
+fn test_block_validation_invalid_randao_layers() {
+    let mut params = get_simple_params();
+    /*
+     * The proposer has changed their commitment since the reveal was generated, so it is hashed
+     * too few times.
+     */
+    params.validation_context_randao_last_change += 1;
+
+    let mutator =
+        |block, attester_map, proposer_map, stores| (block, attester_map, proposer_map, stores);
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert_eq!(
+        status,
+        Err(SszBeaconBlockValidationError::InvalidRandaoReveal)
+    );
+}
+
+#[test]
+fn test_block_validation_valid_special() {
+    let params = get_simple_params();
+
+    let mutator = |mut block: BeaconBlock, attester_map, proposer_map, stores: TestStore| {
+        /*
+         * Have a validator log out.
+         */
+        let keypair = Keypair::random();
+        let validator_index = params.total_validators;
+        stores
+            .validator
+            .put_public_key_by_index(validator_index, &keypair.pk)
+            .unwrap();
+        let domain = BeaconState::zero()
+            .fork_data()
+            .domain(block.slot, DomainType::Logout);
+        let logout = LogoutSpecial {
+            validator_index,
+            signature: Signature::new(&message_with_domain(LOGOUT_MESSAGE, domain), &keypair.sk),
+        };
+        block
+            .specials
+            .push(SpecialRecord::logout(&ssz_encode(&logout)));
+        (block, attester_map, proposer_map, stores)
+    };
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert_eq!(status.unwrap().specials.len(), 1);
+}
+
+#[test]
+fn test_block_validation_invalid_special() {
+    let params = get_simple_params();
+
+    let mutator = |mut block: BeaconBlock, attester_map, mut proposer_map: ProposerMap, stores| {
+        /*
+         * Include a randao change for the proposer of this block, which should be made with the
+         * randao reveal instead.
+         */
+        proposer_map.insert(block.slot, 3);
+        let randao_change = RandaoChangeSpecial {
+            proposer_index: 3,
+            new_randao_commitment: Hash256::from("new_commitment".as_bytes()),
+            slot: block.slot,
+        };
+        block
+            .specials
+            .push(SpecialRecord::randao_change(&ssz_encode(&randao_change)));
+        (block, attester_map, proposer_map, stores)
+    };
+
+    let status = run_block_validation_scenario(&params, mutator);
+
+    assert_eq!(
+        status,
+        Err(SszBeaconBlockValidationError::SpecialValidationError(
+            SpecialValidationError::RandaoChangeNotPermitted
+        ))
+    );
+}
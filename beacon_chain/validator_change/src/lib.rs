@@ -1,28 +1,74 @@
 extern crate active_validators;
+extern crate bls;
 extern crate bytes;
 extern crate hashing;
+extern crate ssz;
 extern crate types;
 
+pub mod light_client;
+
 use active_validators::validator_is_active;
+use bls::PublicKey;
 use bytes::{BufMut, BytesMut};
 use hashing::canonical_hash;
+use ssz::{decode_ssz_list, Decodable, DecodeError, Encodable, SszStream};
 use std::cmp::max;
 use types::{Hash256, ValidatorRecord, ValidatorStatus};
 
+#[derive(Debug, PartialEq)]
 pub enum UpdateValidatorSetError {
     ArithmeticOverflow,
 }
 
-const VALIDATOR_FLAG_ENTRY: u8 = 0;
-const VALIDATOR_FLAG_EXIT: u8 = 1;
+pub const VALIDATOR_FLAG_ENTRY: u8 = 0;
+pub const VALIDATOR_FLAG_EXIT: u8 = 1;
+
+/// A single entry to or exit from the validator set, as appended to the validator set delta hash
+/// chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorChangeRecord {
+    pub index: usize,
+    pub pubkey: PublicKey,
+    pub flag: u8,
+}
+
+impl Encodable for ValidatorChangeRecord {
+    fn ssz_append(&self, s: &mut SszStream) {
+        s.append(&(self.index as u64));
+        s.append_vec(&self.pubkey.as_bytes());
+        s.append(&self.flag);
+    }
+}
+
+impl Decodable for ValidatorChangeRecord {
+    fn ssz_decode(bytes: &[u8], i: usize) -> Result<(Self, usize), DecodeError> {
+        let (index, i) = u64::ssz_decode(bytes, i)?;
+        let (pubkey_bytes, i) = decode_ssz_list(bytes, i)?;
+        let pubkey = PublicKey::from_bytes(&pubkey_bytes).map_err(|_| DecodeError::TooShort)?;
+        let (flag, i) = u8::ssz_decode(bytes, i)?;
+        Ok((
+            Self {
+                index: index as usize,
+                pubkey,
+                flag,
+            },
+            i,
+        ))
+    }
+}
 
+/// Activate pending validators and exit validators which are pending exit, in order of index,
+/// until the total balance changed reaches the maximum allowed for a single update.
+///
+/// Each change is appended to the `hash_chain`, returning the new validator set delta hash chain
+/// along with the changes, in the order in which they were appended.
 pub fn update_validator_set(
     validators: &mut Vec<ValidatorRecord>,
     hash_chain: Hash256,
     present_slot: u64,
     deposit_size_gwei: u64,
     max_validator_churn_quotient: u64,
-) -> Result<(), UpdateValidatorSetError> {
+) -> Result<(Hash256, Vec<ValidatorChangeRecord>), UpdateValidatorSetError> {
     /*
      * Total balance of all active validators.
      *
@@ -53,9 +99,8 @@ pub fn update_validator_set(
         )
     };
 
-    let mut hasher = ValidatorChangeHashChain {
-        bytes: hash_chain.to_vec(),
-    };
+    let mut hasher = ValidatorChangeHashChain::new(hash_chain);
+    let mut records = vec![];
     let mut total_changed: u64 = 0;
     for (i, v) in validators.iter_mut().enumerate() {
         match v.status {
@@ -73,6 +118,11 @@ pub fn update_validator_set(
                 if new_total_changed <= max_allowable_change {
                     v.status = ValidatorStatus::Active as u8;
                     hasher.extend(i, &v.pubkey.as_bytes(), VALIDATOR_FLAG_ENTRY);
+                    records.push(ValidatorChangeRecord {
+                        index: i,
+                        pubkey: v.pubkey.clone(),
+                        flag: VALIDATOR_FLAG_ENTRY,
+                    });
                     total_changed = new_total_changed;
                 } else {
                     // Entering the validator would exceed the balance delta.
@@ -94,6 +144,11 @@ pub fn update_validator_set(
                     v.status = ValidatorStatus::PendingWithdraw as u8;
                     v.exit_slot = present_slot;
                     hasher.extend(i, &v.pubkey.as_bytes(), VALIDATOR_FLAG_EXIT);
+                    records.push(ValidatorChangeRecord {
+                        index: i,
+                        pubkey: v.pubkey.clone(),
+                        flag: VALIDATOR_FLAG_EXIT,
+                    });
                     total_changed = new_total_changed;
                 } else {
                     // Exiting the validator would exceed the balance delta.
@@ -106,7 +161,7 @@ pub fn update_validator_set(
             break;
         }
     }
-    Ok(())
+    Ok((hasher.hash(), records))
 }
 
 pub struct ValidatorChangeHashChain {
@@ -114,11 +169,21 @@ pub struct ValidatorChangeHashChain {
 }
 
 impl ValidatorChangeHashChain {
+    pub fn new(hash_chain: Hash256) -> Self {
+        Self {
+            bytes: hash_chain.to_vec(),
+        }
+    }
+
     pub fn extend(&mut self, index: usize, pubkey: &Vec<u8>, flag: u8) {
         let mut message = self.bytes.clone();
         message.append(&mut serialize_validator_change_record(index, pubkey, flag));
         self.bytes = canonical_hash(&message);
     }
+
+    pub fn hash(&self) -> Hash256 {
+        Hash256::from(&self.bytes[..])
+    }
 }
 
 fn serialize_validator_change_record(index: usize, pubkey: &Vec<u8>, flag: u8) -> Vec<u8> {
@@ -136,8 +201,194 @@ fn serialize_validator_change_record(index: usize, pubkey: &Vec<u8>, flag: u8) -
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use ssz::ssz_encode;
+
+    const DEPOSIT_SIZE: u64 = 10;
+    const BALANCE: u64 = 100;
+    const CHURN_QUOTIENT: u64 = 10;
+
+    fn validators(statuses: &[ValidatorStatus]) -> Vec<ValidatorRecord> {
+        statuses
+            .iter()
+            .map(|status| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = *status as u8;
+                v.balance = BALANCE;
+                v
+            }).collect()
+    }
+
+    fn statuses(validators: &[ValidatorRecord]) -> Vec<u8> {
+        validators.iter().map(|v| v.status).collect()
+    }
+
+    /// Manually build the hash of the `hash_chain` extended with a single validator change.
+    fn expected_hash(hash_chain: &Hash256, index: u32, pubkey: &[u8], flag: u8) -> Hash256 {
+        let mut message = hash_chain.to_vec();
+        message.push(flag);
+        message.extend_from_slice(&[(index >> 16) as u8, (index >> 8) as u8, index as u8]);
+        message.extend_from_slice(pubkey);
+        Hash256::from(&canonical_hash(&message)[..])
+    }
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn test_update_validator_set_no_changes() {
+        let mut validators = validators(&[ValidatorStatus::Active; 4]);
+        let original = validators.clone();
+        let hash_chain = Hash256::from("hash_chain".as_bytes());
+
+        let (new_hash_chain, records) =
+            update_validator_set(&mut validators, hash_chain, 5, DEPOSIT_SIZE, CHURN_QUOTIENT)
+                .unwrap();
+
+        assert_eq!(new_hash_chain, hash_chain);
+        assert_eq!(records, vec![]);
+        assert_eq!(validators, original);
+    }
+
+    #[test]
+    fn test_update_validator_set_churn_limit() {
+        /*
+         * The total active balance is 400, so the maximum change is max(2 * 10, 400 / 10) = 40,
+         * which is enough for four deposits.
+         */
+        let mut statuses_in = vec![ValidatorStatus::Active; 4];
+        statuses_in.extend_from_slice(&[ValidatorStatus::PendingActivation; 6]);
+        let mut validators = validators(&statuses_in);
+
+        update_validator_set(
+            &mut validators,
+            Hash256::zero(),
+            5,
+            DEPOSIT_SIZE,
+            CHURN_QUOTIENT,
+        ).unwrap();
+
+        let active = ValidatorStatus::Active as u8;
+        let pending = ValidatorStatus::PendingActivation as u8;
+        assert_eq!(
+            statuses(&validators),
+            vec![active, active, active, active, active, active, active, active, pending, pending]
+        );
+    }
+
+    #[test]
+    fn test_update_validator_set_minimum_churn() {
+        /*
+         * With no active validators, two deposits may always be activated.
+         */
+        let mut validators = validators(&[ValidatorStatus::PendingActivation; 3]);
+
+        update_validator_set(
+            &mut validators,
+            Hash256::zero(),
+            5,
+            DEPOSIT_SIZE,
+            CHURN_QUOTIENT,
+        ).unwrap();
+
+        let active = ValidatorStatus::Active as u8;
+        let pending = ValidatorStatus::PendingActivation as u8;
+        assert_eq!(statuses(&validators), vec![active, active, pending]);
+    }
+
+    #[test]
+    fn test_update_validator_set_exits() {
+        let mut validators = validators(&[
+            ValidatorStatus::Active,
+            ValidatorStatus::Active,
+            ValidatorStatus::Active,
+            ValidatorStatus::Active,
+            ValidatorStatus::PendingExit,
+            ValidatorStatus::PendingExit,
+        ]);
+
+        /*
+         * The maximum change is 40, less than the balance of a single validator. As the first
+         * exit exceeds the limit it is not processed, nor is any later change.
+         */
+        update_validator_set(
+            &mut validators,
+            Hash256::zero(),
+            5,
+            DEPOSIT_SIZE,
+            CHURN_QUOTIENT,
+        ).unwrap();
+        assert_eq!(validators[4].status, ValidatorStatus::PendingExit as u8);
+        assert_eq!(validators[5].status, ValidatorStatus::PendingExit as u8);
+
+        /*
+         * With a lower churn quotient the maximum change is 400 / 2 = 200, enough for both exits.
+         */
+        update_validator_set(&mut validators, Hash256::zero(), 5, DEPOSIT_SIZE, 2).unwrap();
+        for v in &validators[4..] {
+            assert_eq!(v.status, ValidatorStatus::PendingWithdraw as u8);
+            assert_eq!(v.exit_slot, 5);
+        }
+        for v in &validators[..4] {
+            assert_eq!(v.status, ValidatorStatus::Active as u8);
+            assert_eq!(v.exit_slot, 0);
+        }
+    }
+
+    #[test]
+    fn test_update_validator_set_hash_chain() {
+        let mut validators = validators(&[
+            ValidatorStatus::Active,
+            ValidatorStatus::PendingActivation,
+            ValidatorStatus::Active,
+            ValidatorStatus::PendingExit,
+        ]);
+        validators[3].balance = DEPOSIT_SIZE;
+        let hash_chain = Hash256::from("hash_chain".as_bytes());
+
+        let (new_hash_chain, records) =
+            update_validator_set(&mut validators, hash_chain, 5, DEPOSIT_SIZE, CHURN_QUOTIENT)
+                .unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                ValidatorChangeRecord {
+                    index: 1,
+                    pubkey: validators[1].pubkey.clone(),
+                    flag: VALIDATOR_FLAG_ENTRY,
+                },
+                ValidatorChangeRecord {
+                    index: 3,
+                    pubkey: validators[3].pubkey.clone(),
+                    flag: VALIDATOR_FLAG_EXIT,
+                },
+            ]
+        );
+
+        let expected = expected_hash(
+            &hash_chain,
+            1,
+            &validators[1].pubkey.as_bytes(),
+            VALIDATOR_FLAG_ENTRY,
+        );
+        let expected = expected_hash(
+            &expected,
+            3,
+            &validators[3].pubkey.as_bytes(),
+            VALIDATOR_FLAG_EXIT,
+        );
+        assert_eq!(new_hash_chain, expected);
+    }
+    #[test]
+    fn test_validator_change_record_ssz_round_trip() {
+        let validators = validators(&[ValidatorStatus::Active]);
+        let original = ValidatorChangeRecord {
+            index: 42,
+            pubkey: validators[0].pubkey.clone(),
+            flag: VALIDATOR_FLAG_EXIT,
+        };
+
+        let bytes = ssz_encode(&original);
+        let (decoded, _) = ValidatorChangeRecord::ssz_decode(&bytes, 0).unwrap();
+
+        assert_eq!(original, decoded);
     }
 }
@@ -0,0 +1,189 @@
+use super::active_validators::validator_is_active;
+use super::bls::PublicKey;
+use super::types::{Hash256, ValidatorRecord};
+use super::{
+    ValidatorChangeHashChain, ValidatorChangeRecord, VALIDATOR_FLAG_ENTRY, VALIDATOR_FLAG_EXIT,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+pub enum LightClientError {
+    HashChainMismatch,
+    UnknownFlag,
+    ValidatorAlreadyActive,
+    UnknownValidator,
+    PublicKeyMismatch,
+}
+
+/// Returns the result of extending the `hash_chain` with each of the `records`, in order.
+pub fn replay_hash_chain(hash_chain: Hash256, records: &[ValidatorChangeRecord]) -> Hash256 {
+    let mut hasher = ValidatorChangeHashChain::new(hash_chain);
+    for record in records {
+        hasher.extend(record.index, &record.pubkey.as_bytes(), record.flag);
+    }
+    hasher.hash()
+}
+
+/// The active validator set as known to a light client, which follows changes to the set by way
+/// of the `validator_set_delta_hash_chain` rather than by processing the full chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightClientValidatorSet {
+    /// The public keys of the active validators, keyed by validator index.
+    pub validators: HashMap<usize, PublicKey>,
+    /// The validator set delta hash chain which resulted in `validators`.
+    pub hash_chain: Hash256,
+}
+
+impl LightClientValidatorSet {
+    /// Instantiate from a trusted validator set and the hash chain which resulted in it.
+    pub fn new(validators: HashMap<usize, PublicKey>, hash_chain: Hash256) -> Self {
+        Self {
+            validators,
+            hash_chain,
+        }
+    }
+
+    /// Instantiate from the active validators in a trusted list of validator records.
+    pub fn from_validator_records(validators: &[ValidatorRecord], hash_chain: Hash256) -> Self {
+        let validators = validators
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| validator_is_active(v))
+            .map(|(i, v)| (i, v.pubkey.clone()))
+            .collect();
+        Self::new(validators, hash_chain)
+    }
+
+    /// Apply the `records` to the validator set, if replaying them upon the present hash chain
+    /// results in the `expected_hash_chain` (e.g., the `validator_set_delta_hash_chain` of a
+    /// state trusted by the light client).
+    ///
+    /// The validator set is not modified if an error is returned.
+    pub fn apply_changes(
+        &mut self,
+        records: &[ValidatorChangeRecord],
+        expected_hash_chain: &Hash256,
+    ) -> Result<(), LightClientError> {
+        if replay_hash_chain(self.hash_chain, records) != *expected_hash_chain {
+            return Err(LightClientError::HashChainMismatch);
+        }
+
+        let mut validators = self.validators.clone();
+        for record in records {
+            match record.flag {
+                VALIDATOR_FLAG_ENTRY => {
+                    if validators.contains_key(&record.index) {
+                        return Err(LightClientError::ValidatorAlreadyActive);
+                    }
+                    validators.insert(record.index, record.pubkey.clone());
+                }
+                VALIDATOR_FLAG_EXIT => {
+                    let pubkey = validators
+                        .remove(&record.index)
+                        .ok_or(LightClientError::UnknownValidator)?;
+                    if pubkey != record.pubkey {
+                        return Err(LightClientError::PublicKeyMismatch);
+                    }
+                }
+                _ => return Err(LightClientError::UnknownFlag),
+            }
+        }
+
+        self.validators = validators;
+        self.hash_chain = *expected_hash_chain;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::ValidatorStatus;
+    use super::super::update_validator_set;
+    use super::*;
+
+    fn validators(statuses: &[ValidatorStatus]) -> Vec<ValidatorRecord> {
+        statuses
+            .iter()
+            .map(|status| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                v.status = *status as u8;
+                v.balance = 10;
+                v
+            }).collect()
+    }
+
+    #[test]
+    fn test_light_client_follows_validator_set_update() {
+        let mut validators = validators(&[
+            ValidatorStatus::Active,
+            ValidatorStatus::PendingActivation,
+            ValidatorStatus::PendingExit,
+        ]);
+        let hash_chain = Hash256::from("hash_chain".as_bytes());
+        let mut light_client =
+            LightClientValidatorSet::from_validator_records(&validators, hash_chain);
+        assert_eq!(light_client.validators.len(), 1);
+
+        let (new_hash_chain, records) =
+            update_validator_set(&mut validators, hash_chain, 5, 10, 32).unwrap();
+
+        light_client
+            .apply_changes(&records, &new_hash_chain)
+            .unwrap();
+
+        assert_eq!(
+            light_client,
+            LightClientValidatorSet::from_validator_records(&validators, new_hash_chain)
+        );
+    }
+
+    #[test]
+    fn test_light_client_rejects_bad_records() {
+        let validators = validators(&[ValidatorStatus::Active, ValidatorStatus::PendingActivation]);
+        let hash_chain = Hash256::zero();
+        let mut light_client =
+            LightClientValidatorSet::from_validator_records(&validators, hash_chain);
+        let original = light_client.clone();
+
+        let entry = ValidatorChangeRecord {
+            index: 1,
+            pubkey: validators[1].pubkey.clone(),
+            flag: VALIDATOR_FLAG_ENTRY,
+        };
+        let expected = replay_hash_chain(hash_chain, &[entry.clone()]);
+
+        /*
+         * The records do not result in the expected hash chain.
+         */
+        assert_eq!(
+            light_client.apply_changes(&[], &expected),
+            Err(LightClientError::HashChainMismatch)
+        );
+
+        /*
+         * The records result in the expected hash chain, but are inconsistent with the validator
+         * set.
+         */
+        let mut bad_entry = entry.clone();
+        bad_entry.index = 0;
+        let bad_entries = [bad_entry];
+        assert_eq!(
+            light_client.apply_changes(&bad_entries, &replay_hash_chain(hash_chain, &bad_entries)),
+            Err(LightClientError::ValidatorAlreadyActive)
+        );
+
+        let mut bad_exit = entry.clone();
+        bad_exit.index = 0;
+        bad_exit.flag = VALIDATOR_FLAG_EXIT;
+        let bad_exits = [bad_exit];
+        assert_eq!(
+            light_client.apply_changes(&bad_exits, &replay_hash_chain(hash_chain, &bad_exits)),
+            Err(LightClientError::PublicKeyMismatch)
+        );
+        assert_eq!(light_client, original);
+
+        light_client.apply_changes(&[entry], &expected).unwrap();
+        assert_eq!(light_client.validators.len(), 2);
+        assert_eq!(light_client.hash_chain, expected);
+    }
+}
@@ -1,10 +1,13 @@
 use bls::verify_proof_of_possession;
-use types::{ValidatorRecord, ValidatorRegistration, ValidatorStatus};
+use types::{
+    signing_domain, DomainType, ValidatorRecord, ValidatorRegistration, ValidatorStatus,
+    INITIAL_FORK_VERSION,
+};
 
 /// The size of a validators deposit in GWei.
 pub const DEPOSIT_GWEI: u64 = 32_000_000_000;
 
-/// Inducts validators into a `CrystallizedState`.
+/// Inducts validators into a `BeaconState`.
 pub struct ValidatorInductor {
     pub current_slot: u64,
     pub shard_count: u16,
@@ -28,10 +31,10 @@ impl ValidatorInductor {
         }
     }
 
-    /// Attempt to induct a validator into the CrystallizedState.
+    /// Attempt to induct a validator into the BeaconState.
     ///
     /// Returns an error if the registration is invalid, otherwise returns the index of the
-    /// validator in `CrystallizedState.validators`.
+    /// validator in `BeaconState.validators`.
     pub fn induct(
         &mut self,
         rego: &ValidatorRegistration,
@@ -56,8 +59,12 @@ impl ValidatorInductor {
 
         /*
          * Prove validator has knowledge of their secret key.
+         *
+         * The deposit is made on the PoW chain without knowledge of the slot at which it will be
+         * inducted, so the proof is always made under the genesis fork version.
          */
-        if !verify_proof_of_possession(&r.proof_of_possession, &r.pubkey) {
+        let domain = signing_domain(INITIAL_FORK_VERSION, DomainType::ProofOfPossession);
+        if !verify_proof_of_possession(&r.proof_of_possession, &r.pubkey, domain) {
             return Err(ValidatorInductionError::InvaidProofOfPossession);
         }
 
@@ -73,7 +80,7 @@ impl ValidatorInductor {
         })
     }
 
-    /// Returns the index of the first `ValidatorRecord` in the `CrystallizedState` where
+    /// Returns the index of the first `ValidatorRecord` in the `BeaconState` where
     /// `validator.status == Withdrawn`. If no such record exists, `None` is returned.
     fn first_withdrawn_validator(&mut self) -> Option<usize> {
         for i in self.empty_validator_start..self.validators.len() {
@@ -85,7 +92,7 @@ impl ValidatorInductor {
         None
     }
 
-    /// Adds a `ValidatorRecord` to the `CrystallizedState` by replacing first validator where
+    /// Adds a `ValidatorRecord` to the `BeaconState` by replacing first validator where
     /// `validator.status == Withdraw`. If no such withdrawn validator exists, adds the new
     /// validator to the end of the list.
     fn add_validator(&mut self, v: ValidatorRecord) -> usize {
@@ -110,21 +117,26 @@ impl ValidatorInductor {
 mod tests {
     use super::*;
 
-    use bls::{Keypair, Signature};
+    use bls::{message_with_domain, Keypair, Signature};
     use hashing::proof_of_possession_hash;
-    use types::{Address, Hash256};
+    use types::{Address, ForkData, Hash256};
+
+    fn pop_domain() -> u64 {
+        ForkData::genesis().domain(0, DomainType::ProofOfPossession)
+    }
 
     fn registration_equals_record(reg: &ValidatorRegistration, rec: &ValidatorRecord) -> bool {
         (reg.pubkey == rec.pubkey)
             & (reg.withdrawal_shard == rec.withdrawal_shard)
             & (reg.withdrawal_address == rec.withdrawal_address)
             & (reg.randao_commitment == rec.randao_commitment)
-            & (verify_proof_of_possession(&reg.proof_of_possession, &rec.pubkey))
+            & (verify_proof_of_possession(&reg.proof_of_possession, &rec.pubkey, pop_domain()))
     }
 
-    /// Generate a proof of possession for some keypair.
+    /// Generate a proof of possession for some keypair, in the genesis signing domain.
     fn get_proof_of_possession(kp: &Keypair) -> Signature {
-        let pop_message = proof_of_possession_hash(&kp.pk.as_bytes());
+        let pop_message =
+            proof_of_possession_hash(&message_with_domain(&kp.pk.as_bytes(), pop_domain()));
         Signature::new_hashed(&pop_message, &kp.sk)
     }
 
@@ -278,4 +290,41 @@ mod tests {
         );
         assert_eq!(validators.len(), 0);
     }
+
+    #[test]
+    fn test_validator_inductor_proof_of_possession_other_fork() {
+        let validators = vec![];
+
+        let kp = Keypair::random();
+        let mut r = get_registration();
+        r.pubkey = kp.pk.clone();
+        let fork_data = ForkData {
+            pre_fork_version: 0,
+            post_fork_version: 1,
+            fork_slot_number: 10,
+        };
+
+        /*
+         * A proof of possession made under a later fork version is invalid, even after the fork.
+         */
+        let domain = fork_data.domain(10, DomainType::ProofOfPossession);
+        let pop_message = proof_of_possession_hash(&message_with_domain(&kp.pk.as_bytes(), domain));
+        r.proof_of_possession = Signature::new_hashed(&pop_message, &kp.sk);
+
+        let mut inductor = ValidatorInductor::new(10, 1024, validators);
+        let result = inductor.induct(&r, ValidatorStatus::PendingActivation);
+
+        assert_eq!(
+            result,
+            Err(ValidatorInductionError::InvaidProofOfPossession)
+        );
+
+        /*
+         * A proof of possession made under the genesis fork version remains valid after the fork.
+         */
+        r.proof_of_possession = get_proof_of_possession(&kp);
+        let result = inductor.induct(&r, ValidatorStatus::PendingActivation);
+
+        assert_eq!(result, Ok(0));
+    }
 }
@@ -1,8 +1,14 @@
 extern crate active_validators;
+extern crate hashing;
 extern crate honey_badger_split;
 extern crate types;
 extern crate vec_shuffle;
 
+mod persistent_committees;
 mod shuffle;
 
+pub use persistent_committees::{
+    apply_persistent_committee_reassignments, initial_persistent_committees,
+    persistent_committee_reassignments,
+};
 pub use shuffle::{shard_and_committees_for_cycle, ValidatorAssignmentError};
@@ -0,0 +1,203 @@
+use super::ValidatorAssignmentError;
+use active_validators::active_validator_indices;
+use hashing::canonical_hash;
+use honey_badger_split::SplitExt;
+use types::{ShardReassignmentRecord, ValidatorRecord};
+use vec_shuffle::shuffle;
+
+/// Delegates the active validators into one persistent committee per shard, given a random seed.
+///
+/// Returns a vector where the `i`'th element is the persistent committee for shard `i`.
+pub fn initial_persistent_committees(
+    seed: &[u8],
+    validators: &[ValidatorRecord],
+    shard_count: u16,
+) -> Result<Vec<Vec<u32>>, ValidatorAssignmentError> {
+    if shard_count == 0 {
+        return Err(ValidatorAssignmentError::TooFewShards);
+    }
+
+    let shuffled_validator_indices: Vec<u32> = {
+        let validator_indices = active_validator_indices(validators)
+            .iter()
+            .map(|i| *i as u32)
+            .collect();
+        shuffle(seed, validator_indices)?
+    };
+
+    Ok(shuffled_validator_indices
+        .honey_badger_split(shard_count as usize)
+        .map(|committee| committee.to_vec())
+        .collect())
+}
+
+/// Randomly selects `count` active validators, given a random seed, and assigns each to the
+/// persistent committee of a random shard.
+///
+/// Each reassignment is to take effect at `slot`.
+pub fn persistent_committee_reassignments(
+    seed: &[u8],
+    validators: &[ValidatorRecord],
+    shard_count: u16,
+    count: usize,
+    slot: u64,
+) -> Result<Vec<ShardReassignmentRecord>, ValidatorAssignmentError> {
+    if shard_count == 0 {
+        return Err(ValidatorAssignmentError::TooFewShards);
+    }
+
+    let shuffled_validator_indices = shuffle(seed, active_validator_indices(validators))?;
+    /*
+     * The shards are shuffled with a seed distinct from (but derived from) that of the
+     * validators.
+     */
+    let shuffled_shards = shuffle(&canonical_hash(seed), (0..u64::from(shard_count)).collect())?;
+
+    Ok(shuffled_validator_indices
+        .iter()
+        .take(count)
+        .enumerate()
+        .map(|(i, validator_index)| ShardReassignmentRecord {
+            validator_index: *validator_index as u64,
+            shard: shuffled_shards[i % shuffled_shards.len()],
+            slot,
+        }).collect())
+}
+
+/// Applies each of the `reassignments` which takes effect at or before `present_slot`, in order,
+/// moving the validator from its present persistent committee (if any) to the committee of the
+/// reassigned shard.
+///
+/// Applied reassignments are removed from `reassignments`. Reassignments to unknown shards are
+/// discarded.
+pub fn apply_persistent_committee_reassignments(
+    persistent_committees: &mut Vec<Vec<u32>>,
+    reassignments: &mut Vec<ShardReassignmentRecord>,
+    present_slot: u64,
+) {
+    let (due, pending): (Vec<ShardReassignmentRecord>, Vec<ShardReassignmentRecord>) =
+        reassignments
+            .drain(..)
+            .partition(|record| record.slot <= present_slot);
+    *reassignments = pending;
+
+    for record in due {
+        let validator_index = record.validator_index as u32;
+        if record.shard as usize >= persistent_committees.len() {
+            continue;
+        }
+        for committee in persistent_committees.iter_mut() {
+            committee.retain(|i| *i != validator_index);
+        }
+        persistent_committees[record.shard as usize].push(validator_index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::ValidatorStatus;
+
+    fn validators(active: usize, inactive: usize) -> Vec<ValidatorRecord> {
+        (0..active + inactive)
+            .map(|i| {
+                let (mut v, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+                if i < active {
+                    v.status = ValidatorStatus::Active as u8;
+                }
+                v
+            }).collect()
+    }
+
+    fn sorted(committees: &[Vec<u32>]) -> Vec<u32> {
+        let mut all: Vec<u32> = committees.iter().flat_map(|c| c.iter().cloned()).collect();
+        all.sort();
+        all
+    }
+
+    #[test]
+    fn test_initial_persistent_committees() {
+        let validators = validators(10, 2);
+
+        let committees = initial_persistent_committees(&[0; 32], &validators, 4).unwrap();
+
+        /*
+         * Every active validator is in exactly one committee.
+         */
+        assert_eq!(committees.len(), 4);
+        assert_eq!(sorted(&committees), (0..10).collect::<Vec<u32>>());
+        for committee in &committees {
+            assert!(committee.len() >= 2 && committee.len() <= 3);
+        }
+
+        assert_eq!(
+            initial_persistent_committees(&[0; 32], &validators, 0),
+            Err(ValidatorAssignmentError::TooFewShards)
+        );
+    }
+
+    #[test]
+    fn test_persistent_committee_reassignments() {
+        let validators = validators(10, 2);
+
+        let reassignments =
+            persistent_committee_reassignments(&[1; 32], &validators, 4, 3, 42).unwrap();
+
+        assert_eq!(reassignments.len(), 3);
+        let mut indices: Vec<u64> = reassignments.iter().map(|r| r.validator_index).collect();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), 3);
+        for record in &reassignments {
+            assert!(record.validator_index < 10);
+            assert!(record.shard < 4);
+            assert_eq!(record.slot, 42);
+        }
+
+        /*
+         * The selection is deterministic given the seed.
+         */
+        assert_eq!(
+            persistent_committee_reassignments(&[1; 32], &validators, 4, 3, 42).unwrap(),
+            reassignments
+        );
+
+        /*
+         * The count is bounded by the number of active validators.
+         */
+        assert_eq!(
+            persistent_committee_reassignments(&[1; 32], &validators, 4, 20, 42)
+                .unwrap()
+                .len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_apply_persistent_committee_reassignments() {
+        let mut committees = vec![vec![0, 1], vec![2, 3]];
+        let mut reassignments = vec![
+            ShardReassignmentRecord {
+                validator_index: 0,
+                shard: 1,
+                slot: 4,
+            },
+            ShardReassignmentRecord {
+                validator_index: 4,
+                shard: 0,
+                slot: 4,
+            },
+            ShardReassignmentRecord {
+                validator_index: 2,
+                shard: 0,
+                slot: 8,
+            },
+        ];
+
+        apply_persistent_committee_reassignments(&mut committees, &mut reassignments, 6);
+
+        assert_eq!(committees, vec![vec![1, 4], vec![2, 3, 0]]);
+        assert_eq!(reassignments.len(), 1);
+        assert_eq!(reassignments[0].slot, 8);
+    }
+}
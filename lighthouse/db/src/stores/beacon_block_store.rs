@@ -73,6 +73,57 @@ impl<T: ClientDB> BeaconBlockStore<T> {
             }
         }
     }
+
+    /// Retrieve the hash of the latest block which is in the chains of both `a` and `b`, where `a`
+    /// and `b` are block hashes.
+    ///
+    /// This function will read each block down both chains until they meet, always stepping back
+    /// along the chain with the highest block. If the chains do not meet before a block at slot
+    /// zero, the function will return None.
+    pub fn find_common_ancestor(
+        &self,
+        a: &[u8],
+        b: &[u8],
+    ) -> Result<Option<BeaconBlockHash>, BeaconBlockAtSlotError> {
+        let (mut a, mut b) = (a.to_vec(), b.to_vec());
+        let (mut a_parent, mut a_slot) = self.parent_and_slot(&a)?;
+        let (mut b_parent, mut b_slot) = self.parent_and_slot(&b)?;
+
+        while a != b {
+            if a_slot == 0 && b_slot == 0 {
+                return Ok(None);
+            }
+            if a_slot >= b_slot {
+                a = a_parent;
+                let (parent, slot) = self.parent_and_slot(&a)?;
+                a_parent = parent;
+                a_slot = slot;
+            } else {
+                b = b_parent;
+                let (parent, slot) = self.parent_and_slot(&b)?;
+                b_parent = parent;
+                b_slot = slot;
+            }
+        }
+
+        Ok(Some(a))
+    }
+
+    /// Retrieve the parent hash and slot of the block with the given hash.
+    pub fn parent_and_slot(
+        &self,
+        hash: &[u8],
+    ) -> Result<(BeaconBlockHash, u64), BeaconBlockAtSlotError> {
+        let ssz = self
+            .get_serialized_block(hash)?
+            .ok_or(BeaconBlockAtSlotError::UnknownBeaconBlock)?;
+        let block = SszBeaconBlock::from_slice(&ssz)
+            .map_err(|_| BeaconBlockAtSlotError::InvalidBeaconBlock)?;
+        let parent_hash = block
+            .parent_hash()
+            .ok_or(BeaconBlockAtSlotError::InvalidBeaconBlock)?;
+        Ok((parent_hash.to_vec(), block.slot()))
+    }
 }
 
 impl From<DBError> for BeaconBlockAtSlotError {
@@ -203,4 +254,67 @@ mod tests {
         let ssz = bs.block_at_slot(&Hash256::from("unknown".as_bytes()), 2);
         assert_eq!(ssz, Err(BeaconBlockAtSlotError::UnknownBeaconBlock));
     }
+
+    #[test]
+    fn test_find_common_ancestor() {
+        let db = Arc::new(MemoryDB::open());
+        let bs = Arc::new(BeaconBlockStore::new(db.clone()));
+
+        /*
+         * Build the following block trees, where each block is named for its slot and the chains
+         * fork after block 1. Block "zero" and block "other_zero" have different parents.
+         *
+         * 0 - 1 - 2 - 4
+         *      \
+         *       3 - 5 - 6
+         *
+         * 0 - 7
+         */
+        let hash = |name: &str| Hash256::from(name.as_bytes()).to_vec();
+        let blocks: [(&str, &str, u64); 9] = [
+            ("zero", "genesis", 0),
+            ("one", "zero", 1),
+            ("two", "one", 2),
+            ("four", "two", 4),
+            ("three", "one", 3),
+            ("five", "three", 5),
+            ("six", "five", 6),
+            ("other_zero", "other_genesis", 0),
+            ("seven", "other_zero", 7),
+        ];
+        for (name, parent, slot) in blocks.iter() {
+            let mut block = BeaconBlock::zero();
+            block.attestations.push(AttestationRecord::zero());
+            block.ancestor_hashes.push(Hash256::from(parent.as_bytes()));
+            block.slot = *slot;
+            let mut s = SszStream::new();
+            s.append(&block);
+            bs.put_serialized_block(&hash(*name), &s.drain()).unwrap();
+        }
+
+        assert_eq!(
+            bs.find_common_ancestor(&hash("four"), &hash("six")),
+            Ok(Some(hash("one")))
+        );
+        assert_eq!(
+            bs.find_common_ancestor(&hash("six"), &hash("two")),
+            Ok(Some(hash("one")))
+        );
+        assert_eq!(
+            bs.find_common_ancestor(&hash("six"), &hash("three")),
+            Ok(Some(hash("three")))
+        );
+        assert_eq!(
+            bs.find_common_ancestor(&hash("four"), &hash("four")),
+            Ok(Some(hash("four")))
+        );
+        assert_eq!(
+            bs.find_common_ancestor(&hash("six"), &hash("seven")),
+            Ok(None)
+        );
+        assert_eq!(
+            bs.find_common_ancestor(&hash("four"), &hash("unknown")),
+            Err(BeaconBlockAtSlotError::UnknownBeaconBlock)
+        );
+    }
 }
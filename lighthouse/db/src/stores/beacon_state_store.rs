@@ -0,0 +1,187 @@
+extern crate ssz;
+extern crate types;
+extern crate validator_change;
+
+use self::ssz::{decode_ssz_list, ssz_encode, Decodable, SszStream};
+use self::types::{BeaconState, Hash256};
+use self::validator_change::ValidatorChangeRecord;
+use super::STATE_DB_COLUMN as DB_COLUMN;
+use super::VALIDATOR_CHANGES_DB_COLUMN;
+use super::{ClientDB, DBError};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+pub enum BeaconStateStoreError {
+    DBError(String),
+    DecodeError,
+}
+
+impl From<DBError> for BeaconStateStoreError {
+    fn from(error: DBError) -> Self {
+        BeaconStateStoreError::DBError(error.message)
+    }
+}
+
+/// Stores `BeaconState` objects, keyed by their canonical root.
+///
+/// The validator set changes made by the cycle transitions which resulted in each state are
+/// stored alongside it, as they are not recorded in the state itself.
+pub struct BeaconStateStore<T>
+where
+    T: ClientDB,
+{
+    db: Arc<T>,
+}
+
+impl<T: ClientDB> BeaconStateStore<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        Self { db }
+    }
+
+    pub fn put_state(&self, root: &Hash256, state: &BeaconState) -> Result<(), DBError> {
+        self.db.put(DB_COLUMN, &root[..], &ssz_encode(state))
+    }
+
+    pub fn get_state(&self, root: &Hash256) -> Result<Option<BeaconState>, BeaconStateStoreError> {
+        match self.db.get(DB_COLUMN, &root[..])? {
+            None => Ok(None),
+            Some(ssz) => {
+                let (state, _) = BeaconState::ssz_decode(&ssz, 0)
+                    .map_err(|_| BeaconStateStoreError::DecodeError)?;
+                Ok(Some(state))
+            }
+        }
+    }
+
+    pub fn state_exists(&self, root: &Hash256) -> Result<bool, DBError> {
+        self.db.exists(DB_COLUMN, &root[..])
+    }
+
+    pub fn delete_state(&self, root: &Hash256) -> Result<(), DBError> {
+        self.db.delete(DB_COLUMN, &root[..])
+    }
+
+    /// Store the validator set changes made by the cycle transitions which resulted in the state
+    /// with the given `root`, as pairs of the slot of each change and the records appended to the
+    /// `validator_set_delta_hash_chain` by it.
+    pub fn put_validator_changes(
+        &self,
+        root: &Hash256,
+        changes: &[(u64, Vec<ValidatorChangeRecord>)],
+    ) -> Result<(), DBError> {
+        let mut s = SszStream::new();
+        for (slot, records) in changes {
+            s.append(slot);
+            s.append_vec(records);
+        }
+        self.db
+            .put(VALIDATOR_CHANGES_DB_COLUMN, &root[..], &s.drain())
+    }
+
+    /// Returns the validator set changes stored for the state with the given `root`, or an empty
+    /// list if none were stored.
+    pub fn get_validator_changes(
+        &self,
+        root: &Hash256,
+    ) -> Result<Vec<(u64, Vec<ValidatorChangeRecord>)>, BeaconStateStoreError> {
+        let ssz = match self.db.get(VALIDATOR_CHANGES_DB_COLUMN, &root[..])? {
+            None => return Ok(vec![]),
+            Some(ssz) => ssz,
+        };
+
+        let mut changes = vec![];
+        let mut i = 0;
+        while i < ssz.len() {
+            let (slot, next) =
+                u64::ssz_decode(&ssz, i).map_err(|_| BeaconStateStoreError::DecodeError)?;
+            let (records, next) =
+                decode_ssz_list(&ssz, next).map_err(|_| BeaconStateStoreError::DecodeError)?;
+            changes.push((slot, records));
+            i = next;
+        }
+        Ok(changes)
+    }
+
+    pub fn delete_validator_changes(&self, root: &Hash256) -> Result<(), DBError> {
+        self.db.delete(VALIDATOR_CHANGES_DB_COLUMN, &root[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::MemoryDB;
+    use super::types::ValidatorRecord;
+    use super::validator_change::VALIDATOR_FLAG_EXIT;
+    use super::*;
+
+    fn test_state() -> BeaconState {
+        let mut state = BeaconState::zero();
+        state.last_state_recalculation_slot = 64;
+        state.validator_set_delta_hash_chain = Hash256::from("delta".as_bytes());
+        state.randao_mix = Hash256::from("randao_mix".as_bytes());
+        state
+    }
+
+    #[test]
+    fn test_beacon_state_store_put_get() {
+        let db = Arc::new(MemoryDB::open());
+        let store = BeaconStateStore::new(db);
+
+        let state = test_state();
+        let root = state.canonical_root();
+
+        assert!(!store.state_exists(&root).unwrap());
+        assert_eq!(store.get_state(&root).unwrap(), None);
+
+        store.put_state(&root, &state).unwrap();
+
+        assert!(store.state_exists(&root).unwrap());
+        assert_eq!(store.get_state(&root).unwrap(), Some(state));
+
+        store.delete_state(&root).unwrap();
+
+        assert!(!store.state_exists(&root).unwrap());
+    }
+
+    #[test]
+    fn test_beacon_state_store_validator_changes() {
+        let db = Arc::new(MemoryDB::open());
+        let store = BeaconStateStore::new(db);
+
+        let root = test_state().canonical_root();
+        let (validator, _) = ValidatorRecord::zero_with_thread_rand_keypair();
+        let changes = vec![
+            (
+                4,
+                vec![ValidatorChangeRecord {
+                    index: 0,
+                    pubkey: validator.pubkey.clone(),
+                    flag: VALIDATOR_FLAG_EXIT,
+                }],
+            ),
+            (8, vec![]),
+        ];
+
+        assert_eq!(store.get_validator_changes(&root), Ok(vec![]));
+
+        store.put_validator_changes(&root, &changes).unwrap();
+        assert_eq!(store.get_validator_changes(&root), Ok(changes));
+
+        store.delete_validator_changes(&root).unwrap();
+        assert_eq!(store.get_validator_changes(&root), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_beacon_state_store_bad_ssz() {
+        let db = Arc::new(MemoryDB::open());
+        let store = BeaconStateStore::new(db.clone());
+
+        let root = Hash256::from("root".as_bytes());
+        db.put(DB_COLUMN, &root[..], "cats".as_bytes()).unwrap();
+
+        assert_eq!(
+            store.get_state(&root),
+            Err(BeaconStateStoreError::DecodeError)
+        );
+    }
+}
@@ -0,0 +1,162 @@
+extern crate ssz;
+extern crate types;
+
+use self::ssz::{decode_ssz_list, ssz_encode, Decodable, Encodable};
+use self::types::Hash256;
+use super::METADATA_DB_COLUMN as DB_COLUMN;
+use super::{ClientDB, DBError};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+pub enum MetadataStoreError {
+    DBError(String),
+    DecodeError,
+}
+
+impl From<DBError> for MetadataStoreError {
+    fn from(error: DBError) -> Self {
+        MetadataStoreError::DBError(error.message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum MetadataKeys {
+    HeadBlockHashes,
+    CanonicalHeadIndex,
+    LastFinalizedSlot,
+    GenesisStateRoot,
+}
+
+/// Stores the metadata required to resume a `BeaconChain` (e.g., the block tree heads).
+pub struct MetadataStore<T>
+where
+    T: ClientDB,
+{
+    db: Arc<T>,
+}
+
+impl<T: ClientDB> MetadataStore<T> {
+    pub fn new(db: Arc<T>) -> Self {
+        Self { db }
+    }
+
+    fn key_bytes(&self, key: &MetadataKeys) -> &'static [u8] {
+        match key {
+            MetadataKeys::HeadBlockHashes => b"head_block_hashes",
+            MetadataKeys::CanonicalHeadIndex => b"canonical_head_index",
+            MetadataKeys::LastFinalizedSlot => b"last_finalized_slot",
+            MetadataKeys::GenesisStateRoot => b"genesis_state_root",
+        }
+    }
+
+    fn put<E: Encodable>(&self, key: &MetadataKeys, val: &E) -> Result<(), DBError> {
+        self.db
+            .put(DB_COLUMN, self.key_bytes(key), &ssz_encode(val))
+    }
+
+    fn get<D: Decodable>(&self, key: &MetadataKeys) -> Result<Option<D>, MetadataStoreError> {
+        match self.db.get(DB_COLUMN, self.key_bytes(key))? {
+            None => Ok(None),
+            Some(ssz) => {
+                let (val, _) =
+                    D::ssz_decode(&ssz, 0).map_err(|_| MetadataStoreError::DecodeError)?;
+                Ok(Some(val))
+            }
+        }
+    }
+
+    pub fn put_head_block_hashes(&self, hashes: &[Hash256]) -> Result<(), DBError> {
+        self.put(&MetadataKeys::HeadBlockHashes, &hashes.to_vec())
+    }
+
+    pub fn get_head_block_hashes(&self) -> Result<Option<Vec<Hash256>>, MetadataStoreError> {
+        let key = self.key_bytes(&MetadataKeys::HeadBlockHashes);
+        match self.db.get(DB_COLUMN, key)? {
+            None => Ok(None),
+            Some(ssz) => {
+                let (hashes, _) =
+                    decode_ssz_list(&ssz, 0).map_err(|_| MetadataStoreError::DecodeError)?;
+                Ok(Some(hashes))
+            }
+        }
+    }
+
+    pub fn put_canonical_head_index(&self, index: usize) -> Result<(), DBError> {
+        self.put(&MetadataKeys::CanonicalHeadIndex, &(index as u64))
+    }
+
+    pub fn get_canonical_head_index(&self) -> Result<Option<usize>, MetadataStoreError> {
+        let index: Option<u64> = self.get(&MetadataKeys::CanonicalHeadIndex)?;
+        Ok(index.map(|i| i as usize))
+    }
+
+    pub fn put_last_finalized_slot(&self, slot: u64) -> Result<(), DBError> {
+        self.put(&MetadataKeys::LastFinalizedSlot, &slot)
+    }
+
+    pub fn get_last_finalized_slot(&self) -> Result<Option<u64>, MetadataStoreError> {
+        self.get(&MetadataKeys::LastFinalizedSlot)
+    }
+
+    /// Store the root of the genesis state, from which every other state is derived.
+    pub fn put_genesis_state_root(&self, state_root: &Hash256) -> Result<(), DBError> {
+        self.put(&MetadataKeys::GenesisStateRoot, state_root)
+    }
+
+    pub fn get_genesis_state_root(&self) -> Result<Option<Hash256>, MetadataStoreError> {
+        self.get(&MetadataKeys::GenesisStateRoot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::MemoryDB;
+    use super::*;
+
+    #[test]
+    fn test_metadata_store_empty() {
+        let db = Arc::new(MemoryDB::open());
+        let store = MetadataStore::new(db);
+
+        assert_eq!(store.get_head_block_hashes(), Ok(None));
+        assert_eq!(store.get_canonical_head_index(), Ok(None));
+        assert_eq!(store.get_last_finalized_slot(), Ok(None));
+        assert_eq!(store.get_genesis_state_root(), Ok(None));
+    }
+
+    #[test]
+    fn test_metadata_store_put_get() {
+        let db = Arc::new(MemoryDB::open());
+        let store = MetadataStore::new(db);
+
+        let hashes = vec![
+            Hash256::from("one".as_bytes()),
+            Hash256::from("two".as_bytes()),
+        ];
+        let state_root = Hash256::from("state".as_bytes());
+
+        store.put_head_block_hashes(&hashes).unwrap();
+        store.put_canonical_head_index(1).unwrap();
+        store.put_last_finalized_slot(42).unwrap();
+        store.put_genesis_state_root(&state_root).unwrap();
+
+        assert_eq!(store.get_head_block_hashes(), Ok(Some(hashes)));
+        assert_eq!(store.get_canonical_head_index(), Ok(Some(1)));
+        assert_eq!(store.get_last_finalized_slot(), Ok(Some(42)));
+        assert_eq!(store.get_genesis_state_root(), Ok(Some(state_root)));
+    }
+
+    #[test]
+    fn test_metadata_store_bad_ssz() {
+        let db = Arc::new(MemoryDB::open());
+        let store = MetadataStore::new(db.clone());
+
+        db.put(DB_COLUMN, b"last_finalized_slot", "cats".as_bytes())
+            .unwrap();
+
+        assert_eq!(
+            store.get_last_finalized_slot(),
+            Err(MetadataStoreError::DecodeError)
+        );
+    }
+}
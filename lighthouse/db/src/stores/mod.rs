@@ -1,17 +1,33 @@
 use super::{ClientDB, DBError};
 
 mod beacon_block_store;
+mod beacon_state_store;
+mod metadata_store;
 mod pow_chain_store;
 mod validator_store;
 
 pub use self::beacon_block_store::{BeaconBlockAtSlotError, BeaconBlockStore};
-pub use self::pow_chain_store::PoWChainStore;
+pub use self::beacon_state_store::{BeaconStateStore, BeaconStateStoreError};
+pub use self::metadata_store::{MetadataStore, MetadataStoreError};
+pub use self::pow_chain_store::{PoWChainStore, PoWChainStoreError};
 pub use self::validator_store::{ValidatorStore, ValidatorStoreError};
 
 use super::bls;
 
 pub const BLOCKS_DB_COLUMN: &str = "blocks";
 pub const POW_CHAIN_DB_COLUMN: &str = "powchain";
+pub const DEPOSITS_DB_COLUMN: &str = "deposits";
 pub const VALIDATOR_DB_COLUMN: &str = "validator";
+pub const STATE_DB_COLUMN: &str = "state";
+pub const VALIDATOR_CHANGES_DB_COLUMN: &str = "validator_changes";
+pub const METADATA_DB_COLUMN: &str = "metadata";
 
-pub const COLUMNS: [&str; 3] = [BLOCKS_DB_COLUMN, POW_CHAIN_DB_COLUMN, VALIDATOR_DB_COLUMN];
+pub const COLUMNS: [&str; 7] = [
+    BLOCKS_DB_COLUMN,
+    POW_CHAIN_DB_COLUMN,
+    DEPOSITS_DB_COLUMN,
+    VALIDATOR_DB_COLUMN,
+    STATE_DB_COLUMN,
+    VALIDATOR_CHANGES_DB_COLUMN,
+    METADATA_DB_COLUMN,
+];
@@ -1,7 +1,29 @@
+extern crate ssz;
+extern crate types;
+
+use self::ssz::{decode_ssz_list, ssz_encode, Decodable};
+use self::types::{Hash256, PoWBlock, ValidatorRegistration};
+use super::DEPOSITS_DB_COLUMN;
 use super::POW_CHAIN_DB_COLUMN as DB_COLUMN;
 use super::{ClientDB, DBError};
 use std::sync::Arc;
 
+/// The key under which the hash of the latest followed PoW block is stored. It cannot collide with
+/// the 32-byte block hash keys.
+const HEAD_KEY: &[u8] = b"head";
+
+#[derive(Debug, PartialEq)]
+pub enum PoWChainStoreError {
+    DBError(String),
+    DecodeError,
+}
+
+impl From<DBError> for PoWChainStoreError {
+    fn from(error: DBError) -> Self {
+        PoWChainStoreError::DBError(error.message)
+    }
+}
+
 pub struct PoWChainStore<T>
 where
     T: ClientDB,
@@ -14,13 +36,150 @@ impl<T: ClientDB> PoWChainStore<T> {
         Self { db }
     }
 
-    pub fn put_block_hash(&self, hash: &[u8]) -> Result<(), DBError> {
-        self.db.put(DB_COLUMN, hash, &[0])
+    pub fn put_block(&self, block: &PoWBlock) -> Result<(), DBError> {
+        self.db.put(DB_COLUMN, &block.hash[..], &ssz_encode(block))
+    }
+
+    pub fn get_block(&self, hash: &[u8]) -> Result<Option<PoWBlock>, PoWChainStoreError> {
+        match self.db.get(DB_COLUMN, hash)? {
+            None => Ok(None),
+            Some(ssz) => {
+                let (block, _) =
+                    PoWBlock::ssz_decode(&ssz, 0).map_err(|_| PoWChainStoreError::DecodeError)?;
+                Ok(Some(block))
+            }
+        }
     }
 
     pub fn block_hash_exists(&self, hash: &[u8]) -> Result<bool, DBError> {
         self.db.exists(DB_COLUMN, hash)
     }
+
+    /// Delete the block with the given `hash`, along with its deposits.
+    pub fn delete_block(&self, hash: &[u8]) -> Result<(), DBError> {
+        self.db.delete(DB_COLUMN, hash)?;
+        self.db.delete(DEPOSITS_DB_COLUMN, hash)
+    }
+
+    /// Store the `hash` of the latest block to have been followed.
+    pub fn put_head(&self, hash: &Hash256) -> Result<(), DBError> {
+        self.db.put(DB_COLUMN, HEAD_KEY, &hash[..])
+    }
+
+    /// Retrieve the hash of the latest block to have been followed, if any.
+    pub fn get_head(&self) -> Result<Option<Hash256>, PoWChainStoreError> {
+        match self.db.get(DB_COLUMN, HEAD_KEY)? {
+            None => Ok(None),
+            Some(bytes) => {
+                if bytes.len() != 32 {
+                    return Err(PoWChainStoreError::DecodeError);
+                }
+                Ok(Some(Hash256::from(&bytes[..])))
+            }
+        }
+    }
+
+    /// Store all of the deposits made to the deposit contract up to and including the PoW
+    /// receipt root `hash`, in the order in which they were made.
+    pub fn put_deposits(
+        &self,
+        hash: &[u8],
+        deposits: &[ValidatorRegistration],
+    ) -> Result<(), DBError> {
+        self.db
+            .put(DEPOSITS_DB_COLUMN, hash, &ssz_encode(&deposits.to_vec()))
+    }
+
+    /// Retrieve all of the deposits made up to and including the PoW receipt root `hash`.
+    ///
+    /// Returns `None` if the deposits for `hash` are unknown.
+    pub fn get_deposits(
+        &self,
+        hash: &[u8],
+    ) -> Result<Option<Vec<ValidatorRegistration>>, PoWChainStoreError> {
+        match self.db.get(DEPOSITS_DB_COLUMN, hash)? {
+            None => Ok(None),
+            Some(ssz) => {
+                let (deposits, _) =
+                    decode_ssz_list(&ssz, 0).map_err(|_| PoWChainStoreError::DecodeError)?;
+                Ok(Some(deposits))
+            }
+        }
+    }
 }
 
-// TODO: add tests once a memory-db is implemented
+#[cfg(test)]
+mod tests {
+    use super::super::super::MemoryDB;
+    use super::*;
+
+    #[test]
+    fn test_pow_chain_store_blocks() {
+        let db = Arc::new(MemoryDB::open());
+        let store = PoWChainStore::new(db);
+
+        let block = PoWBlock {
+            hash: Hash256::from("hash".as_bytes()),
+            parent_hash: Hash256::from("parent_hash".as_bytes()),
+            number: 42,
+            timestamp: 1_537_488_655,
+        };
+        let hash = &block.hash[..];
+        assert!(!store.block_hash_exists(hash).unwrap());
+        assert_eq!(store.get_block(hash), Ok(None));
+
+        store.put_block(&block).unwrap();
+        store
+            .put_deposits(hash, &[ValidatorRegistration::random()])
+            .unwrap();
+        assert!(store.block_hash_exists(hash).unwrap());
+        assert_eq!(store.get_block(hash), Ok(Some(block.clone())));
+
+        store.delete_block(hash).unwrap();
+        assert!(!store.block_hash_exists(hash).unwrap());
+        assert_eq!(store.get_deposits(hash), Ok(None));
+    }
+
+    #[test]
+    fn test_pow_chain_store_head() {
+        let db = Arc::new(MemoryDB::open());
+        let store = PoWChainStore::new(db);
+
+        assert_eq!(store.get_head(), Ok(None));
+
+        let hash = Hash256::from("hash".as_bytes());
+        store.put_head(&hash).unwrap();
+        assert_eq!(store.get_head(), Ok(Some(hash)));
+        assert!(!store.block_hash_exists(&hash[..]).unwrap());
+    }
+
+    #[test]
+    fn test_pow_chain_store_deposits() {
+        let db = Arc::new(MemoryDB::open());
+        let store = PoWChainStore::new(db);
+
+        let hash = b"pow_receipt_root";
+        assert_eq!(store.get_deposits(hash), Ok(None));
+
+        let deposits = vec![ValidatorRegistration::random(); 3];
+        store.put_deposits(hash, &deposits).unwrap();
+        assert_eq!(store.get_deposits(hash), Ok(Some(deposits)));
+
+        store.put_deposits(hash, &[]).unwrap();
+        assert_eq!(store.get_deposits(hash), Ok(Some(vec![])));
+    }
+
+    #[test]
+    fn test_pow_chain_store_bad_deposits_ssz() {
+        let db = Arc::new(MemoryDB::open());
+        let store = PoWChainStore::new(db.clone());
+
+        db.put(DEPOSITS_DB_COLUMN, b"pow_receipt_root", "cats".as_bytes())
+            .unwrap();
+
+        assert_eq!(
+            store.get_deposits(b"pow_receipt_root"),
+            Err(PoWChainStoreError::DecodeError)
+        );
+    }
+}
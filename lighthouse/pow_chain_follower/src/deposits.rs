@@ -0,0 +1,99 @@
+use ssz::{ssz_encode, Decodable};
+use types::ValidatorRegistration;
+
+/// The size of a word in the ABI encoding of a log.
+const WORD_LEN: usize = 32;
+
+/// Decode the `ValidatorRegistration` from the data of a deposit contract log.
+///
+/// The log data is the ABI encoding of a single `bytes` value (an offset word, a length word and
+/// the zero-padded bytes), where the bytes are the SSZ encoding of the registration.
+///
+/// Returns `None` if the data is malformed.
+pub fn decode_deposit_log(data: &[u8]) -> Option<ValidatorRegistration> {
+    let offset = decode_word(data, 0)?;
+    let len = decode_word(data, offset)?;
+    let start = offset.checked_add(WORD_LEN)?;
+    let bytes = data.get(start..start.checked_add(len)?)?;
+
+    match ValidatorRegistration::ssz_decode(bytes, 0) {
+        Ok((registration, i)) if i == bytes.len() => Some(registration),
+        _ => None,
+    }
+}
+
+/// Encode a `ValidatorRegistration` as the data of a deposit contract log.
+///
+/// The inverse of `decode_deposit_log`.
+pub fn encode_deposit_log(registration: &ValidatorRegistration) -> Vec<u8> {
+    let bytes = ssz_encode(registration);
+    let padding = (WORD_LEN - bytes.len() % WORD_LEN) % WORD_LEN;
+    [
+        &encode_word(WORD_LEN)[..],
+        &encode_word(bytes.len())[..],
+        &bytes[..],
+        &vec![0; padding][..],
+    ].concat()
+}
+
+/// Decode the word at `offset` in the `data` as an integer, returning `None` if the word is
+/// missing or the integer does not fit in a `usize`.
+fn decode_word(data: &[u8], offset: usize) -> Option<usize> {
+    let word = data.get(offset..offset.checked_add(WORD_LEN)?)?;
+    let (high, low) = word.split_at(WORD_LEN - 8);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+    let n = low.iter().fold(0u64, |n, b| (n << 8) | u64::from(*b));
+    if n > usize::max_value() as u64 {
+        return None;
+    }
+    Some(n as usize)
+}
+
+fn encode_word(n: usize) -> [u8; WORD_LEN] {
+    let mut word = [0; WORD_LEN];
+    for i in 0..8 {
+        word[WORD_LEN - 1 - i] = ((n as u64) >> (8 * i)) as u8;
+    }
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_log_encode_decode() {
+        let registration = ValidatorRegistration::random();
+
+        let data = encode_deposit_log(&registration);
+
+        assert_eq!(data.len() % WORD_LEN, 0);
+        assert_eq!(decode_deposit_log(&data), Some(registration));
+    }
+
+    #[test]
+    fn test_deposit_log_malformed() {
+        let registration = ValidatorRegistration::random();
+        let data = encode_deposit_log(&registration);
+
+        assert_eq!(decode_deposit_log(&[]), None);
+        assert_eq!(decode_deposit_log(&data[..WORD_LEN * 2 + 4]), None);
+
+        /*
+         * An offset which points beyond the data.
+         */
+        let mut bad_offset = data.clone();
+        bad_offset[WORD_LEN - 2] = 255;
+        assert_eq!(decode_deposit_log(&bad_offset), None);
+
+        /*
+         * A length which does not match the SSZ encoding.
+         */
+        let mut bad_len = data.clone();
+        let len = ssz_encode(&registration).len();
+        bad_len[WORD_LEN..WORD_LEN * 2].copy_from_slice(&encode_word(len - 1));
+        assert_eq!(decode_deposit_log(&bad_len), None);
+    }
+}
@@ -0,0 +1,341 @@
+extern crate db;
+#[macro_use]
+extern crate serde_json;
+extern crate ssz;
+extern crate types;
+
+mod deposits;
+pub mod mock_server;
+mod rpc;
+
+pub use deposits::{decode_deposit_log, encode_deposit_log};
+pub use rpc::{DepositLog, JsonRpcClient, RpcError};
+
+use db::stores::{PoWChainStore, PoWChainStoreError};
+use db::{ClientDB, DBError};
+use std::sync::Arc;
+use types::{Address, PoWBlock};
+
+#[derive(Debug, PartialEq)]
+pub enum PoWChainFollowerError {
+    RpcError(RpcError),
+    MissingBlock(u64),
+    UnexpectedLogBlockHash,
+    DBError(String),
+}
+
+impl From<RpcError> for PoWChainFollowerError {
+    fn from(e: RpcError) -> Self {
+        PoWChainFollowerError::RpcError(e)
+    }
+}
+
+impl From<DBError> for PoWChainFollowerError {
+    fn from(e: DBError) -> Self {
+        PoWChainFollowerError::DBError(e.message)
+    }
+}
+
+impl From<PoWChainStoreError> for PoWChainFollowerError {
+    fn from(e: PoWChainStoreError) -> Self {
+        match e {
+            PoWChainStoreError::DBError(s) => PoWChainFollowerError::DBError(s),
+            PoWChainStoreError::DecodeError => PoWChainFollowerError::DBError(
+                "Unable to decode PoW chain data from database.".to_string(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoWChainFollowerConfig {
+    pub deposit_contract_address: Address,
+    /// The number of the first block to be followed. Deposits cannot have been made prior to the
+    /// deployment of the deposit contract.
+    pub deposit_contract_deployment_block: u64,
+    /// The number of blocks by which the follower trails the head of the PoW chain.
+    pub follow_distance: u64,
+}
+
+/// The changes made to the followed PoW chain by a call to `PoWChainFollower::update`.
+#[derive(Debug, Default, PartialEq)]
+pub struct PoWChainUpdate {
+    pub imported: u64,
+    pub reverted: u64,
+}
+
+/// Follows the PoW chain via the JSON-RPC API of a PoW chain node, storing each block and the
+/// deposits made to the deposit contract up to that block in a `PoWChainStore`.
+///
+/// Blocks are only imported once they are `follow_distance` blocks behind the head of the PoW
+/// chain, so reorgs shallower than the follow distance are never seen. Should a deeper reorg
+/// occur, the followed blocks which are no longer in the canonical chain are reverted.
+pub struct PoWChainFollower<T>
+where
+    T: ClientDB,
+{
+    client: JsonRpcClient,
+    store: Arc<PoWChainStore<T>>,
+    config: PoWChainFollowerConfig,
+}
+
+impl<T: ClientDB> PoWChainFollower<T> {
+    pub fn new(
+        client: JsonRpcClient,
+        store: Arc<PoWChainStore<T>>,
+        config: PoWChainFollowerConfig,
+    ) -> Self {
+        Self {
+            client,
+            store,
+            config,
+        }
+    }
+
+    /// Returns the latest followed block, if any.
+    pub fn head(&self) -> Result<Option<PoWBlock>, PoWChainFollowerError> {
+        match self.store.get_head()? {
+            None => Ok(None),
+            Some(hash) => Ok(self.store.get_block(&hash[..])?),
+        }
+    }
+
+    /// Import all blocks which are at least `follow_distance` blocks behind the head of the PoW
+    /// chain, reverting any followed blocks which have been reorganised out of the chain.
+    ///
+    /// Intended to be called periodically. An error leaves the store consistent, so the update
+    /// may simply be retried.
+    pub fn update(&self) -> Result<PoWChainUpdate, PoWChainFollowerError> {
+        let mut update = PoWChainUpdate::default();
+
+        let remote_head = self.client.block_number()?;
+        let target = match remote_head.checked_sub(self.config.follow_distance) {
+            Some(target) => target,
+            None => return Ok(update),
+        };
+
+        let mut head = self.head()?;
+        loop {
+            let number = match &head {
+                Some(head) => head.number + 1,
+                None => self.config.deposit_contract_deployment_block,
+            };
+            if number > target {
+                break;
+            }
+
+            let block = self
+                .client
+                .block_by_number(number)?
+                .ok_or(PoWChainFollowerError::MissingBlock(number))?;
+
+            /*
+             * If the new block does not build upon the head, the head is no longer in the
+             * canonical PoW chain. Revert it and try again from its parent.
+             */
+            if let Some(old_head) = head.clone() {
+                if block.parent_hash != old_head.hash {
+                    self.store.put_head(&old_head.parent_hash)?;
+                    self.store.delete_block(&old_head.hash[..])?;
+                    head = self.store.get_block(&old_head.parent_hash[..])?;
+                    update.reverted += 1;
+                    continue;
+                }
+            }
+
+            /*
+             * The deposits are stored cumulatively, so the deposits up to any followed block
+             * (i.e., any PoW receipt root) may be read directly.
+             */
+            let mut deposits = match &head {
+                Some(head) => self.store.get_deposits(&head.hash[..])?.unwrap_or_default(),
+                None => vec![],
+            };
+            for log in self
+                .client
+                .logs(&self.config.deposit_contract_address, number)?
+            {
+                if log.removed {
+                    continue;
+                }
+                /*
+                 * The PoW chain may have reorganised since the block was requested.
+                 */
+                if log.block_hash != block.hash {
+                    return Err(PoWChainFollowerError::UnexpectedLogBlockHash);
+                }
+                /*
+                 * The deposit contract only emits well-formed logs, so any others are ignored.
+                 */
+                if let Some(registration) = decode_deposit_log(&log.data) {
+                    deposits.push(registration);
+                }
+            }
+
+            self.store.put_block(&block)?;
+            self.store.put_deposits(&block.hash[..], &deposits)?;
+            self.store.put_head(&block.hash)?;
+            head = Some(block);
+            update.imported += 1;
+        }
+
+        Ok(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock_server::{MockPoWChain, MockServer};
+    use super::*;
+    use db::MemoryDB;
+    use std::time::Duration;
+    use types::{Hash256, ValidatorRegistration};
+
+    const FOLLOW_DISTANCE: u64 = 2;
+
+    fn test_follower(server: &MockServer) -> PoWChainFollower<MemoryDB> {
+        let db = Arc::new(MemoryDB::open());
+        let client = JsonRpcClient::new(server.addr(), Duration::from_secs(5));
+        let config = PoWChainFollowerConfig {
+            deposit_contract_address: server.chain.lock().unwrap().deposit_contract_address,
+            deposit_contract_deployment_block: 1,
+            follow_distance: FOLLOW_DISTANCE,
+        };
+        PoWChainFollower::new(client, Arc::new(PoWChainStore::new(db)), config)
+    }
+
+    /// Start a server for a chain of `len` blocks after genesis, each with one deposit.
+    fn test_server(len: usize) -> (MockServer, Vec<ValidatorRegistration>) {
+        let mut chain = MockPoWChain::new(Address::from("deposit_contract".as_bytes()));
+        let deposits: Vec<ValidatorRegistration> =
+            (0..len).map(|_| ValidatorRegistration::random()).collect();
+        for deposit in &deposits {
+            chain.push_block(vec![deposit.clone()]);
+        }
+        (MockServer::start(chain).unwrap(), deposits)
+    }
+
+    fn block_hash(server: &MockServer, number: usize) -> Hash256 {
+        server.chain.lock().unwrap().blocks[number].block.hash
+    }
+
+    #[test]
+    fn test_follower_imports_blocks() {
+        let (server, deposits) = test_server(6);
+        let follower = test_follower(&server);
+
+        let update = follower.update().unwrap();
+
+        /*
+         * Blocks 1 to 4 are imported, trailing the head (block 6) by the follow distance.
+         */
+        assert_eq!(
+            update,
+            PoWChainUpdate {
+                imported: 4,
+                reverted: 0,
+            }
+        );
+        assert_eq!(follower.head().unwrap().unwrap().number, 4);
+        assert!(!follower
+            .store
+            .block_hash_exists(&block_hash(&server, 0)[..])
+            .unwrap());
+        for number in 1..=4 {
+            let hash = block_hash(&server, number);
+            let block = follower.store.get_block(&hash[..]).unwrap().unwrap();
+            assert_eq!(block, server.chain.lock().unwrap().blocks[number].block);
+            assert_eq!(
+                follower.store.get_deposits(&hash[..]),
+                Ok(Some(deposits[..number].to_vec()))
+            );
+        }
+        assert!(!follower
+            .store
+            .block_hash_exists(&block_hash(&server, 5)[..])
+            .unwrap());
+
+        /*
+         * Nothing further is imported until the chain grows.
+         */
+        assert_eq!(follower.update(), Ok(PoWChainUpdate::default()));
+        server.chain.lock().unwrap().push_block(vec![]);
+        assert_eq!(follower.update().unwrap().imported, 1);
+        assert_eq!(follower.head().unwrap().unwrap().number, 5);
+    }
+
+    #[test]
+    fn test_follower_ignores_shallow_reorg() {
+        let (server, _) = test_server(6);
+        let follower = test_follower(&server);
+        follower.update().unwrap();
+        let head = follower.head().unwrap();
+
+        {
+            let mut chain = server.chain.lock().unwrap();
+            chain.rewind(FOLLOW_DISTANCE as usize);
+            chain.push_block(vec![]);
+            chain.push_block(vec![]);
+        }
+
+        assert_eq!(follower.update(), Ok(PoWChainUpdate::default()));
+        assert_eq!(follower.head().unwrap(), head);
+    }
+
+    #[test]
+    fn test_follower_reverts_deep_reorg() {
+        let (server, deposits) = test_server(6);
+        let follower = test_follower(&server);
+        follower.update().unwrap();
+        let reverted: Vec<Hash256> = (3..=4).map(|n| block_hash(&server, n)).collect();
+
+        /*
+         * Replace blocks 3 to 6 with blocks which do not contain any deposits, and extend the
+         * chain by one block.
+         */
+        {
+            let mut chain = server.chain.lock().unwrap();
+            chain.rewind(4);
+            for _ in 0..5 {
+                chain.push_block(vec![]);
+            }
+        }
+
+        let update = follower.update().unwrap();
+
+        assert_eq!(
+            update,
+            PoWChainUpdate {
+                imported: 3,
+                reverted: 2,
+            }
+        );
+        /*
+         * The reverted blocks are removed along with their deposits.
+         */
+        for hash in reverted {
+            assert!(!follower.store.block_hash_exists(&hash[..]).unwrap());
+            assert_eq!(follower.store.get_deposits(&hash[..]), Ok(None));
+        }
+        let head = follower.head().unwrap().unwrap();
+        assert_eq!(head.number, 5);
+        assert_eq!(head.hash, block_hash(&server, 5));
+        assert_eq!(
+            follower.store.get_deposits(&head.hash[..]),
+            Ok(Some(deposits[..2].to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_follower_short_chain() {
+        let (server, _) = test_server(1);
+        let follower = test_follower(&server);
+
+        /*
+         * The chain is shorter than the follow distance, so there is nothing to import.
+         */
+        assert_eq!(follower.update(), Ok(PoWChainUpdate::default()));
+        assert_eq!(follower.head(), Ok(None));
+        assert_eq!(follower.client.block_by_number(2), Ok(None));
+    }
+}
@@ -0,0 +1,234 @@
+use super::deposits::encode_deposit_log;
+use super::rpc::{
+    content_length, encode_data, encode_quantity, parse_data, parse_quantity, split_http_message,
+};
+use serde_json::Value;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use types::{Address, Hash256, PoWBlock, ValidatorRegistration};
+
+/// The number of seconds between the blocks of a `MockPoWChain`.
+pub const MOCK_BLOCK_INTERVAL: u64 = 15;
+
+/// A block of a `MockPoWChain`, along with the deposits made in it.
+#[derive(Debug, Clone)]
+pub struct MockBlock {
+    pub block: PoWBlock,
+    pub deposits: Vec<ValidatorRegistration>,
+}
+
+/// An in-memory PoW chain, where the block at each index has the index as its number.
+#[derive(Debug, Clone)]
+pub struct MockPoWChain {
+    pub deposit_contract_address: Address,
+    pub blocks: Vec<MockBlock>,
+}
+
+impl MockPoWChain {
+    /// Create a new chain containing only a genesis block.
+    pub fn new(deposit_contract_address: Address) -> Self {
+        let genesis = MockBlock {
+            block: PoWBlock {
+                hash: Hash256::random(),
+                ..PoWBlock::zero()
+            },
+            deposits: vec![],
+        };
+        Self {
+            deposit_contract_address,
+            blocks: vec![genesis],
+        }
+    }
+
+    /// Append a new block with the given `deposits` to the chain, returning the new block.
+    pub fn push_block(&mut self, deposits: Vec<ValidatorRegistration>) -> PoWBlock {
+        let parent = self.head().clone();
+        let block = PoWBlock {
+            hash: Hash256::random(),
+            parent_hash: parent.hash,
+            number: parent.number + 1,
+            timestamp: parent.timestamp + MOCK_BLOCK_INTERVAL,
+        };
+        self.blocks.push(MockBlock {
+            block: block.clone(),
+            deposits,
+        });
+        block
+    }
+
+    /// Remove the latest `depth` blocks from the chain, so that new blocks may be added to the
+    /// chain in their place. The genesis block is never removed.
+    pub fn rewind(&mut self, depth: usize) {
+        let len = self.blocks.len().saturating_sub(depth).max(1);
+        self.blocks.truncate(len);
+    }
+
+    pub fn head(&self) -> &PoWBlock {
+        &self.blocks[self.blocks.len() - 1].block
+    }
+
+    /// Returns the `result` of a JSON-RPC `method`, or an error message.
+    fn respond(&self, method: &str, params: &Value) -> Result<Value, String> {
+        match method {
+            "eth_blockNumber" => Ok(json!(encode_quantity(self.head().number))),
+            "eth_getBlockByNumber" => {
+                let number = parse_quantity(&params[0]).ok_or("Invalid block number")?;
+                match self.blocks.get(number as usize) {
+                    Some(b) => Ok(json!({
+                        "hash": encode_data(&b.block.hash[..]),
+                        "parentHash": encode_data(&b.block.parent_hash[..]),
+                        "number": encode_quantity(b.block.number),
+                        "timestamp": encode_quantity(b.block.timestamp),
+                    })),
+                    None => Ok(Value::Null),
+                }
+            }
+            "eth_getLogs" => {
+                let filter = &params[0];
+                let from = parse_quantity(&filter["fromBlock"]).ok_or("Invalid fromBlock")?;
+                let to = parse_quantity(&filter["toBlock"]).ok_or("Invalid toBlock")?;
+                let address = parse_data(&filter["address"]).ok_or("Invalid address")?;
+                if address[..] != self.deposit_contract_address[..] {
+                    return Ok(json!([]));
+                }
+
+                let mut logs = vec![];
+                for b in self
+                    .blocks
+                    .iter()
+                    .filter(|b| b.block.number >= from && b.block.number <= to)
+                {
+                    for deposit in &b.deposits {
+                        logs.push(json!({
+                            "address": encode_data(&self.deposit_contract_address[..]),
+                            "blockHash": encode_data(&b.block.hash[..]),
+                            "blockNumber": encode_quantity(b.block.number),
+                            "data": encode_data(&encode_deposit_log(deposit)),
+                            "removed": false,
+                        }));
+                    }
+                }
+                Ok(Value::Array(logs))
+            }
+            _ => Err("Method not found".to_string()),
+        }
+    }
+}
+
+/// Serves a `MockPoWChain` over the HTTP JSON-RPC API of a PoW chain node, on a local port.
+///
+/// The chain may be modified while the server is running. The server is stopped when dropped.
+pub struct MockServer {
+    pub chain: Arc<Mutex<MockPoWChain>>,
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start(chain: MockPoWChain) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let chain = Arc::new(Mutex::new(chain));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let chain = chain.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    /*
+                     * A failed request only affects its client, so it is ignored.
+                     */
+                    if let Ok(stream) = stream {
+                        let _ = handle_connection(stream, &chain);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            chain,
+            addr,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        /*
+         * Wake the server thread, which is blocked waiting for a connection.
+         */
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Read a single HTTP request from the `stream` and write the JSON-RPC response to it.
+fn handle_connection(mut stream: TcpStream, chain: &Mutex<MockPoWChain>) -> io::Result<()> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+    let body = loop {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+
+        let message = String::from_utf8_lossy(&request).into_owned();
+        if let Some((head, body)) = split_http_message(&message) {
+            let len = content_length(head).unwrap_or(0);
+            if body.len() >= len {
+                break body[..len].to_string();
+            }
+        }
+    };
+
+    let response = match serde_json::from_str::<Value>(&body) {
+        Ok(request) => {
+            let method = request["method"].as_str().unwrap_or("");
+            /*
+             * Panic if the chain lock is poisoned.
+             */
+            let chain = chain.lock().unwrap();
+            match chain.respond(method, &request["params"]) {
+                Ok(result) => json!({"jsonrpc": "2.0", "id": request["id"], "result": result}),
+                Err(message) => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32601, "message": message},
+                }),
+            }
+        }
+        Err(_) => json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": {"code": -32700, "message": "Parse error"},
+        }),
+    }.to_string();
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        response.len(),
+        response
+    )
+}
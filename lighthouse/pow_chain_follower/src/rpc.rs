@@ -0,0 +1,323 @@
+use serde_json::Value;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::str;
+use std::time::Duration;
+use types::{Address, Hash256, PoWBlock};
+
+#[derive(Debug, PartialEq)]
+pub enum RpcError {
+    IoError(String),
+    InvalidHttpResponse,
+    HttpStatus(u16),
+    InvalidJson,
+    InvalidResult,
+    ServerError(String),
+}
+
+impl From<io::Error> for RpcError {
+    fn from(e: io::Error) -> Self {
+        RpcError::IoError(e.to_string())
+    }
+}
+
+/// A log emitted by the deposit contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepositLog {
+    pub block_hash: Hash256,
+    pub block_number: u64,
+    pub data: Vec<u8>,
+    pub removed: bool,
+}
+
+/// A minimal client for the JSON-RPC API of a PoW chain node, served over HTTP.
+///
+/// A new connection is made for each request. The response body may be delimited by
+/// `Content-Length`, the chunked transfer coding or the closing of the connection.
+pub struct JsonRpcClient {
+    addr: SocketAddr,
+    timeout: Duration,
+}
+
+impl JsonRpcClient {
+    pub fn new(addr: SocketAddr, timeout: Duration) -> Self {
+        Self { addr, timeout }
+    }
+
+    /// Returns the number of the latest block known to the node.
+    pub fn block_number(&self) -> Result<u64, RpcError> {
+        let result = self.call("eth_blockNumber", json!([]))?;
+        parse_quantity(&result).ok_or(RpcError::InvalidResult)
+    }
+
+    /// Returns the block with the given `number` from the canonical chain of the node, if any.
+    pub fn block_by_number(&self, number: u64) -> Result<Option<PoWBlock>, RpcError> {
+        let result = self.call(
+            "eth_getBlockByNumber",
+            json!([encode_quantity(number), false]),
+        )?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        let block = PoWBlock {
+            hash: parse_hash(&result["hash"]).ok_or(RpcError::InvalidResult)?,
+            parent_hash: parse_hash(&result["parentHash"]).ok_or(RpcError::InvalidResult)?,
+            number: parse_quantity(&result["number"]).ok_or(RpcError::InvalidResult)?,
+            timestamp: parse_quantity(&result["timestamp"]).ok_or(RpcError::InvalidResult)?,
+        };
+        Ok(Some(block))
+    }
+
+    /// Returns all logs emitted by the contract at `address` in the block with the given `number`.
+    pub fn logs(&self, address: &Address, number: u64) -> Result<Vec<DepositLog>, RpcError> {
+        let filter = json!({
+            "fromBlock": encode_quantity(number),
+            "toBlock": encode_quantity(number),
+            "address": encode_data(&address[..]),
+        });
+        let result = self.call("eth_getLogs", json!([filter]))?;
+        let logs = result.as_array().ok_or(RpcError::InvalidResult)?;
+
+        logs.iter()
+            .map(|log| {
+                Ok(DepositLog {
+                    block_hash: parse_hash(&log["blockHash"]).ok_or(RpcError::InvalidResult)?,
+                    block_number: parse_quantity(&log["blockNumber"])
+                        .ok_or(RpcError::InvalidResult)?,
+                    data: parse_data(&log["data"]).ok_or(RpcError::InvalidResult)?,
+                    removed: log["removed"].as_bool().unwrap_or(false),
+                })
+            }).collect()
+    }
+
+    /// Call the JSON-RPC `method` with the given `params`, returning the `result` of the response.
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let body = self.post(&request.to_string())?;
+        let response: Value = serde_json::from_str(&body).map_err(|_| RpcError::InvalidJson)?;
+
+        if let Some(error) = response.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(RpcError::ServerError(message.to_string()));
+        }
+        response.get("result").cloned().ok_or(RpcError::InvalidJson)
+    }
+
+    /// Make a HTTP POST request with the given JSON `body`, returning the body of the response.
+    fn post(&self, body: &str) -> Result<String, RpcError> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.addr,
+            body.len(),
+            body
+        )?;
+
+        /*
+         * The connection is closed by the server once the response is sent.
+         */
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+
+        /*
+         * The body is handled as bytes, as a chunk boundary may fall within a UTF-8 character.
+         */
+        let head_len = response
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or(RpcError::InvalidHttpResponse)?;
+        let head =
+            str::from_utf8(&response[..head_len]).map_err(|_| RpcError::InvalidHttpResponse)?;
+        let body = &response[head_len + 4..];
+        let status = head
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or(RpcError::InvalidHttpResponse)?;
+        if status != 200 {
+            return Err(RpcError::HttpStatus(status));
+        }
+
+        /*
+         * A chunked body takes precedence over any `Content-Length`, as per RFC 7230.
+         */
+        let body = if is_chunked(head) {
+            decode_chunked(body).ok_or(RpcError::InvalidHttpResponse)?
+        } else {
+            match content_length(head) {
+                Some(len) => body
+                    .get(..len)
+                    .ok_or(RpcError::InvalidHttpResponse)?
+                    .to_vec(),
+                None => body.to_vec(),
+            }
+        };
+        String::from_utf8(body).map_err(|_| RpcError::InvalidHttpResponse)
+    }
+}
+
+/// Split a HTTP message into its head (the start line and headers) and its body.
+pub(crate) fn split_http_message(message: &str) -> Option<(&str, &str)> {
+    message
+        .find("\r\n\r\n")
+        .map(|i| (&message[..i], &message[i + 4..]))
+}
+
+/// Returns the value of the header with the given `name` in the `head` of a HTTP message, if any.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            if parts.next()?.trim().eq_ignore_ascii_case(name) {
+                parts.next().map(|value| value.trim())
+            } else {
+                None
+            }
+        }).next()
+}
+
+/// Returns the value of the `Content-Length` header in the `head` of a HTTP message, if any.
+pub(crate) fn content_length(head: &str) -> Option<usize> {
+    header(head, "content-length")?.parse().ok()
+}
+
+/// Returns true if the `head` of a HTTP message declares a chunked `Transfer-Encoding`.
+pub(crate) fn is_chunked(head: &str) -> bool {
+    header(head, "transfer-encoding").map_or(false, |encoding| {
+        encoding
+            .split(',')
+            .any(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+    })
+}
+
+/// Decode a HTTP message body sent with the chunked transfer coding.
+///
+/// Chunk extensions and trailers are ignored. Returns `None` if the body is malformed or
+/// incomplete.
+pub(crate) fn decode_chunked(body: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = body;
+    let mut decoded = vec![];
+    loop {
+        let line_end = rest.windows(2).position(|w| w == b"\r\n")?;
+        let size_line = str::from_utf8(&rest[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_line.split(';').next()?.trim(), 16).ok()?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            break;
+        }
+        if rest.len() < size || !rest[size..].starts_with(b"\r\n") {
+            return None;
+        }
+        decoded.extend_from_slice(&rest[..size]);
+        rest = &rest[size + 2..];
+    }
+    Some(decoded)
+}
+
+/// Encode an integer as a hex "quantity" (e.g., `0x2a`).
+pub(crate) fn encode_quantity(n: u64) -> String {
+    format!("0x{:x}", n)
+}
+
+/// Encode some bytes as hex "data" (e.g., `0x002a`).
+pub(crate) fn encode_data(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("0x{}", hex)
+}
+
+pub(crate) fn parse_quantity(value: &Value) -> Option<u64> {
+    let s = value.as_str()?;
+    if !s.starts_with("0x") {
+        return None;
+    }
+    u64::from_str_radix(&s[2..], 16).ok()
+}
+
+pub(crate) fn parse_data(value: &Value) -> Option<Vec<u8>> {
+    let s = value.as_str()?;
+    if !s.starts_with("0x") || s.len() % 2 != 0 {
+        return None;
+    }
+    (2..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_hash(value: &Value) -> Option<Hash256> {
+    let bytes = parse_data(value)?;
+    if bytes.len() != 32 {
+        return None;
+    }
+    Some(Hash256::from(&bytes[..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantity_encode_parse() {
+        assert_eq!(encode_quantity(0), "0x0");
+        assert_eq!(encode_quantity(42), "0x2a");
+        assert_eq!(parse_quantity(&json!("0x2a")), Some(42));
+        assert_eq!(parse_quantity(&json!("2a")), None);
+        assert_eq!(parse_quantity(&json!(42)), None);
+    }
+
+    #[test]
+    fn test_data_encode_parse() {
+        let bytes = vec![0, 42, 255];
+        assert_eq!(encode_data(&bytes), "0x002aff");
+        assert_eq!(parse_data(&json!("0x002aff")), Some(bytes));
+        assert_eq!(parse_data(&json!("0x")), Some(vec![]));
+        assert_eq!(parse_data(&json!("0x2aff0")), None);
+        assert_eq!(parse_data(&json!("0xzz")), None);
+    }
+
+    #[test]
+    fn test_split_http_message() {
+        let message = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        let (head, body) = split_http_message(message).unwrap();
+
+        assert_eq!(head, "HTTP/1.1 200 OK\r\nContent-Length: 2");
+        assert_eq!(body, "{}");
+        assert_eq!(content_length(head), Some(2));
+        assert_eq!(content_length("HTTP/1.1 200 OK"), None);
+        assert!(!is_chunked(head));
+    }
+
+    #[test]
+    fn test_decode_chunked() {
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, Chunked";
+        assert!(is_chunked(head));
+
+        /*
+         * The second chunk splits a multi-byte character and carries an extension.
+         */
+        let body = b"4\r\n{\"a\"\r\n3;ext=1\r\n:\"\xc3\r\n3\r\n\xa9\"}\r\n0\r\nTrailer: x\r\n\r\n";
+        let decoded = decode_chunked(body).unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), "{\"a\":\"\u{e9}\"}");
+
+        /*
+         * The terminating zero-length chunk is missing, or a chunk is shorter than its size.
+         */
+        assert_eq!(decode_chunked(b"2\r\n{}\r\n"), None);
+        assert_eq!(decode_chunked(b"3\r\n{}\r\n0\r\n\r\n"), None);
+        assert_eq!(decode_chunked(b"z\r\n{}\r\n0\r\n\r\n"), None);
+    }
+}
+}